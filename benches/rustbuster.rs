@@ -64,13 +64,6 @@ fn fuzzbuster_url_single() -> FuzzBuster {
         http_headers: vec![("Header".to_owned(), "Value".to_owned())],
         wordlist_paths: vec!["./examples/wordlist_short".to_owned()],
         url: "http://localhost/FUZZ".to_owned(),
-        include_status_codes: vec![],
-        ignore_status_codes: vec!["404".to_owned()],
-        include_body: vec![],
-        ignore_body: vec![],
-        no_progress_bar: true,
-        exit_on_connection_errors: false,
-        output: "".to_owned(),
         csrf_url: None,
         csrf_regex: None,
         csrf_headers: None,
@@ -90,13 +83,6 @@ fn fuzzbuster_url_multiple() -> FuzzBuster {
             "./examples/wordlist_short".to_owned(),
         ],
         url: "http://localhost/FUZZ/FUZZ".to_owned(),
-        include_status_codes: vec![],
-        ignore_status_codes: vec!["404".to_owned()],
-        include_body: vec![],
-        ignore_body: vec![],
-        no_progress_bar: true,
-        exit_on_connection_errors: false,
-        output: "".to_owned(),
         csrf_url: None,
         csrf_regex: None,
         csrf_headers: None,
@@ -113,13 +99,6 @@ fn fuzzbuster_header_single() -> FuzzBuster {
         http_headers: vec![("Header".to_owned(), "FUZZ".to_owned())],
         wordlist_paths: vec!["./examples/wordlist_short".to_owned()],
         url: "http://localhost/".to_owned(),
-        include_status_codes: vec![],
-        ignore_status_codes: vec!["404".to_owned()],
-        include_body: vec![],
-        ignore_body: vec![],
-        no_progress_bar: true,
-        exit_on_connection_errors: false,
-        output: "".to_owned(),
         csrf_url: None,
         csrf_regex: None,
         csrf_headers: None,
@@ -139,13 +118,6 @@ fn fuzzbuster_header_multiple() -> FuzzBuster {
             "./examples/wordlist_short".to_owned(),
         ],
         url: "http://localhost/".to_owned(),
-        include_status_codes: vec![],
-        ignore_status_codes: vec!["404".to_owned()],
-        include_body: vec![],
-        ignore_body: vec![],
-        no_progress_bar: true,
-        exit_on_connection_errors: false,
-        output: "".to_owned(),
         csrf_url: None,
         csrf_regex: None,
         csrf_headers: None,
@@ -162,13 +134,6 @@ fn fuzzbuster_body_single() -> FuzzBuster {
         http_headers: vec![("Header".to_owned(), "Value".to_owned())],
         wordlist_paths: vec!["./examples/wordlist_short".to_owned()],
         url: "http://localhost/".to_owned(),
-        include_status_codes: vec![],
-        ignore_status_codes: vec!["404".to_owned()],
-        include_body: vec![],
-        ignore_body: vec![],
-        no_progress_bar: true,
-        exit_on_connection_errors: false,
-        output: "".to_owned(),
         csrf_url: None,
         csrf_regex: None,
         csrf_headers: None,
@@ -188,13 +153,6 @@ fn fuzzbuster_body_multiple() -> FuzzBuster {
             "./examples/wordlist_short".to_owned(),
         ],
         url: "http://localhost/".to_owned(),
-        include_status_codes: vec![],
-        ignore_status_codes: vec!["404".to_owned()],
-        include_body: vec![],
-        ignore_body: vec![],
-        no_progress_bar: true,
-        exit_on_connection_errors: false,
-        output: "".to_owned(),
         csrf_url: None,
         csrf_regex: None,
         csrf_headers: None,
@@ -221,22 +179,22 @@ fn criterion_benchmark(c: &mut Criterion) {
         })
     });
     c.bench_function("build_requests_url_single", |b| {
-        b.iter(|| fuzzbuster_url_single().build_requests())
+        b.iter(|| fuzzbuster_url_single().build_requests().unwrap())
     });
     c.bench_function("build_requests_url_multiple", |b| {
-        b.iter(|| fuzzbuster_url_multiple().build_requests())
+        b.iter(|| fuzzbuster_url_multiple().build_requests().unwrap())
     });
     c.bench_function("build_requests_header_single", |b| {
-        b.iter(|| fuzzbuster_header_single().build_requests())
+        b.iter(|| fuzzbuster_header_single().build_requests().unwrap())
     });
     c.bench_function("build_requests_header_multiple", |b| {
-        b.iter(|| fuzzbuster_header_multiple().build_requests())
+        b.iter(|| fuzzbuster_header_multiple().build_requests().unwrap())
     });
     c.bench_function("build_requests_body_single", |b| {
-        b.iter(|| fuzzbuster_body_single().build_requests())
+        b.iter(|| fuzzbuster_body_single().build_requests().unwrap())
     });
     c.bench_function("build_requests_body_multiple", |b| {
-        b.iter(|| fuzzbuster_body_multiple().build_requests())
+        b.iter(|| fuzzbuster_body_multiple().build_requests().unwrap())
     });
 }
 
@@ -1,4 +1,15 @@
 use clap::{App, Arg};
+use librustbuster::{
+    loginbuster::LoginType,
+    matcher::{expr::Expression, StatusMatcher},
+    session::{
+        auth::{AuthType, Credentials},
+        cookie::read_cookies,
+        macros::{encode_placeholders, Macro},
+        JarScope, RequestTemplate, SessionConfig,
+    },
+};
+use std::io::Read;
 use terminal_size::{terminal_size, Height, Width};
 
 pub struct CommonArgs {
@@ -9,12 +20,46 @@ pub struct CommonArgs {
     pub output: String,
 }
 
+pub struct TargetsArgs {
+    pub targets: Vec<String>,
+    pub max_per_host: usize,
+}
+
 pub struct WordlistArgs {
     pub wordlist_paths: Vec<String>,
 }
 
 pub struct DNSArgs {
     pub domain: String,
+    pub recursive_depth: usize,
+    pub permutations: bool,
+    pub permutation_wordlist: Option<String>,
+    pub reverse: bool,
+    pub takeover: bool,
+    pub takeover_http: bool,
+    pub takeover_fingerprints: Option<String>,
+    pub takeover_timeout: u64,
+    pub user_agent: String,
+    pub zone_walk: bool,
+    pub zone_walk_queries: usize,
+    pub nsec3_hashes: Option<String>,
+}
+
+pub struct ResolverArgs {
+    pub resolvers: Option<String>,
+    pub resolver_qps: u32,
+    pub resolver_timeout: u64,
+    pub resolver_retries: usize,
+    pub control_domain: String,
+}
+
+pub struct PtrArgs {
+    pub ranges: Vec<String>,
+    pub input: Option<String>,
+}
+
+pub struct SrvArgs {
+    pub services: Option<String>,
 }
 
 pub struct HTTPArgs {
@@ -24,8 +69,8 @@ pub struct HTTPArgs {
     pub url: String,
     pub ignore_certificate: bool,
     pub http_headers: Vec<(String, String)>,
-    pub include_status_codes: Vec<String>,
-    pub ignore_status_codes: Vec<String>,
+    pub include_status_codes: StatusMatcher,
+    pub ignore_status_codes: StatusMatcher,
 }
 
 pub struct BodyArgs {
@@ -33,19 +78,69 @@ pub struct BodyArgs {
     pub ignore_strings: Vec<String>,
 }
 
+pub struct FilterArgs {
+    pub matches: Vec<Expression>,
+    pub filters: Vec<Expression>,
+}
+
+pub struct SessionArgs {
+    pub session: Option<SessionConfig>,
+}
+
+pub struct AuthArgs {
+    pub auth: Option<Credentials>,
+}
+
 pub struct DirArgs {
     pub append_slash: bool,
     pub extensions: Vec<String>,
+    pub fingerprint: bool,
+    pub signatures: Option<String>,
 }
 
 pub struct TildeArgs {
     pub extension: Option<String>,
 }
 
+pub struct VhostArgs {
+    pub similarity_threshold: f64,
+    pub sni: bool,
+    pub harvest: bool,
+}
+
+pub struct ProbeArgs {
+    pub hosts: Vec<String>,
+    pub ports: Vec<u16>,
+    pub user_agent: String,
+    pub http_headers: Vec<(String, String)>,
+    pub timeout: u64,
+    pub alive_output: Option<String>,
+}
+
+pub struct CsrfArgs {
+    pub csrf_url: Option<String>,
+    pub csrf_regex: Option<String>,
+    pub csrf_headers: Option<Vec<(String, String)>>,
+}
+
 pub struct FuzzArgs {
     pub csrf_url: Option<String>,
     pub csrf_regex: Option<String>,
     pub csrf_headers: Option<Vec<(String, String)>>,
+    pub csrf_reuse: usize,
+    pub csrf_invalid: Option<Expression>,
+    pub pre_request: Option<Macro>,
+}
+
+pub struct LoginArgs {
+    pub usernames: Vec<String>,
+    pub passwords: Vec<String>,
+    pub combos: Vec<String>,
+    pub login_type: LoginType,
+    pub success: Option<Expression>,
+    pub lockout: Option<Expression>,
+    pub lockout_pause: u64,
+    pub similarity_threshold: f64,
 }
 
 pub fn set_common_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
@@ -89,23 +184,37 @@ pub fn set_common_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
             .long("no-progress-bar")
             .help("Disables the progress bar"),
     )
+    .arg(
+        Arg::with_name("config")
+            .long("config")
+            .help("Loads the options from the specified TOML or YAML configuration file")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("profile")
+            .long("profile")
+            .help("Loads the options from the specified profile")
+            .takes_value(true),
+    )
 }
 
 pub fn set_http_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.arg(
         Arg::with_name("include-status-codes")
             .long("include-status-codes")
-            .help("Sets the list of status codes to include")
+            .help("Sets the status codes to include, e.g. 200, 2xx, 300-399 or !302")
             .short("s")
             .default_value("")
+            .multiple(true)
             .use_delimiter(true),
     )
     .arg(
         Arg::with_name("ignore-status-codes")
             .long("ignore-status-codes")
-            .help("Sets the list of status codes to ignore")
+            .help("Sets the status codes to ignore, e.g. 404, 5xx, 400-499 or !401")
             .short("S")
             .default_value("404")
+            .multiple(true)
             .use_delimiter(true),
     )
     .arg(
@@ -147,6 +256,14 @@ pub fn set_http_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
             .multiple(true)
             .takes_value(true),
     )
+    .arg(
+        Arg::with_name("cookie")
+            .long("cookie")
+            .help("Sends the specified cookie, e.g. \"session=1234\"")
+            .short("c")
+            .multiple(true)
+            .takes_value(true),
+    )
     .arg(
         Arg::with_name("url")
             .long("url")
@@ -154,7 +271,7 @@ pub fn set_http_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
             .help("Sets the target URL")
             .short("u")
             .takes_value(true)
-            .required(true),
+            .required_unless("targets"),
     )
 }
 
@@ -170,7 +287,7 @@ pub fn set_body_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     .arg(
         Arg::with_name("include-string")
             .long("include-string")
-            .help("Includes results with specified string in the HTTP body")
+            .help("Includes results with any of the specified strings in the HTTP body")
             .short("i")
             .multiple(true)
             .conflicts_with("ignore-string")
@@ -178,6 +295,94 @@ pub fn set_body_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     )
 }
 
+pub fn set_filter_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("match")
+            .long("match")
+            .help("Keeps results matching any of the expressions, e.g. \"status in 200..299 && body ~ /admin/i\"")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("filter")
+            .long("filter")
+            .help("Drops results matching the expression, e.g. \"size == 1234 || time > 2s\"")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true),
+    )
+}
+
+pub fn set_auth_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("auth")
+            .long("auth")
+            .help("Authenticates with the specified user:password, DOMAIN\\user:password for NTLM, or token for bearer")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("auth-type")
+            .long("auth-type")
+            .help("Uses the specified authentication scheme [default: basic]")
+            .possible_values(&["basic", "digest", "ntlm", "bearer"])
+            .requires("auth")
+            .takes_value(true),
+    )
+}
+
+pub fn set_session_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("cookie-jar")
+            .long("cookie-jar")
+            .help("Stores and replays the cookies set by the server, in one jar shared by all the workers or one per worker")
+            .possible_values(&["global", "worker"])
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("cookie-file")
+            .long("cookie-file")
+            .help("Seeds the cookie jar from the specified Netscape cookies.txt or name=value file")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("login-url")
+            .long("login-url")
+            .help("Logs in via the specified URL before the first request of each session")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("login-method")
+            .long("login-method")
+            .help("Uses the specified HTTP method to log in [default: POST]")
+            .requires("login-url")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("login-body")
+            .long("login-body")
+            .help("Uses the specified HTTP body to log in")
+            .requires("login-url")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("login-header")
+            .long("login-header")
+            .help("Appends the specified HTTP header to the login request")
+            .requires("login-url")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("session-expired")
+            .long("session-expired")
+            .help("Logs in again and retries the request when its response matches the expression, e.g. \"status == 302 && header.location ~ /login/\"")
+            .requires("login-url")
+            .takes_value(true),
+    )
+}
+
 pub fn set_dir_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.arg(
         Arg::with_name("extensions")
@@ -185,6 +390,7 @@ pub fn set_dir_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
             .help("Sets the extensions")
             .short("e")
             .default_value("")
+            .multiple(true)
             .use_delimiter(true),
     )
     .arg(
@@ -193,6 +399,18 @@ pub fn set_dir_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
             .help("Tries to also append / to the base request")
             .short("f"),
     )
+    .arg(
+        Arg::with_name("fingerprint")
+            .long("fingerprint")
+            .help("Fingerprints the targets and adds the extensions and wordlists of the detected technologies"),
+    )
+    .arg(
+        Arg::with_name("signatures")
+            .long("signatures")
+            .help("Adds the technology signatures in the specified JSON file to the bundled ones")
+            .requires("fingerprint")
+            .takes_value(true),
+    )
 }
 
 pub fn set_wordlist_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
@@ -208,6 +426,21 @@ pub fn set_wordlist_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     )
 }
 
+pub fn set_targets_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("targets")
+            .long("targets")
+            .help("Reads the targets from the specified file, one per line, - for stdin")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("max-per-host")
+            .long("max-per-host")
+            .help("Caps the amount of concurrent requests to a single host")
+            .takes_value(true),
+    )
+}
+
 pub fn set_tilde_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.arg(
         Arg::with_name("extension")
@@ -219,6 +452,150 @@ pub fn set_tilde_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
 }
 
 pub fn set_dns_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("domain")
+            .long("domain")
+            .help("Uses the specified domain")
+            .short("d")
+            .required_unless("targets")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("recursive-depth")
+            .long("recursive-depth")
+            .help("Bruteforces under each discovered subdomain up to the specified depth")
+            .short("r")
+            .default_value("0")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("permutations")
+            .long("permutations")
+            .help("Resolves permutations of the discovered subdomains in a second round")
+            .short("P"),
+    )
+    .arg(
+        Arg::with_name("permutation-wordlist")
+            .long("permutation-wordlist")
+            .help("Sets the wordlist used to generate permutations")
+            .requires("permutations")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("reverse")
+            .long("reverse")
+            .help("Reports the hostnames found via reverse lookups of the discovered addresses"),
+    )
+    .arg(
+        Arg::with_name("takeover")
+            .long("takeover")
+            .help("Flags CNAMEs to unclaimed third-party resources as possible takeovers"),
+    )
+    .arg(
+        Arg::with_name("takeover-http")
+            .long("takeover-http")
+            .help("Compares the pages behind the CNAMEs against the takeover fingerprints")
+            .requires("takeover"),
+    )
+    .arg(
+        Arg::with_name("takeover-fingerprints")
+            .long("takeover-fingerprints")
+            .help("Adds the fingerprints in the specified JSON file to the bundled ones")
+            .requires("takeover")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("takeover-timeout")
+            .long("takeover-timeout")
+            .help("Sets the timeout in milliseconds of each takeover fingerprint request")
+            .default_value("10000")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("user-agent")
+            .long("user-agent")
+            .help("Uses the specified User-Agent for the takeover fingerprint requests")
+            .short("a")
+            .default_value("rustbuster")
+            .takes_value(true),
+    )
+    .arg(Arg::with_name("zone-walk").long("zone-walk").help(
+        "Enumerates DNSSEC-signed zones through their NSEC/NSEC3 records before bruteforcing",
+    ))
+    .arg(
+        Arg::with_name("zone-walk-queries")
+            .long("zone-walk-queries")
+            .help("Sets the maximum amount of queries used to walk the zone")
+            .default_value("10000")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("nsec3-hashes")
+            .long("nsec3-hashes")
+            .help("Saves the collected NSEC3 hashes in the specified file for offline cracking")
+            .requires("zone-walk")
+            .takes_value(true),
+    )
+}
+
+pub fn set_resolver_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("resolvers")
+            .long("resolvers")
+            .help("Spreads the queries across the resolvers in the specified file, one IP, https:// or tls:// URL per line")
+            .short("R")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("resolver-qps")
+            .long("resolver-qps")
+            .help("Sets the maximum amount of queries per second for each resolver, 0 is unlimited")
+            .default_value("50")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("resolver-timeout")
+            .long("resolver-timeout")
+            .help("Sets the timeout in milliseconds of each query")
+            .default_value("2000")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("resolver-retries")
+            .long("resolver-retries")
+            .help("Sets the amount of times a failed query is retried on another resolver")
+            .default_value("2")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("control-domain")
+            .long("control-domain")
+            .help("Uses the specified domain to health-check the resolvers")
+            .default_value("example.com")
+            .takes_value(true),
+    )
+}
+
+pub fn set_ptr_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("range")
+            .long("range")
+            .help("Sets the CIDR ranges to reverse lookup")
+            .multiple(true)
+            .use_delimiter(true)
+            .required_unless("input")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("input")
+            .long("input")
+            .help("Reverse lookups the addresses found in the specified dns results file")
+            .short("i")
+            .takes_value(true),
+    )
+}
+
+pub fn set_srv_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.arg(
         Arg::with_name("domain")
             .long("domain")
@@ -227,6 +604,12 @@ pub fn set_dns_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
             .required(true)
             .takes_value(true),
     )
+    .arg(
+        Arg::with_name("services")
+            .long("services")
+            .help("Queries the services in the specified file instead of the bundled ones")
+            .takes_value(true),
+    )
 }
 
 pub fn set_vhost_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
@@ -235,21 +618,78 @@ pub fn set_vhost_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
             .long("domain")
             .help("Uses the specified domain to bruteforce")
             .short("d")
-            .required(true)
+            .required_unless("targets")
             .takes_value(true),
     )
     .arg(
-        Arg::with_name("ignore-string")
-            .long("ignore-string")
-            .help("Ignores results with specified string in the HTTP body")
-            .short("x")
-            .required(true)
+        Arg::with_name("similarity-threshold")
+            .long("similarity-threshold")
+            .help("Ignores vhosts whose response is at least this similar to the one of a bogus vhost")
+            .default_value("0.9")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("sni")
+            .long("sni")
+            .help("Sends the candidate vhost as TLS SNI and records the certificate it gets back, without verifying its hostname"),
+    )
+    .arg(
+        Arg::with_name("harvest")
+            .long("harvest")
+            .help("Seeds vhosts from the target certificate, redirects, CSP headers and links"),
+    )
+}
+
+pub fn set_probe_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("hosts")
+            .long("hosts")
+            .help("Probes the specified hosts, IPs or CIDR ranges")
             .multiple(true)
+            .use_delimiter(true)
+            .required_unless("targets")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("ports")
+            .long("ports")
+            .help("Sets the ports to probe, ranges like 8000-8100 are allowed")
+            .short("p")
+            .default_value("80,443,8000,8080,8443")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("user-agent")
+            .long("user-agent")
+            .help("Uses the specified User-Agent")
+            .short("a")
+            .default_value("rustbuster")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("http-header")
+            .long("http-header")
+            .help("Appends the specified HTTP header")
+            .short("H")
+            .multiple(true)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("timeout")
+            .long("timeout")
+            .help("Sets the timeout in milliseconds of each probe")
+            .default_value("5000")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("alive-output")
+            .long("alive-output")
+            .help("Saves the alive services in the specified file, one URL per line, ready for --targets")
             .takes_value(true),
     )
 }
 
-pub fn set_fuzz_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+pub fn set_csrf_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.arg(
         Arg::with_name("csrf-url")
             .long("csrf-url")
@@ -274,6 +714,92 @@ pub fn set_fuzz_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     )
 }
 
+pub fn set_fuzz_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("csrf-reuse")
+            .long("csrf-reuse")
+            .help("Reuses each CSRF token for the specified amount of requests of the same session, 0 reuses it until --csrf-invalid matches [default: 1]")
+            .requires("csrf-url")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("csrf-invalid")
+            .long("csrf-invalid")
+            .help("Fetches a new CSRF token and retries when a response matches the expression, e.g. \"status == 403\"")
+            .requires("csrf-url")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("macro")
+            .long("macro")
+            .help("Runs the requests in the specified TOML or YAML file before each attempt, their extracted values replace {{name}}")
+            .takes_value(true),
+    )
+}
+
+pub fn set_login_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("usernames")
+            .long("usernames")
+            .help("Tries the usernames in the specified file, one per line")
+            .requires("passwords")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("passwords")
+            .long("passwords")
+            .help("Sprays each password in the specified file against all the usernames")
+            .requires("usernames")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("combos")
+            .long("combos")
+            .help("Tries the user:password pairs in the specified file, one per line")
+            .required_unless("usernames")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("login-type")
+            .long("login-type")
+            .help("Sends the credentials in a form or JSON body, or through HTTP authentication [default: form]")
+            .possible_values(&["form", "json", "basic", "digest", "ntlm"])
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("success")
+            .long("success")
+            .help("Detects a successful login when the response matches the expression, instead of comparing it to a failed one")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("lockout")
+            .long("lockout")
+            .help("Detects lockout and captcha responses matching the expression [default: HTTP 429 or lockout/captcha messages]")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("lockout-pause")
+            .long("lockout-pause")
+            .help("Pauses for the specified seconds after a lockout response")
+            .default_value("60")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("similarity-threshold")
+            .long("similarity-threshold")
+            .help("Reports logins whose response is less similar than this to the one of bogus credentials")
+            .default_value("0.9")
+            .takes_value(true),
+    )
+}
+
 pub fn extract_common_args<'a>(submatches: &clap::ArgMatches<'a>) -> CommonArgs {
     let mut no_banner = submatches.is_present("no-banner");
     let mut no_progress_bar = submatches.is_present("no-progress-bar");
@@ -311,13 +837,13 @@ pub fn extract_common_args<'a>(submatches: &clap::ArgMatches<'a>) -> CommonArgs
     }
 }
 
-pub fn extract_http_args<'a>(submatches: &clap::ArgMatches<'a>) -> HTTPArgs {
+pub fn extract_http_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<HTTPArgs, ()> {
     let user_agent = submatches.value_of("user-agent").unwrap();
     let http_method = submatches.value_of("http-method").unwrap();
     let http_body = submatches.value_of("http-body").unwrap();
-    let url = submatches.value_of("url").unwrap();
+    let url = submatches.value_of("url").unwrap_or("");
     let ignore_certificate = submatches.is_present("ignore-certificate");
-    let http_headers: Vec<(String, String)> = if submatches.is_present("http-header") {
+    let mut http_headers: Vec<(String, String)> = if submatches.is_present("http-header") {
         submatches
             .values_of("http-header")
             .unwrap()
@@ -326,30 +852,26 @@ pub fn extract_http_args<'a>(submatches: &clap::ArgMatches<'a>) -> HTTPArgs {
     } else {
         Vec::new()
     };
-    let include_status_codes = submatches
-        .values_of("include-status-codes")
-        .unwrap()
-        .filter(|s| {
-            if s.is_empty() {
-                return false;
-            }
-            s.parse::<hyper::StatusCode>().is_ok()
-        })
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
-    let ignore_status_codes = submatches
-        .values_of("ignore-status-codes")
-        .unwrap()
-        .filter(|s| {
-            if s.is_empty() {
-                return false;
-            }
-            s.parse::<hyper::StatusCode>().is_ok()
+    if submatches.is_present("cookie") {
+        let cookies: Vec<&str> = submatches.values_of("cookie").unwrap().collect();
+        http_headers.push(("Cookie".to_owned(), cookies.join("; ")));
+    }
+    let status_codes = |name: &str| {
+        let specs: Vec<&str> = submatches.values_of(name).unwrap().collect();
+        StatusMatcher::new(&specs).map_err(|e| {
+            error!("Error in --{}: {}", name, e);
         })
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
+    };
+    let include_status_codes = status_codes("include-status-codes")?;
+    let ignore_status_codes = if submatches.occurrences_of("ignore-status-codes") == 0
+        && (submatches.is_present("match") || submatches.is_present("filter"))
+    {
+        StatusMatcher::default()
+    } else {
+        status_codes("ignore-status-codes")?
+    };
 
-    HTTPArgs {
+    Ok(HTTPArgs {
         user_agent: user_agent.to_owned(),
         http_method: http_method.to_owned(),
         http_body: http_body.to_owned(),
@@ -358,17 +880,176 @@ pub fn extract_http_args<'a>(submatches: &clap::ArgMatches<'a>) -> HTTPArgs {
         http_headers,
         include_status_codes,
         ignore_status_codes,
-    }
+    })
 }
 
-pub fn extract_dns_args<'a>(submatches: &clap::ArgMatches<'a>) -> DNSArgs {
+pub fn extract_dns_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<DNSArgs, ()> {
     let domain = submatches.value_of("domain").unwrap_or("");
+    let recursive_depth = match submatches
+        .value_of("recursive-depth")
+        .unwrap_or("0")
+        .parse::<usize>()
+    {
+        Ok(v) => v,
+        Err(_) => {
+            error!("Error in --recursive-depth: expected a number");
+            return Err(());
+        }
+    };
+    let permutations = submatches.is_present("permutations");
+    let permutation_wordlist = submatches
+        .value_of("permutation-wordlist")
+        .map(|w| w.to_owned());
+    let reverse = submatches.is_present("reverse");
+    let takeover = submatches.is_present("takeover");
+    let takeover_http = submatches.is_present("takeover-http");
+    let takeover_fingerprints = submatches
+        .value_of("takeover-fingerprints")
+        .map(|f| f.to_owned());
+    let takeover_timeout = match submatches
+        .value_of("takeover-timeout")
+        .unwrap_or("10000")
+        .parse::<u64>()
+    {
+        Ok(v) => v,
+        Err(_) => {
+            error!("Error in --takeover-timeout: expected a number");
+            return Err(());
+        }
+    };
+    let user_agent = submatches.value_of("user-agent").unwrap_or("rustbuster");
+    let zone_walk = submatches.is_present("zone-walk");
+    let zone_walk_queries = match submatches
+        .value_of("zone-walk-queries")
+        .unwrap_or("10000")
+        .parse::<usize>()
+    {
+        Ok(v) => v,
+        Err(_) => {
+            error!("Error in --zone-walk-queries: expected a number");
+            return Err(());
+        }
+    };
+    let nsec3_hashes = submatches.value_of("nsec3-hashes").map(|f| f.to_owned());
 
-    DNSArgs {
+    Ok(DNSArgs {
         domain: domain.to_owned(),
+        recursive_depth,
+        permutations,
+        permutation_wordlist,
+        reverse,
+        takeover,
+        takeover_http,
+        takeover_fingerprints,
+        takeover_timeout,
+        user_agent: user_agent.to_owned(),
+        zone_walk,
+        zone_walk_queries,
+        nsec3_hashes,
+    })
+}
+
+pub fn extract_resolver_args<'a>(submatches: &clap::ArgMatches<'a>) -> ResolverArgs {
+    let resolvers = submatches.value_of("resolvers").map(|r| r.to_owned());
+    let resolver_qps = submatches
+        .value_of("resolver-qps")
+        .unwrap_or("0")
+        .parse::<u32>()
+        .expect("resolver-qps is a number");
+    let resolver_timeout = submatches
+        .value_of("resolver-timeout")
+        .unwrap_or("2000")
+        .parse::<u64>()
+        .expect("resolver-timeout is a number");
+    let resolver_retries = submatches
+        .value_of("resolver-retries")
+        .unwrap_or("0")
+        .parse::<usize>()
+        .expect("resolver-retries is a number");
+    let control_domain = submatches
+        .value_of("control-domain")
+        .unwrap_or("example.com");
+
+    ResolverArgs {
+        resolvers,
+        resolver_qps,
+        resolver_timeout,
+        resolver_retries,
+        control_domain: control_domain.to_owned(),
     }
 }
 
+pub fn extract_ptr_args<'a>(submatches: &clap::ArgMatches<'a>) -> PtrArgs {
+    let ranges: Vec<String> = if submatches.is_present("range") {
+        submatches
+            .values_of("range")
+            .unwrap()
+            .map(|r| r.to_owned())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let input = submatches.value_of("input").map(|i| i.to_owned());
+
+    PtrArgs { ranges, input }
+}
+
+pub fn extract_srv_args<'a>(submatches: &clap::ArgMatches<'a>) -> SrvArgs {
+    let services = submatches.value_of("services").map(|s| s.to_owned());
+
+    SrvArgs { services }
+}
+
+pub fn extract_vhost_args<'a>(submatches: &clap::ArgMatches<'a>) -> VhostArgs {
+    let similarity_threshold = submatches
+        .value_of("similarity-threshold")
+        .unwrap_or("0.9")
+        .parse::<f64>()
+        .expect("similarity-threshold is a number");
+
+    VhostArgs {
+        similarity_threshold,
+        sni: submatches.is_present("sni"),
+        harvest: submatches.is_present("harvest"),
+    }
+}
+
+pub fn extract_probe_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<ProbeArgs, ()> {
+    let hosts: Vec<String> = match submatches.values_of("hosts") {
+        Some(v) => v.map(|h| h.to_owned()).collect(),
+        None => Vec::new(),
+    };
+    let ports = match crate::probebuster::utils::parse_ports(
+        submatches.value_of("ports").unwrap_or("80,443"),
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Invalid ports: {}", e);
+            return Err(());
+        }
+    };
+    let timeout = submatches
+        .value_of("timeout")
+        .unwrap_or("5000")
+        .parse::<u64>()
+        .expect("timeout is a number");
+    let http_headers: Vec<(String, String)> = match submatches.values_of("http-header") {
+        Some(v) => v
+            .map(crate::fuzzbuster::utils::split_http_headers)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(ProbeArgs {
+        hosts,
+        ports,
+        user_agent: submatches.value_of("user-agent").unwrap().to_owned(),
+        http_headers,
+        timeout,
+        alive_output: submatches.value_of("alive-output").map(|o| o.to_owned()),
+    })
+}
+
 pub fn extract_body_args<'a>(submatches: &clap::ArgMatches<'a>) -> BodyArgs {
     let ignore_strings: Vec<String> = if submatches.is_present("ignore-string") {
         submatches
@@ -395,6 +1076,121 @@ pub fn extract_body_args<'a>(submatches: &clap::ArgMatches<'a>) -> BodyArgs {
     }
 }
 
+pub fn extract_filter_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<FilterArgs, ()> {
+    let parse = |name: &str| -> Result<Vec<Expression>, ()> {
+        submatches
+            .values_of(name)
+            .map(|values| values.collect::<Vec<&str>>())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|expression| {
+                Expression::parse(expression).map_err(|e| {
+                    error!("Error in --{}: {}", name, e);
+                })
+            })
+            .collect()
+    };
+
+    Ok(FilterArgs {
+        matches: parse("match")?,
+        filters: parse("filter")?,
+    })
+}
+
+pub fn extract_auth_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<AuthArgs, ()> {
+    let credentials = match submatches.value_of("auth") {
+        Some(v) => v,
+        None => return Ok(AuthArgs { auth: None }),
+    };
+    let auth_type = submatches
+        .value_of("auth-type")
+        .unwrap_or("basic")
+        .parse::<AuthType>()
+        .expect("auth-type is basic, digest, ntlm or bearer");
+
+    match Credentials::new(auth_type, credentials) {
+        Ok(v) => Ok(AuthArgs { auth: Some(v) }),
+        Err(e) => {
+            error!("Error in --auth: {}", e);
+            Err(())
+        }
+    }
+}
+
+pub fn extract_session_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<SessionArgs, ()> {
+    if !submatches.is_present("cookie-jar")
+        && !submatches.is_present("cookie-file")
+        && !submatches.is_present("login-url")
+    {
+        return Ok(SessionArgs { session: None });
+    }
+
+    let scope = submatches
+        .value_of("cookie-jar")
+        .unwrap_or("global")
+        .parse::<JarScope>()
+        .expect("cookie-jar is global or worker");
+    let mut session = SessionConfig::new(scope);
+
+    if let Some(path) = submatches.value_of("cookie-file") {
+        session.cookies = match read_cookies(path) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Error while reading the cookie file: {}", e);
+                return Err(());
+            }
+        };
+    }
+
+    if let Some(url) = submatches.value_of("login-url") {
+        let uri = match librustbuster::error::check_url(url) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Error in --login-url: {}", e);
+                return Err(());
+            }
+        };
+        let mut headers: Vec<(String, String)> = submatches
+            .values_of("login-header")
+            .map(|headers| {
+                headers
+                    .map(crate::fuzzbuster::utils::split_http_headers)
+                    .collect()
+            })
+            .unwrap_or_default();
+        headers.push((
+            "User-Agent".to_owned(),
+            submatches
+                .value_of("user-agent")
+                .unwrap_or("rustbuster")
+                .to_owned(),
+        ));
+        session.login = Some(RequestTemplate {
+            method: submatches
+                .value_of("login-method")
+                .unwrap_or("POST")
+                .to_owned(),
+            uri,
+            headers,
+            body: submatches.value_of("login-body").unwrap_or("").to_owned(),
+        });
+    }
+
+    if let Some(expression) = submatches.value_of("session-expired") {
+        session.expired = match Expression::parse(expression) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                error!("Error in --session-expired: {}", e);
+                return Err(());
+            }
+        };
+    }
+
+    Ok(SessionArgs {
+        session: Some(session),
+    })
+}
+
 pub fn extract_dir_args<'a>(submatches: &clap::ArgMatches<'a>) -> DirArgs {
     let append_slash = submatches.is_present("append-slash");
     let extensions = submatches
@@ -406,10 +1202,12 @@ pub fn extract_dir_args<'a>(submatches: &clap::ArgMatches<'a>) -> DirArgs {
     DirArgs {
         append_slash,
         extensions,
+        fingerprint: submatches.is_present("fingerprint"),
+        signatures: submatches.value_of("signatures").map(|s| s.to_owned()),
     }
 }
 
-pub fn extract_fuzz_args<'a>(submatches: &clap::ArgMatches<'a>) -> FuzzArgs {
+pub fn extract_csrf_args<'a>(submatches: &clap::ArgMatches<'a>) -> CsrfArgs {
     let csrf_url = match submatches.value_of("csrf-url") {
         Some(v) => Some(v.to_owned()),
         None => None,
@@ -429,15 +1227,115 @@ pub fn extract_fuzz_args<'a>(submatches: &clap::ArgMatches<'a>) -> FuzzArgs {
     } else {
         None
     };
-    FuzzArgs {
+
+    CsrfArgs {
         csrf_url,
         csrf_regex,
         csrf_headers,
     }
 }
 
+pub fn extract_fuzz_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<FuzzArgs, ()> {
+    let CsrfArgs {
+        csrf_url,
+        csrf_regex,
+        csrf_headers,
+    } = extract_csrf_args(submatches);
+    let csrf_reuse = match submatches
+        .value_of("csrf-reuse")
+        .unwrap_or("1")
+        .parse::<usize>()
+    {
+        Ok(0) if !submatches.is_present("csrf-invalid") => {
+            error!("Error in --csrf-reuse: 0 requires --csrf-invalid");
+            return Err(());
+        }
+        Ok(v) => v,
+        Err(_) => {
+            error!("Error in --csrf-reuse: expected a number");
+            return Err(());
+        }
+    };
+    let csrf_invalid = match submatches.value_of("csrf-invalid").map(Expression::parse) {
+        Some(Ok(v)) => Some(v),
+        Some(Err(e)) => {
+            error!("Error in --csrf-invalid: {}", e);
+            return Err(());
+        }
+        None => None,
+    };
+    let pre_request = match submatches.value_of("macro").map(Macro::load) {
+        Some(Ok(v)) => Some(v),
+        Some(Err(e)) => {
+            error!("Error while loading the macro: {}", e);
+            return Err(());
+        }
+        None => None,
+    };
+
+    Ok(FuzzArgs {
+        csrf_url,
+        csrf_regex,
+        csrf_headers,
+        csrf_reuse,
+        csrf_invalid,
+        pre_request,
+    })
+}
+
+pub fn extract_login_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<LoginArgs, ()> {
+    let paths = |name: &str| -> Vec<String> {
+        submatches
+            .values_of(name)
+            .map(|values| values.map(|v| v.to_owned()).collect())
+            .unwrap_or_default()
+    };
+    let login_type = submatches
+        .value_of("login-type")
+        .unwrap_or("form")
+        .parse::<LoginType>()
+        .expect("login-type is form, json, basic, digest or ntlm");
+    let expression = |name: &str| -> Result<Option<Expression>, ()> {
+        match submatches.value_of(name).map(Expression::parse) {
+            Some(Ok(v)) => Ok(Some(v)),
+            Some(Err(e)) => {
+                error!("Error in --{}: {}", name, e);
+                Err(())
+            }
+            None => Ok(None),
+        }
+    };
+    let lockout_pause = match submatches
+        .value_of("lockout-pause")
+        .unwrap_or("60")
+        .parse::<u64>()
+    {
+        Ok(v) => v,
+        Err(_) => {
+            error!("Error in --lockout-pause: expected a number of seconds");
+            return Err(());
+        }
+    };
+    let similarity_threshold = submatches
+        .value_of("similarity-threshold")
+        .unwrap_or("0.9")
+        .parse::<f64>()
+        .expect("similarity-threshold is a number");
+
+    Ok(LoginArgs {
+        usernames: paths("usernames"),
+        passwords: paths("passwords"),
+        combos: paths("combos"),
+        login_type,
+        success: expression("success")?,
+        lockout: expression("lockout")?,
+        lockout_pause,
+        similarity_threshold,
+    })
+}
+
 pub fn url_is_valid(url: &str) -> bool {
-    match url.parse::<hyper::Uri>() {
+    match encode_placeholders(url).parse::<hyper::Uri>() {
         Err(e) => {
             error!(
                 "Invalid URL: {}, consider adding a protocol like http:// or https://",
@@ -471,6 +1369,94 @@ pub fn extract_tilde_args<'a>(submatches: &clap::ArgMatches<'a>) -> TildeArgs {
     TildeArgs { extension }
 }
 
+pub fn extract_targets_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<TargetsArgs, ()> {
+    let max_per_host = submatches
+        .value_of("max-per-host")
+        .unwrap_or("0")
+        .parse::<usize>()
+        .expect("max-per-host is a number");
+    let contents = match submatches.value_of("targets") {
+        Some("-") => {
+            let mut contents = String::new();
+            if let Err(e) = std::io::stdin().read_to_string(&mut contents) {
+                error!("Error while reading targets from stdin: {}", e);
+                return Err(());
+            }
+            contents
+        }
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Error while reading targets file: {}\n{}", path, e);
+                return Err(());
+            }
+        },
+        None => String::new(),
+    };
+    let targets = contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_owned())
+        .collect();
+
+    Ok(TargetsArgs {
+        targets,
+        max_per_host,
+    })
+}
+
+pub fn url_targets(targets: &[String], url: &str) -> Vec<String> {
+    if targets.is_empty() {
+        return if url_is_valid(url) {
+            vec![url.to_owned()]
+        } else {
+            Vec::new()
+        };
+    }
+
+    targets
+        .iter()
+        .map(|target| {
+            if target.contains("://") {
+                target.to_owned()
+            } else {
+                format!("http://{}/", target)
+            }
+        })
+        .filter(|target| url_is_valid(target))
+        .collect()
+}
+
+pub fn domain_targets(targets: &[String], domain: &str) -> Vec<String> {
+    if targets.is_empty() {
+        return if domain.is_empty() {
+            Vec::new()
+        } else {
+            vec![domain.to_owned()]
+        };
+    }
+
+    targets
+        .iter()
+        .filter_map(|target| {
+            let host = match target.find("://") {
+                Some(i) => &target[i + 3..],
+                None => target,
+            };
+            let host = host.split('/').next().unwrap_or_default();
+            let host = host.split(':').next().unwrap_or_default();
+            let host = host.trim_end_matches('.').to_lowercase();
+
+            if host.is_empty() {
+                None
+            } else {
+                Some(host)
+            }
+        })
+        .collect()
+}
+
 pub fn extract_wordlist_args<'a>(submatches: &clap::ArgMatches<'a>) -> Result<WordlistArgs, ()> {
     let wordlist_paths = submatches
         .values_of("wordlist")
@@ -0,0 +1,205 @@
+use clap::App;
+use serde_json::{Map, Value};
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+mod spec;
+
+const MODES: &[&str] = &[
+    "dir", "dns", "ptr", "srv", "vhost", "tilde", "probe", "fuzz", "login",
+];
+const FORMATS: &[&str] = &["toml", "yaml", "yml"];
+const RESERVED: &[&str] = &["config", "profile"];
+
+pub fn parse_document(contents: &str, path: &str) -> Result<Value, String> {
+    let document: Value = if path.ends_with(".yaml") || path.ends_with(".yml") {
+        serde_yaml::from_str(contents).map_err(|e| e.to_string())?
+    } else {
+        toml::from_str(contents).map_err(|e| e.to_string())?
+    };
+
+    match document {
+        Value::Object(_) => Ok(document),
+        Value::Null => Ok(Value::Object(Map::new())),
+        _ => Err("expected a table of options".to_owned()),
+    }
+}
+
+fn load_document(path: &Path) -> Result<Value, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_document(&contents, &path.to_string_lossy())
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn find_document(dir: &Path, name: &str) -> Option<PathBuf> {
+    FORMATS
+        .iter()
+        .map(|format| dir.join(format!("{}.{}", name, format)))
+        .find(|path| path.is_file())
+}
+
+fn merge(settings: &mut Vec<(String, Value)>, key: &str, value: &Value) {
+    settings.retain(|(name, _)| name != key);
+    settings.push((key.to_owned(), value.clone()));
+}
+
+pub fn mode_settings(document: &Value, mode: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut settings: Vec<(String, Value)> = Vec::new();
+    let table = match document.as_object() {
+        Some(v) => v,
+        None => return Err("expected a table of options".to_owned()),
+    };
+
+    for (key, value) in table {
+        if key != "profiles" && !MODES.contains(&key.as_str()) {
+            merge(&mut settings, key, value);
+        }
+    }
+
+    if let Some(value) = table.get(mode) {
+        let options = value
+            .as_object()
+            .ok_or_else(|| format!("{} must be a table of options", mode))?;
+        for (key, value) in options {
+            merge(&mut settings, key, value);
+        }
+    }
+
+    Ok(settings)
+}
+
+pub fn load(
+    config: Option<&str>,
+    profile: Option<&str>,
+    mode: &str,
+) -> Result<Vec<(String, Value)>, String> {
+    let dir = dirs::config_dir().map(|dir| dir.join("rustbuster"));
+    let document = match config {
+        Some(path) => Some(load_document(Path::new(path))?),
+        None => match dir.as_ref().and_then(|dir| find_document(dir, "config")) {
+            Some(path) => Some(load_document(&path)?),
+            None => None,
+        },
+    };
+
+    let mut settings = match &document {
+        Some(document) => mode_settings(document, mode)?,
+        None => Vec::new(),
+    };
+
+    if let Some(name) = profile {
+        let inline = document
+            .as_ref()
+            .and_then(|document| document.get("profiles"))
+            .and_then(|profiles| profiles.get(name))
+            .cloned();
+        let profile = match inline {
+            Some(v) => v,
+            None => {
+                let path = dir
+                    .as_ref()
+                    .and_then(|dir| find_document(&dir.join("profiles"), name))
+                    .ok_or_else(|| format!("profile {} not found", name))?;
+                load_document(&path)?
+            }
+        };
+
+        for (key, value) in mode_settings(&profile, mode)? {
+            merge(&mut settings, &key, &value);
+        }
+    }
+
+    Ok(settings)
+}
+
+fn scalar(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.to_owned()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(format!("invalid value for {}", key)),
+    }
+}
+
+pub fn to_args(settings: &[(String, Value)]) -> Result<Vec<(String, Vec<String>)>, String> {
+    settings
+        .iter()
+        .map(|(key, value)| {
+            if RESERVED.contains(&key.as_str()) {
+                return Err(format!("{} can't be set in a configuration file", key));
+            }
+
+            let args = match value {
+                Value::Bool(true) => vec![format!("--{}", key)],
+                Value::Bool(false) | Value::Null => Vec::new(),
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| scalar(key, item).map(|item| format!("--{}={}", key, item)))
+                    .collect::<Result<Vec<String>, String>>()?,
+                _ => vec![format!("--{}={}", key, scalar(key, value)?)],
+            };
+
+            Ok((key.to_owned(), args))
+        })
+        .collect()
+}
+
+fn option_value<'a>(argv: &'a [String], name: &str) -> Option<&'a str> {
+    let prefix = format!("--{}=", name);
+    argv.iter().enumerate().find_map(|(i, arg)| {
+        if arg == &format!("--{}", name) {
+            argv.get(i + 1).map(|value| value.as_str())
+        } else {
+            arg.strip_prefix(&prefix)
+        }
+    })
+}
+
+pub fn expand_args<'a: 'b, 'b, F>(argv: Vec<String>, app: F) -> Result<Vec<String>, String>
+where
+    F: Fn() -> App<'a, 'b>,
+{
+    let mode = match argv.get(1) {
+        Some(mode) if MODES.contains(&mode.as_str()) => mode.to_owned(),
+        _ => return Ok(argv),
+    };
+    let settings = load(
+        option_value(&argv[2..], "config"),
+        option_value(&argv[2..], "profile"),
+        &mode,
+    )?;
+    let mut args = to_args(&settings)?;
+    if args.is_empty() {
+        return Ok(argv);
+    }
+
+    let merge = |args: &[(String, Vec<String>)]| -> Vec<String> {
+        argv[..2]
+            .iter()
+            .cloned()
+            .chain(args.iter().flat_map(|(_, args)| args.iter().cloned()))
+            .chain(argv[2..].iter().cloned())
+            .collect()
+    };
+
+    if let Ok(matches) = app().get_matches_from_safe(merge(&args)) {
+        if let Some(submatches) = matches.subcommand_matches(&mode) {
+            for (key, args) in args.iter_mut() {
+                let values = submatches.values_of(key.as_str()).map_or(0, |v| v.count());
+                if args.len() > 1 && values < args.len() {
+                    let prefix = format!("--{}=", key);
+                    let list: Vec<&str> = args.iter().map(|arg| &arg[prefix.len()..]).collect();
+                    *args = vec![format!("{}{}", prefix, list.join(","))];
+                }
+            }
+            // later single-value options override earlier ones, repeatable ones add up instead
+            args.retain(|(key, args)| submatches.occurrences_of(key) <= args.len() as u64);
+        }
+    }
+    debug!("configuration options {:?}", args);
+
+    Ok(merge(&args))
+}
@@ -0,0 +1,101 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name config;
+
+    use clap::{App, AppSettings, Arg, SubCommand};
+    use serde_json::json;
+
+    test mode_settings() {
+        let document = crate::config::parse_document(
+            "threads = 20\nuser-agent = \"global\"\n\n[dir]\nuser-agent = \"dir\"\nextensions = [\"asp\", \"aspx\"]\n\n[dns]\nreverse = true\n",
+            "config.toml",
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                ("threads".to_owned(), json!(20)),
+                ("extensions".to_owned(), json!(["asp", "aspx"])),
+                ("user-agent".to_owned(), json!("dir")),
+            ],
+            crate::config::mode_settings(&document, "dir").unwrap()
+        );
+        assert!(crate::config::parse_document("- a\n- b\n", "config.yaml").is_err());
+    }
+
+    test to_args() {
+        let settings = vec![
+            ("ignore-certificate".to_owned(), json!(true)),
+            ("no-banner".to_owned(), json!(false)),
+            ("threads".to_owned(), json!(20)),
+            ("http-header".to_owned(), json!(["A: 1", "B: -2"])),
+        ];
+
+        assert_eq!(
+            vec![
+                ("ignore-certificate".to_owned(), vec!["--ignore-certificate".to_owned()]),
+                ("no-banner".to_owned(), Vec::new()),
+                ("threads".to_owned(), vec!["--threads=20".to_owned()]),
+                (
+                    "http-header".to_owned(),
+                    vec!["--http-header=A: 1".to_owned(), "--http-header=B: -2".to_owned()]
+                ),
+            ],
+            crate::config::to_args(&settings).unwrap()
+        );
+        assert!(crate::config::to_args(&[("profile".to_owned(), json!("x"))]).is_err());
+        assert!(crate::config::to_args(&[("threads".to_owned(), json!({"a": 1}))]).is_err());
+    }
+
+    test expand_args_precedence() {
+        let path = std::env::temp_dir().join("rustbuster-config-spec.yaml");
+        std::fs::write(
+            &path,
+            "threads: 5\ndir:\n  url: http://a/\n  wordlist: [w1]\nprofiles:\n  fast:\n    threads: 50\n    dir:\n      wordlist: [w2, w3]\n",
+        )
+        .unwrap();
+        let app = || {
+            App::new("rustbuster")
+                .global_setting(AppSettings::AllArgsOverrideSelf)
+                .subcommand(
+                    SubCommand::with_name("dir")
+                        .arg(Arg::with_name("config").long("config").takes_value(true))
+                        .arg(Arg::with_name("profile").long("profile").takes_value(true))
+                        .arg(Arg::with_name("threads").long("threads").short("t").takes_value(true))
+                        .arg(Arg::with_name("url").long("url").short("u").required(true).takes_value(true))
+                        .arg(Arg::with_name("wordlist").long("wordlist").short("w").multiple(true).takes_value(true)),
+                )
+        };
+        let expand = |args: &[&str]| {
+            let argv = vec!["rustbuster", "dir", "--config", path.to_str().unwrap()]
+                .into_iter()
+                .chain(args.iter().cloned())
+                .map(|arg| arg.to_owned())
+                .collect();
+            let matches = app().get_matches_from(crate::config::expand_args(argv, app).unwrap());
+            let submatches = matches.subcommand_matches("dir").unwrap().clone();
+            (
+                submatches.value_of("threads").unwrap().to_owned(),
+                submatches.value_of("url").unwrap().to_owned(),
+                submatches.values_of("wordlist").unwrap().collect::<Vec<&str>>().join(","),
+            )
+        };
+
+        assert_eq!(("5".to_owned(), "http://a/".to_owned(), "w1".to_owned()), expand(&[]));
+        assert_eq!(
+            ("50".to_owned(), "http://a/".to_owned(), "w2,w3".to_owned()),
+            expand(&["--profile", "fast"])
+        );
+        assert_eq!(
+            ("7".to_owned(), "http://b/".to_owned(), "w4".to_owned()),
+            expand(&["--profile=fast", "-t", "7", "-u", "http://b/", "-w", "w4"])
+        );
+        assert!(crate::config::expand_args(
+            vec!["rustbuster".to_owned(), "dir".to_owned(), "--profile".to_owned(), "missing".to_owned(),
+                 "--config".to_owned(), path.to_str().unwrap().to_owned()],
+            app
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::{collections::HashSet, sync::mpsc::Sender};
+
+use super::{result_processor::SingleDirScanResult, utils::build_urls, DirConfig};
+use crate::{
+    error::{check_url, Error},
+    scan::{ScanHandle, ScanStream},
+    session::{auth::Credentials, SessionConfig},
+};
+
+#[derive(Debug, Clone)]
+pub struct DirScanBuilder {
+    config: DirConfig,
+    targets: Vec<String>,
+    wordlist_paths: Vec<String>,
+    extensions: Vec<String>,
+    append_slash: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DirScan {
+    config: DirConfig,
+    urls: Vec<(String, hyper::Uri)>,
+}
+
+impl DirScanBuilder {
+    pub fn new() -> Self {
+        DirScanBuilder {
+            config: DirConfig {
+                n_threads: 10,
+                ignore_certificate: false,
+                http_method: "GET".to_owned(),
+                http_body: "".to_owned(),
+                user_agent: "rustbuster".to_owned(),
+                http_headers: Vec::new(),
+                max_per_host: 0,
+                session: None,
+                auth: None,
+                read_body: true,
+            },
+            targets: Vec::new(),
+            wordlist_paths: Vec::new(),
+            extensions: Vec::new(),
+            append_slash: false,
+        }
+    }
+
+    pub fn target(mut self, url: &str) -> Self {
+        self.targets.push(url.to_owned());
+        self
+    }
+
+    pub fn targets(mut self, urls: Vec<String>) -> Self {
+        self.targets.extend(urls);
+        self
+    }
+
+    pub fn wordlist(mut self, path: &str) -> Self {
+        self.wordlist_paths.push(path.to_owned());
+        self
+    }
+
+    pub fn extension(mut self, extension: &str) -> Self {
+        self.extensions.push(extension.to_owned());
+        self
+    }
+
+    pub fn append_slash(mut self, append_slash: bool) -> Self {
+        self.append_slash = append_slash;
+        self
+    }
+
+    pub fn threads(mut self, n_threads: usize) -> Self {
+        self.config.n_threads = n_threads;
+        self
+    }
+
+    pub fn max_per_host(mut self, max_per_host: usize) -> Self {
+        self.config.max_per_host = max_per_host;
+        self
+    }
+
+    pub fn ignore_certificate(mut self, ignore_certificate: bool) -> Self {
+        self.config.ignore_certificate = ignore_certificate;
+        self
+    }
+
+    pub fn method(mut self, http_method: &str) -> Self {
+        self.config.http_method = http_method.to_owned();
+        self
+    }
+
+    pub fn body(mut self, http_body: &str) -> Self {
+        self.config.http_body = http_body.to_owned();
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.config.user_agent = user_agent.to_owned();
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.config
+            .http_headers
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn auth(mut self, credentials: Credentials) -> Self {
+        self.config.auth = Some(credentials);
+        self
+    }
+
+    pub fn session(mut self, session: SessionConfig) -> Self {
+        self.config.session = Some(session);
+        self
+    }
+
+    pub fn read_body(mut self, read_body: bool) -> Self {
+        self.config.read_body = read_body;
+        self
+    }
+
+    pub fn build(self) -> Result<DirScan, Error> {
+        if self.targets.is_empty() {
+            return Err(Error::MissingOption("target"));
+        }
+        if self.wordlist_paths.is_empty() {
+            return Err(Error::MissingOption("wordlist"));
+        }
+
+        let mut urls = Vec::new();
+        for target in &self.targets {
+            check_url(target)?;
+            let mut seen = HashSet::new();
+            for wordlist in &self.wordlist_paths {
+                for url in build_urls(wordlist, target, self.extensions.clone(), self.append_slash)?
+                {
+                    if seen.insert(url.to_string()) {
+                        urls.push((target.to_owned(), url));
+                    }
+                }
+            }
+        }
+
+        Ok(DirScan {
+            config: self.config,
+            urls,
+        })
+    }
+}
+
+impl Default for DirScanBuilder {
+    fn default() -> Self {
+        DirScanBuilder::new()
+    }
+}
+
+impl DirScan {
+    pub fn builder() -> DirScanBuilder {
+        DirScanBuilder::new()
+    }
+
+    pub fn total(&self) -> usize {
+        self.urls.len()
+    }
+
+    pub fn stream(self) -> Result<(ScanStream<SingleDirScanResult>, ScanHandle), Error> {
+        super::stream(self.urls, self.config)
+    }
+
+    pub fn run(self, tx: Sender<SingleDirScanResult>) -> Result<(), Error> {
+        super::run(tx, self.urls, self.config)
+    }
+}
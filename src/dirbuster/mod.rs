@@ -1,16 +1,23 @@
-use futures::Stream;
-use hyper::{
-    client::HttpConnector,
-    rt::{self, Future},
-    Body, Client, Method, Request, StatusCode, Uri,
+use futures::{
+    future::{self, Either},
+    sync::mpsc::{self, UnboundedSender},
+    Stream,
 };
-use hyper_tls::{self, HttpsConnector};
-use native_tls;
-use std::sync::mpsc::Sender;
+use hyper::{rt::Future, Method, StatusCode, Uri};
+use std::sync::{mpsc::Sender, Arc};
 
+pub mod builder;
 pub mod result_processor;
 pub mod utils;
 
+use crate::{
+    error::Error,
+    fingerprint::{self, Signature, Technology},
+    matcher::collect_headers,
+    scan::{self, ScanHandle, ScanStream},
+    scheduler::{host_of, FairScheduler},
+    session::{self, auth::Credentials, HttpClient, RequestTemplate, SessionConfig, SessionPool},
+};
 use result_processor::SingleDirScanResult;
 
 #[derive(Debug, Clone)]
@@ -21,80 +28,147 @@ pub struct DirConfig {
     pub http_body: String,
     pub user_agent: String,
     pub http_headers: Vec<(String, String)>,
+    pub max_per_host: usize,
+    pub session: Option<SessionConfig>,
+    pub auth: Option<Credentials>,
+    pub read_body: bool,
 }
 
 fn make_request_future(
-    tx: Sender<SingleDirScanResult>,
-    client: &Client<HttpsConnector<HttpConnector>>,
+    tx: UnboundedSender<SingleDirScanResult>,
+    client: &HttpClient,
+    sessions: Option<Arc<SessionPool>>,
+    target_url: String,
     url: Uri,
     config: &DirConfig,
-) -> impl Future<Item = (), Error = ()> {
+) -> impl Future<Item = (), Error = Error> {
     let tx_err = tx.clone();
     let mut target = SingleDirScanResult {
+        target: target_url,
         url: url.to_string(),
         method: Method::GET.to_string(),
         status: StatusCode::default().to_string(),
         error: None,
         extra: None,
+        length: None,
+        time: None,
+        headers: Vec::new(),
+        body: String::new(),
     };
     let mut target_err = target.clone();
-    let mut request_builder = Request::builder();
+    let host = match url.host() {
+        Some(v) => v.to_owned(),
+        None => {
+            target.error = Some(
+                Error::InvalidUrl {
+                    url: url.to_string(),
+                    reason: "missing host".to_owned(),
+                }
+                .to_string(),
+            );
+            let _ = tx.unbounded_send(target);
+            return Either::A(future::ok(()));
+        }
+    };
+    let mut headers = config.http_headers.clone();
+    headers.push(("User-Agent".to_owned(), config.user_agent.clone()));
+    headers.push(("Host".to_owned(), host));
+    let request = RequestTemplate {
+        method: config.http_method.clone(),
+        uri: url,
+        headers,
+        body: config.http_body.clone(),
+    };
 
-    for header_tuple in &config.http_headers {
-        request_builder.header(header_tuple.0.as_str(), header_tuple.1.as_str());
-    }
+    let exchange_client = client.clone().read_body(config.read_body);
+    let request_future = session::send(client.clone(), sessions, move |session| {
+        session.exchange(&exchange_client, &request)
+    })
+    .and_then(move |response| {
+        target.status = response.status.to_string();
+        if response.status.is_redirection() {
+            target.extra = response
+                .headers
+                .get("Location")
+                .and_then(|location| location.to_str().ok())
+                .map(|location| location.to_owned());
+        }
+        target.headers = collect_headers(&response.headers);
+        target.length = Some(response.length);
+        target.time = Some(response.time.as_millis() as u64);
+        target.body = String::from_utf8_lossy(&response.body).into_owned();
+        let _ = tx.unbounded_send(target);
+        Ok(())
+    })
+    .or_else(move |e| {
+        target_err.error = Some(e.to_string());
+        let _ = tx_err.unbounded_send(target_err);
+        Ok(())
+    });
 
-    let request = request_builder
-        .header("User-Agent", &config.user_agent[..])
-        .method(&config.http_method[..])
-        .uri(&url)
-        .header("Host", url.host().unwrap())
-        .body(Body::from(config.http_body.clone()))
-        .expect("Request builder");
+    Either::B(request_future)
+}
 
-    client
-        .request(request)
-        .and_then(move |res| {
-            let status = res.status();
-            target.status = status.to_string();
-            if status.is_redirection() {
-                target.extra = Some(
-                    res.headers()
-                        .get("Location")
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .to_owned(),
-                );
-            }
+fn build_client(config: &DirConfig) -> Result<HttpClient, Error> {
+    Ok(HttpClient::https(config.n_threads, config.ignore_certificate)?.auth(config.auth.clone()))
+}
 
-            tx.send(target).unwrap();
-            Ok(())
-        })
-        .or_else(move |e| {
-            target_err.error = Some(e.to_string());
-            tx_err.send(target_err).unwrap_or_else(|_| ());
-            Ok(())
-        })
+pub fn fingerprint_target(
+    target: &str,
+    config: &DirConfig,
+    signatures: Vec<Signature>,
+) -> Result<Vec<Technology>, Error> {
+    let client = build_client(config)?;
+    let mut runtime = tokio::runtime::Runtime::new().map_err(Error::Runtime)?;
+
+    match runtime.block_on(fingerprint::fingerprint(
+        &client,
+        target,
+        &config.user_agent,
+        signatures,
+    )) {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            warn!("Unable to fingerprint {}: {}", target, e);
+            Ok(Vec::new())
+        }
+    }
 }
 
-pub fn run(tx: Sender<SingleDirScanResult>, urls: Vec<hyper::Uri>, config: DirConfig) {
-    let mut tls_connector_builder = native_tls::TlsConnector::builder();
-    tls_connector_builder.danger_accept_invalid_certs(config.ignore_certificate);
-    let tls_connector = tls_connector_builder
-        .build()
-        .expect("TLS initialization failed");
-    let mut http_connector = HttpConnector::new(config.n_threads);
-    http_connector.enforce_http(false);
-    let https_connector = HttpsConnector::from((http_connector, tls_connector));
-    let client = Client::builder().build(https_connector);
+pub fn stream(
+    urls: Vec<(String, hyper::Uri)>,
+    config: DirConfig,
+) -> Result<(ScanStream<SingleDirScanResult>, ScanHandle), Error> {
+    let (tx, rx) = mpsc::unbounded();
+    let total = urls.len();
+    let client = build_client(&config)?;
     let n_threads = config.n_threads;
+    let max_per_host = config.max_per_host;
+    let sessions = config
+        .session
+        .clone()
+        .map(|session| Arc::new(SessionPool::new(session)));
+    let jobs = urls
+        .into_iter()
+        .map(|(target, url)| (host_of(&target), (target, url)));
 
-    let stream = futures::stream::iter_ok(urls)
-        .map(move |url| make_request_future(tx.clone(), &client, url, &config))
-        .buffer_unordered(n_threads)
-        .for_each(Ok)
-        .map_err(|err| eprintln!("Err {:?}", err));
+    let scan = FairScheduler::new(
+        futures::stream::iter_ok(jobs),
+        move |(target, url)| {
+            make_request_future(tx.clone(), &client, sessions.clone(), target, url, &config)
+        },
+        n_threads,
+        max_per_host,
+    )
+    .for_each(Ok);
 
-    rt::run(stream);
+    Ok(ScanStream::new(scan, rx, Some(total)))
+}
+
+pub fn run(
+    tx: Sender<SingleDirScanResult>,
+    urls: Vec<(String, hyper::Uri)>,
+    config: DirConfig,
+) -> Result<(), Error> {
+    scan::forward(stream(urls, config)?.0, tx)
 }
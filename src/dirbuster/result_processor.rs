@@ -1,53 +1,62 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::matcher::{find_header, Matchable, ResultFilter};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SingleDirScanResult {
+    pub target: String,
     pub url: String,
     pub method: String,
     pub status: String,
     pub error: Option<String>,
     pub extra: Option<String>,
+    pub length: Option<usize>,
+    pub time: Option<u64>,
+    pub headers: Vec<(String, String)>,
+    #[serde(skip)]
+    pub body: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct ResultProcessorConfig {
-    pub include: Vec<String>,
-    pub ignore: Vec<String>,
+impl Matchable for SingleDirScanResult {
+    fn status(&self) -> Option<&str> {
+        Some(&self.status)
+    }
+
+    fn length(&self) -> Option<usize> {
+        self.length
+    }
+
+    fn body(&self) -> Option<&str> {
+        Some(&self.body)
+    }
+
+    fn time(&self) -> Option<Duration> {
+        self.time.map(Duration::from_millis)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ScanResult {
     pub results: Vec<SingleDirScanResult>,
-    config: ResultProcessorConfig,
+    filter: ResultFilter<SingleDirScanResult>,
 }
 
 impl ScanResult {
-    pub fn new(config: ResultProcessorConfig) -> Self {
+    pub fn new(filter: ResultFilter<SingleDirScanResult>) -> Self {
         ScanResult {
             results: Vec::<SingleDirScanResult>::new(),
-            config,
+            filter,
         }
     }
 
-    pub fn maybe_add_result(&mut self, res: SingleDirScanResult) -> bool {
+    pub fn maybe_add_result(&mut self, mut res: SingleDirScanResult) -> bool {
         trace!("{:?}", res);
-        let mut ignore = false;
-        let mut include = false;
-        for code in self.config.ignore.iter() {
-            if res.status.starts_with(code) {
-                ignore = true;
-                break;
-            }
-        }
-
-        for code in self.config.include.iter() {
-            if res.status.starts_with(code) {
-                include = true;
-                break;
-            }
-        }
-
-        if !ignore && (self.config.include.is_empty() || include) {
+        if self.filter.is_match(&res) {
+            res.body = String::new();
             self.results.push(res);
             return true;
         }
@@ -1,17 +1,17 @@
-use std::{fs, fs::File, io::Write, path::Path, str};
+use std::{fs::File, io::Write, path::Path, str};
 
 use super::result_processor::SingleDirScanResult;
+use crate::error::{read_file, Error};
 
 pub fn build_urls(
     wordlist_path: &str,
     url: &str,
     extensions: Vec<String>,
     append_slash: bool,
-) -> Vec<hyper::Uri> {
+) -> Result<Vec<hyper::Uri>, Error> {
     debug!("building urls");
     let mut urls: Vec<hyper::Uri> = Vec::new();
-    let wordlist =
-        fs::read_to_string(wordlist_path).expect("Something went wrong reading the wordlist file");
+    let wordlist = read_file(wordlist_path)?;
     let urls_iter = wordlist
         .lines()
         .filter(|word| !word.starts_with('#') && !word.starts_with(' '))
@@ -69,7 +69,7 @@ pub fn build_urls(
         }
     }
 
-    urls
+    Ok(urls)
 }
 
 pub fn save_dir_results(path: &str, results: &Vec<SingleDirScanResult>) {
@@ -0,0 +1,133 @@
+use std::sync::{mpsc::Sender, Arc};
+
+use super::{
+    resolver::ResolverPool, result_processor::SingleDnsScanResult, takeover::TakeoverConfig,
+    utils::build_domains, DnsConfig, DnsQueryType,
+};
+use crate::{
+    error::Error,
+    scan::{ScanHandle, ScanStream},
+};
+
+#[derive(Debug, Clone)]
+pub struct DnsScanBuilder {
+    config: DnsConfig,
+    wordlist_paths: Vec<String>,
+    names: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DnsScan {
+    config: DnsConfig,
+    domains: Vec<String>,
+}
+
+impl DnsScanBuilder {
+    pub fn new() -> Self {
+        DnsScanBuilder {
+            config: DnsConfig {
+                n_threads: 10,
+                resolvers: None,
+                query_type: DnsQueryType::Host,
+                takeover: None,
+                targets: Vec::new(),
+                max_per_host: 0,
+            },
+            wordlist_paths: Vec::new(),
+            names: Vec::new(),
+        }
+    }
+
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.config.targets.push(domain.to_owned());
+        self
+    }
+
+    pub fn domains(mut self, domains: Vec<String>) -> Self {
+        self.config.targets.extend(domains);
+        self
+    }
+
+    pub fn wordlist(mut self, path: &str) -> Self {
+        self.wordlist_paths.push(path.to_owned());
+        self
+    }
+
+    pub fn names(mut self, names: Vec<String>) -> Self {
+        self.names.extend(names);
+        self
+    }
+
+    pub fn query_type(mut self, query_type: DnsQueryType) -> Self {
+        self.config.query_type = query_type;
+        self
+    }
+
+    pub fn resolvers(mut self, resolvers: ResolverPool) -> Self {
+        self.config.resolvers = Some(Arc::new(resolvers));
+        self
+    }
+
+    pub fn takeover(mut self, takeover: TakeoverConfig) -> Self {
+        self.config.takeover = Some(Arc::new(takeover));
+        self
+    }
+
+    pub fn threads(mut self, n_threads: usize) -> Self {
+        self.config.n_threads = n_threads;
+        self
+    }
+
+    pub fn max_per_host(mut self, max_per_host: usize) -> Self {
+        self.config.max_per_host = max_per_host;
+        self
+    }
+
+    pub fn build(self) -> Result<DnsScan, Error> {
+        if self.config.query_type != DnsQueryType::Host && self.config.resolvers.is_none() {
+            return Err(Error::MissingOption("resolvers"));
+        }
+        if !self.wordlist_paths.is_empty() && self.config.targets.is_empty() {
+            return Err(Error::MissingOption("domain"));
+        }
+
+        let mut domains = self.names;
+        for wordlist in &self.wordlist_paths {
+            for target in &self.config.targets {
+                domains.extend(build_domains(wordlist, target)?);
+            }
+        }
+        if domains.is_empty() {
+            return Err(Error::MissingOption("wordlist"));
+        }
+
+        Ok(DnsScan {
+            config: self.config,
+            domains,
+        })
+    }
+}
+
+impl Default for DnsScanBuilder {
+    fn default() -> Self {
+        DnsScanBuilder::new()
+    }
+}
+
+impl DnsScan {
+    pub fn builder() -> DnsScanBuilder {
+        DnsScanBuilder::new()
+    }
+
+    pub fn total(&self) -> usize {
+        self.domains.len()
+    }
+
+    pub fn stream(self) -> Result<(ScanStream<SingleDnsScanResult>, ScanHandle), Error> {
+        super::stream(self.domains, self.config)
+    }
+
+    pub fn run(self, tx: Sender<SingleDnsScanResult>) -> Result<(), Error> {
+        super::run(tx, self.domains, self.config)
+    }
+}
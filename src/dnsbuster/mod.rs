@@ -1,22 +1,60 @@
-use futures::{future, Future, Stream};
-use hyper::rt;
+use futures::{
+    future,
+    sync::mpsc::{self, UnboundedSender},
+    Future, Stream,
+};
+use hyper::{client::HttpConnector, Client};
+use hyper_tls::HttpsConnector;
+use trust_dns_proto::{
+    op::ResponseCode,
+    rr::{Name, RData, RecordType},
+};
 
-use std::{net::ToSocketAddrs, sync::mpsc::Sender};
+use std::{
+    io,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    sync::{mpsc::Sender, Arc},
+};
 
+pub mod builder;
+pub mod resolver;
 pub mod result_processor;
+mod spec;
+pub mod takeover;
 pub mod utils;
+pub mod zonewalk;
 
-use result_processor::SingleDnsScanResult;
+use crate::{
+    error::Error,
+    scan::{self, ScanHandle, ScanStream},
+    scheduler::FairScheduler,
+};
+use resolver::ResolverPool;
+use result_processor::{SingleDnsScanResult, SrvRecord};
+use takeover::TakeoverConfig;
+use utils::{strip_port, target_of};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DnsQueryType {
+    Host,
+    Ptr,
+    Srv,
+}
 
 #[derive(Debug, Clone)]
 pub struct DnsConfig {
     pub n_threads: usize,
+    pub resolvers: Option<Arc<ResolverPool>>,
+    pub query_type: DnsQueryType,
+    pub takeover: Option<Arc<TakeoverConfig>>,
+    pub targets: Vec<String>,
+    pub max_per_host: usize,
 }
 
 fn make_request_future(
-    tx: Sender<SingleDnsScanResult>,
+    tx: UnboundedSender<SingleDnsScanResult>,
     domain: String,
-) -> impl Future<Item = (), Error = ()> {
+) -> impl Future<Item = (), Error = Error> {
     future::lazy(move || {
         match domain.to_socket_addrs() {
             Ok(v) => {
@@ -27,20 +65,34 @@ fn make_request_future(
                 }
 
                 let result = SingleDnsScanResult {
+                    target: None,
                     domain,
                     status: true,
                     extra: Some(addrs),
+                    resolver: None,
+                    error: None,
+                    ptr: None,
+                    cname: None,
+                    takeover: None,
+                    srv: None,
                 };
-                tx.send(result).unwrap();
+                let _ = tx.unbounded_send(result);
             }
             Err(_e) => {
                 let result = SingleDnsScanResult {
+                    target: None,
                     domain,
                     status: false,
                     extra: None,
+                    resolver: None,
+                    error: None,
+                    ptr: None,
+                    cname: None,
+                    takeover: None,
+                    srv: None,
                 };
 
-                tx.send(result).unwrap();
+                let _ = tx.unbounded_send(result);
             }
         };
 
@@ -48,12 +100,347 @@ fn make_request_future(
     })
 }
 
-pub fn run(tx: Sender<SingleDnsScanResult>, domains: Vec<String>, config: DnsConfig) {
-    let stream = futures::stream::iter_ok(domains)
-        .map(move |url| make_request_future(tx.clone(), url))
-        .buffer_unordered(config.n_threads)
-        .for_each(Ok)
-        .map_err(|err| eprintln!("Err {:?}", err));
+fn make_resolver_request_future(
+    tx: UnboundedSender<SingleDnsScanResult>,
+    resolvers: Arc<ResolverPool>,
+    takeover: Option<(Arc<TakeoverConfig>, Client<HttpsConnector<HttpConnector>>)>,
+    domain: String,
+) -> impl Future<Item = (), Error = Error> {
+    let tx_err = tx.clone();
+    let mut target_err = SingleDnsScanResult {
+        target: None,
+        domain: domain.clone(),
+        status: false,
+        extra: None,
+        resolver: None,
+        error: None,
+        ptr: None,
+        cname: None,
+        takeover: None,
+        srv: None,
+    };
+
+    let name = match Name::from_ascii(utils::strip_port(&domain)) {
+        Ok(v) => v,
+        Err(e) => {
+            debug!("Invalid domain {}: {}", domain, e);
+            let _ = tx.unbounded_send(target_err);
+            return future::Either::A(future::ok(()));
+        }
+    };
+
+    let a = resolvers.lookup(name.clone(), RecordType::A);
+    let aaaa = resolvers.lookup(name, RecordType::AAAA);
+
+    future::Either::B(
+        a.join(aaaa)
+            .and_then(move |(a, aaaa)| {
+                let mut addrs: Vec<SocketAddr> = Vec::new();
+                for lookup in [&a, &aaaa].iter() {
+                    for ip in resolver::addresses(&lookup.response) {
+                        addrs.push(SocketAddr::new(ip, 80));
+                    }
+                }
+
+                let cname = resolver::cname_target(&a.response);
+                let dangling =
+                    cname.is_some() && a.response.response_code() == ResponseCode::NXDomain;
+                let status =
+                    a.response.response_code() == ResponseCode::NoError && !addrs.is_empty();
+
+                let check: Box<dyn Future<Item = Option<String>, Error = io::Error> + Send> =
+                    match (&takeover, &cname) {
+                        (Some((config, client)), Some(cname)) => {
+                            match takeover::find_fingerprint(&config.fingerprints, cname) {
+                                Some(fingerprint) if dangling => Box::new(future::ok(Some(
+                                    format!("{} (dangling CNAME)", fingerprint.service),
+                                ))),
+                                Some(fingerprint)
+                                    if status
+                                        && config.http
+                                        && !fingerprint.fingerprint.is_empty() =>
+                                {
+                                    let service = fingerprint.service.clone();
+                                    Box::new(
+                                        takeover::check_http(
+                                            client,
+                                            utils::strip_port(&domain),
+                                            fingerprint.fingerprint.clone(),
+                                            &config.user_agent,
+                                            config.timeout,
+                                        )
+                                        .map(move |matched| {
+                                            if matched {
+                                                Some(format!("{} (fingerprint matched)", service))
+                                            } else {
+                                                None
+                                            }
+                                        })
+                                        .map_err(|_| io::Error::other("takeover check failed")),
+                                    )
+                                }
+                                _ => Box::new(future::ok(None)),
+                            }
+                        }
+                        _ => Box::new(future::ok(None)),
+                    };
+
+                check.and_then(move |takeover| {
+                    let result = SingleDnsScanResult {
+                        target: None,
+                        domain,
+                        status,
+                        extra: if status { Some(addrs) } else { None },
+                        resolver: Some(a.resolver.to_string()),
+                        error: None,
+                        ptr: None,
+                        cname,
+                        takeover,
+                        srv: None,
+                    };
+
+                    let _ = tx.unbounded_send(result);
+                    Ok(())
+                })
+            })
+            .or_else(move |e| {
+                debug!("{} - {}", target_err.domain, e);
+                target_err.error = Some(e.to_string());
+                let _ = tx_err.unbounded_send(target_err);
+                Ok(())
+            }),
+    )
+}
+
+fn make_ptr_request_future(
+    tx: UnboundedSender<SingleDnsScanResult>,
+    resolvers: Arc<ResolverPool>,
+    address: String,
+) -> impl Future<Item = (), Error = Error> {
+    let tx_err = tx.clone();
+    let mut target_err = SingleDnsScanResult {
+        target: None,
+        domain: address.clone(),
+        status: false,
+        extra: None,
+        resolver: None,
+        error: None,
+        ptr: None,
+        cname: None,
+        takeover: None,
+        srv: None,
+    };
+
+    let ip = match address.parse::<IpAddr>() {
+        Ok(v) => v,
+        Err(e) => {
+            debug!("Invalid address {}: {}", address, e);
+            let _ = tx.unbounded_send(target_err);
+            return future::Either::A(future::ok(()));
+        }
+    };
+
+    future::Either::B(
+        resolvers
+            .lookup(Name::from(ip), RecordType::PTR)
+            .and_then(move |lookup| {
+                let names: Vec<String> = lookup
+                    .response
+                    .answers()
+                    .iter()
+                    .filter_map(|record| match record.rdata() {
+                        RData::PTR(name) => Some(name.to_ascii().trim_end_matches('.').to_owned()),
+                        _ => None,
+                    })
+                    .collect();
+
+                let status = !names.is_empty();
+                let result = SingleDnsScanResult {
+                    target: None,
+                    domain: match names.first() {
+                        Some(name) => format!("{}:80", name),
+                        None => address,
+                    },
+                    status,
+                    extra: Some(vec![SocketAddr::new(ip, 80)]),
+                    resolver: Some(lookup.resolver.to_string()),
+                    error: None,
+                    ptr: if status { Some(names) } else { None },
+                    cname: None,
+                    takeover: None,
+                    srv: None,
+                };
+
+                let _ = tx.unbounded_send(result);
+                Ok(())
+            })
+            .or_else(move |e| {
+                debug!("{} - {}", target_err.domain, e);
+                target_err.error = Some(e.to_string());
+                let _ = tx_err.unbounded_send(target_err);
+                Ok(())
+            }),
+    )
+}
+
+fn make_srv_request_future(
+    tx: UnboundedSender<SingleDnsScanResult>,
+    resolvers: Arc<ResolverPool>,
+    service: String,
+) -> impl Future<Item = (), Error = Error> {
+    let tx_err = tx.clone();
+    let mut target_err = SingleDnsScanResult {
+        target: None,
+        domain: service.clone(),
+        status: false,
+        extra: None,
+        resolver: None,
+        error: None,
+        ptr: None,
+        cname: None,
+        takeover: None,
+        srv: None,
+    };
+
+    let name = match Name::from_ascii(&service) {
+        Ok(v) => v,
+        Err(e) => {
+            debug!("Invalid service name {}: {}", service, e);
+            let _ = tx.unbounded_send(target_err);
+            return future::Either::A(future::ok(()));
+        }
+    };
+
+    future::Either::B(
+        resolvers
+            .lookup(name, RecordType::SRV)
+            .and_then(move |lookup| {
+                let mut records: Vec<SrvRecord> = lookup
+                    .response
+                    .answers()
+                    .iter()
+                    .filter_map(|record| match record.rdata() {
+                        RData::SRV(srv) => Some(SrvRecord {
+                            target: srv.target().to_ascii().trim_end_matches('.').to_owned(),
+                            port: srv.port(),
+                            priority: srv.priority(),
+                            weight: srv.weight(),
+                        }),
+                        _ => None,
+                    })
+                    .collect();
+                records.sort_by_key(|record| (record.priority, u16::MAX - record.weight));
 
-    rt::run(stream);
+                let status = !records.is_empty();
+                let result = SingleDnsScanResult {
+                    target: None,
+                    domain: service,
+                    status,
+                    extra: None,
+                    resolver: Some(lookup.resolver.to_string()),
+                    error: None,
+                    ptr: None,
+                    cname: None,
+                    takeover: None,
+                    srv: if status { Some(records) } else { None },
+                };
+
+                let _ = tx.unbounded_send(result);
+                Ok(())
+            })
+            .or_else(move |e| {
+                debug!("{} - {}", target_err.domain, e);
+                target_err.error = Some(e.to_string());
+                let _ = tx_err.unbounded_send(target_err);
+                Ok(())
+            }),
+    )
+}
+
+pub fn stream(
+    domains: Vec<String>,
+    config: DnsConfig,
+) -> Result<(ScanStream<SingleDnsScanResult>, ScanHandle), Error> {
+    let (tx, rx) = mpsc::unbounded();
+    let total = domains.len();
+    let targets = config.targets.clone();
+    let results = rx.map(move |mut result: SingleDnsScanResult| {
+        result.target = target_of(&result.domain, &targets);
+        result
+    });
+    let jobs: Vec<(String, String)> = domains
+        .into_iter()
+        .map(|domain| {
+            let host = target_of(&domain, &config.targets).unwrap_or_else(|| {
+                let name = strip_port(&domain);
+                name.split_once('.')
+                    .map(|(_, parent)| parent)
+                    .unwrap_or(name)
+                    .to_owned()
+            });
+            (host, domain)
+        })
+        .collect();
+    let resolvers = config.resolvers.clone();
+    let query_type = config.query_type;
+    let takeover = match config.takeover.clone() {
+        Some(takeover) => {
+            let mut tls_connector_builder = native_tls::TlsConnector::builder();
+            tls_connector_builder.danger_accept_invalid_certs(true);
+            let tls_connector = tls_connector_builder.build()?;
+            let mut http_connector = HttpConnector::new(config.n_threads);
+            http_connector.enforce_http(false);
+            let https_connector = HttpsConnector::from((http_connector, tls_connector));
+            Some((takeover, Client::builder().build(https_connector)))
+        }
+        None => None,
+    };
+    let scan = FairScheduler::new(
+        futures::stream::iter_ok(jobs),
+        move |url| -> Box<dyn Future<Item = (), Error = Error> + Send> {
+            match (query_type, &resolvers) {
+                (DnsQueryType::Host, Some(resolvers)) => Box::new(make_resolver_request_future(
+                    tx.clone(),
+                    resolvers.clone(),
+                    takeover.clone(),
+                    url,
+                )),
+                (DnsQueryType::Host, None) => Box::new(make_request_future(tx.clone(), url)),
+                (DnsQueryType::Ptr, Some(resolvers)) => {
+                    Box::new(make_ptr_request_future(tx.clone(), resolvers.clone(), url))
+                }
+                (DnsQueryType::Srv, Some(resolvers)) => {
+                    Box::new(make_srv_request_future(tx.clone(), resolvers.clone(), url))
+                }
+                (_, None) => {
+                    let _ = tx.unbounded_send(SingleDnsScanResult {
+                        target: None,
+                        domain: url,
+                        status: false,
+                        extra: None,
+                        resolver: None,
+                        error: Some("this query type requires a resolver".to_owned()),
+                        ptr: None,
+                        cname: None,
+                        takeover: None,
+                        srv: None,
+                    });
+                    Box::new(future::ok(()))
+                }
+            }
+        },
+        config.n_threads,
+        config.max_per_host,
+    )
+    .for_each(Ok);
+
+    Ok(ScanStream::new(scan, results, Some(total)))
+}
+
+pub fn run(
+    tx: Sender<SingleDnsScanResult>,
+    domains: Vec<String>,
+    config: DnsConfig,
+) -> Result<(), Error> {
+    scan::forward(stream(domains, config)?.0, tx)
 }
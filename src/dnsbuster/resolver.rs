@@ -0,0 +1,598 @@
+use futures::{
+    future,
+    sync::{mpsc, oneshot},
+    Future, Stream,
+};
+use hyper::{
+    client::{
+        connect::{Connect, Destination},
+        HttpConnector,
+    },
+    Body, Client, Request, StatusCode, Uri,
+};
+use hyper_tls::HttpsConnector;
+use tokio::{
+    codec::{length_delimited, FramedRead},
+    executor::{DefaultExecutor, Executor},
+    io::{write_all, AsyncRead},
+    net::UdpSocket,
+    timer::{Delay, Timeout},
+};
+use trust_dns_proto::{
+    op::{Edns, Message, MessageType, OpCode, Query, ResponseCode},
+    rr::{Name, RData, RecordType},
+};
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::error::{read_file, Error};
+
+const DNS_PORT: u16 = 53;
+const DOT_PORT: u16 = 853;
+const SYSTEM_RESOLV_CONF: &str = "/etc/resolv.conf";
+const MAX_RESPONSE_SIZE: usize = 4096;
+const DNS_MESSAGE_TYPE: &str = "application/dns-message";
+
+#[derive(Debug)]
+enum Transport {
+    Udp(SocketAddr),
+    Https(Uri, Client<HttpsConnector<HttpConnector>>),
+    Tls(
+        Destination,
+        HttpsConnector<HttpConnector>,
+        Mutex<Option<DotConnection>>,
+    ),
+}
+
+type PendingQueries = Arc<Mutex<HashMap<u16, oneshot::Sender<Vec<u8>>>>>;
+
+#[derive(Debug)]
+struct DotConnection {
+    queries: mpsc::UnboundedSender<Vec<u8>>,
+    pending: PendingQueries,
+}
+
+#[derive(Debug)]
+pub struct Resolver {
+    pub address: String,
+    transport: Transport,
+    interval: Option<Duration>,
+    next_slot: Mutex<Instant>,
+}
+
+#[derive(Debug)]
+pub struct ResolverPool {
+    resolvers: Vec<Arc<Resolver>>,
+    next: AtomicUsize,
+    pub timeout: Duration,
+    pub retries: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lookup {
+    pub resolver: String,
+    pub response: Message,
+}
+
+impl Resolver {
+    pub fn new(address: SocketAddr, qps: u32) -> Self {
+        Resolver::with_transport(address.to_string(), Transport::Udp(address), qps)
+    }
+
+    pub fn from_url(url: &str, qps: u32) -> Option<Self> {
+        if url.starts_with("https://") || url.starts_with("http://") {
+            let uri = url.parse::<Uri>().ok()?;
+            let client = Client::builder().build(https_connector()?);
+            return Some(Resolver::with_transport(
+                url.to_owned(),
+                Transport::Https(uri, client),
+                qps,
+            ));
+        }
+
+        if url.starts_with("tls://") {
+            let authority = url.trim_start_matches("tls://").trim_end_matches('/');
+            let uri = format!("https://{}/", authority).parse::<Uri>().ok()?;
+            let host = uri.host()?.to_owned();
+            let port = uri.port_part().map(|p| p.as_u16()).unwrap_or(DOT_PORT);
+            let destination =
+                Destination::try_from_uri(format!("https://{}:{}/", host, port).parse().ok()?)
+                    .ok()?;
+            return Some(Resolver::with_transport(
+                format!("tls://{}:{}", host, port),
+                Transport::Tls(destination, https_connector()?, Mutex::new(None)),
+                qps,
+            ));
+        }
+
+        parse_resolver_address(url).map(|address| Resolver::new(address, qps))
+    }
+
+    fn with_transport(address: String, transport: Transport, qps: u32) -> Self {
+        let interval = if qps == 0 {
+            None
+        } else {
+            Some(Duration::from_nanos(1_000_000_000 / u64::from(qps)))
+        };
+
+        Resolver {
+            address,
+            transport,
+            interval,
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn reserve_slot(&self) -> Instant {
+        let now = Instant::now();
+        match self.interval {
+            None => now,
+            Some(interval) => {
+                let mut next_slot = self.next_slot.lock().unwrap();
+                let slot = if *next_slot > now { *next_slot } else { now };
+                *next_slot = slot + interval;
+                slot
+            }
+        }
+    }
+
+    fn exchange(
+        &self,
+        bytes: Vec<u8>,
+    ) -> Box<dyn Future<Item = Vec<u8>, Error = io::Error> + Send> {
+        match &self.transport {
+            Transport::Udp(address) => {
+                let address = *address;
+                let local: SocketAddr = match address {
+                    SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+                    SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+                };
+
+                Box::new(
+                    future::result(UdpSocket::bind(&local))
+                        .and_then(move |socket| socket.send_dgram(bytes, &address))
+                        .and_then(|(socket, _)| socket.recv_dgram(vec![0u8; MAX_RESPONSE_SIZE]))
+                        .map(|(_, mut buf, n, _)| {
+                            buf.truncate(n);
+                            buf
+                        }),
+                )
+            }
+            Transport::Https(uri, client) => {
+                let request = match Request::post(uri.clone())
+                    .header("Content-Type", DNS_MESSAGE_TYPE)
+                    .header("Accept", DNS_MESSAGE_TYPE)
+                    .body(Body::from(bytes))
+                {
+                    Ok(v) => v,
+                    Err(e) => return Box::new(future::err(io::Error::other(e))),
+                };
+
+                Box::new(
+                    client
+                        .request(request)
+                        .and_then(|res| {
+                            let status = res.status();
+                            res.into_body().concat2().map(move |body| (status, body))
+                        })
+                        .map_err(io::Error::other)
+                        .and_then(|(status, body)| {
+                            if status != StatusCode::OK {
+                                return Err(io::Error::other(format!(
+                                    "unexpected DoH status {}",
+                                    status
+                                )));
+                            }
+                            Ok(body.to_vec())
+                        }),
+                )
+            }
+            Transport::Tls(destination, connector, connection) => {
+                let id = u16::from_be_bytes([bytes[0], bytes[1]]);
+                let mut framed = (bytes.len() as u16).to_be_bytes().to_vec();
+                framed.extend(bytes);
+
+                let mut connection = connection.lock().unwrap();
+                let dot = match connection.take() {
+                    Some(dot) if !dot.queries.is_closed() => dot,
+                    _ => match DotConnection::open(destination.clone(), connector.clone()) {
+                        Ok(v) => v,
+                        Err(e) => return Box::new(future::err(e)),
+                    },
+                };
+                let (tx, rx) = oneshot::channel();
+                {
+                    let mut pending = dot.pending.lock().unwrap();
+                    pending.retain(|_, waiting| !waiting.is_canceled());
+                    pending.insert(id, tx);
+                }
+                if dot.queries.unbounded_send(framed).is_ok() {
+                    *connection = Some(dot);
+                }
+
+                Box::new(rx.map_err(|_| {
+                    io::Error::new(io::ErrorKind::ConnectionAborted, "DoT connection closed")
+                }))
+            }
+        }
+    }
+
+    pub fn query(
+        self: &Arc<Self>,
+        name: Name,
+        record_type: RecordType,
+        dnssec: bool,
+        timeout: Duration,
+    ) -> impl Future<Item = Lookup, Error = io::Error> {
+        let resolver = self.clone();
+        let slot = self.reserve_slot();
+
+        Delay::new(slot)
+            .map_err(io::Error::other)
+            .and_then(move |_| {
+                let mut query = build_query(name, record_type, dnssec);
+                if let Transport::Https(..) = resolver.transport {
+                    query.set_id(0);
+                }
+                let id = query.id();
+                let address = resolver.address.clone();
+                let timeout_address = address.clone();
+                let bytes = future::result(
+                    query
+                        .to_vec()
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string())),
+                );
+
+                let exchange = bytes
+                    .and_then(move |bytes| resolver.exchange(bytes))
+                    .and_then(move |buf| {
+                        let response = Message::from_vec(&buf).map_err(|e| {
+                            io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+                        })?;
+                        if response.id() != id {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "mismatching DNS message id",
+                            ));
+                        }
+
+                        Ok(Lookup {
+                            resolver: address,
+                            response,
+                        })
+                    });
+
+                Timeout::new(exchange, timeout).map_err(move |e| {
+                    if e.is_elapsed() {
+                        io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("resolver {} timed out", timeout_address),
+                        )
+                    } else if e.is_inner() {
+                        e.into_inner().unwrap()
+                    } else {
+                        io::Error::other("timer error")
+                    }
+                })
+            })
+    }
+}
+
+impl DotConnection {
+    fn open(
+        destination: Destination,
+        connector: HttpsConnector<HttpConnector>,
+    ) -> Result<Self, io::Error> {
+        let (queries, rx) = mpsc::unbounded::<Vec<u8>>();
+        let pending: PendingQueries = Arc::new(Mutex::new(HashMap::new()));
+        let responses = pending.clone();
+        let closed = pending.clone();
+        let address = format!(
+            "{}:{}",
+            destination.host(),
+            destination.port().unwrap_or(DOT_PORT)
+        );
+
+        let connection = connector
+            .connect(destination)
+            .map_err(|e| io::Error::other(e.to_string()))
+            .and_then(move |(stream, _)| {
+                let (reader, writer) = stream.split();
+                let writer = rx
+                    .map_err(|_| io::Error::other("DoT query channel closed"))
+                    .fold(writer, |writer, framed| {
+                        write_all(writer, framed).map(|(writer, _)| writer)
+                    })
+                    .map(|_| ());
+                let reader = FramedRead::new(
+                    reader,
+                    length_delimited::Builder::new()
+                        .length_field_length(2)
+                        .new_codec(),
+                )
+                .for_each(move |frame| {
+                    if frame.len() >= 2 {
+                        let id = u16::from_be_bytes([frame[0], frame[1]]);
+                        if let Some(waiting) = responses.lock().unwrap().remove(&id) {
+                            let _ = waiting.send(frame.to_vec());
+                        }
+                    }
+                    Ok(())
+                });
+
+                reader.select(writer).map(|_| ()).map_err(|(e, _)| e)
+            })
+            .then(move |res| {
+                match res {
+                    Ok(_) => debug!("DoT connection to {} closed", address),
+                    Err(e) => debug!("DoT connection to {} failed: {}", address, e),
+                }
+                closed.lock().unwrap().clear();
+                Ok(())
+            });
+
+        DefaultExecutor::current()
+            .spawn(Box::new(connection))
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        Ok(DotConnection { queries, pending })
+    }
+}
+
+impl ResolverPool {
+    pub fn new(resolvers: Vec<Resolver>, timeout: Duration, retries: usize) -> Result<Self, Error> {
+        if resolvers.is_empty() {
+            return Err(Error::InvalidOption {
+                name: "resolvers",
+                reason: "no usable resolvers".to_owned(),
+            });
+        }
+
+        Ok(ResolverPool {
+            resolvers: resolvers.into_iter().map(Arc::new).collect(),
+            next: AtomicUsize::new(0),
+            timeout,
+            retries,
+        })
+    }
+
+    pub fn from_file(
+        path: &str,
+        qps: u32,
+        timeout: Duration,
+        retries: usize,
+    ) -> Result<Self, Error> {
+        let resolvers = read_file(path)?
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| match Resolver::from_url(line, qps) {
+                Some(resolver) => Some(resolver),
+                None => {
+                    warn!("Invalid resolver address: {}", line);
+                    None
+                }
+            })
+            .collect();
+
+        ResolverPool::new(resolvers, timeout, retries)
+    }
+
+    pub fn from_system(qps: u32, timeout: Duration, retries: usize) -> Result<Self, Error> {
+        let resolvers = fs::read_to_string(SYSTEM_RESOLV_CONF)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                match (fields.next(), fields.next()) {
+                    (Some("nameserver"), Some(address)) => parse_resolver_address(address),
+                    _ => None,
+                }
+            })
+            .map(|address| Resolver::new(address, qps))
+            .collect();
+
+        ResolverPool::new(resolvers, timeout, retries)
+    }
+
+    pub fn len(&self) -> usize {
+        self.resolvers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+
+    pub fn pick(&self) -> Arc<Resolver> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.resolvers.len();
+        self.resolvers[index].clone()
+    }
+
+    pub fn lookup(
+        self: &Arc<Self>,
+        name: Name,
+        record_type: RecordType,
+    ) -> Box<dyn Future<Item = Lookup, Error = io::Error> + Send> {
+        self.lookup_with_retries(name, record_type, false, self.retries)
+    }
+
+    pub fn lookup_dnssec(
+        self: &Arc<Self>,
+        name: Name,
+        record_type: RecordType,
+    ) -> Box<dyn Future<Item = Lookup, Error = io::Error> + Send> {
+        self.lookup_with_retries(name, record_type, true, self.retries)
+    }
+
+    fn lookup_with_retries(
+        self: &Arc<Self>,
+        name: Name,
+        record_type: RecordType,
+        dnssec: bool,
+        retries: usize,
+    ) -> Box<dyn Future<Item = Lookup, Error = io::Error> + Send> {
+        let attempt = self
+            .pick()
+            .query(name.clone(), record_type, dnssec, self.timeout);
+        if retries == 0 {
+            return Box::new(attempt);
+        }
+
+        let pool = self.clone();
+        Box::new(attempt.or_else(move |e| {
+            debug!("retrying {} after error: {}", name, e);
+            pool.lookup_with_retries(name, record_type, dnssec, retries - 1)
+        }))
+    }
+
+    pub fn health_check(self, control_domain: &str) -> Result<Self, Error> {
+        let control = Name::from_ascii(control_domain).map_err(|e| Error::InvalidOption {
+            name: "control domain",
+            reason: format!("{}: {}", control_domain, e),
+        })?;
+        let nx_control = Name::from_ascii(random_label())
+            .expect("random labels are valid names")
+            .append_domain(&control);
+        let timeout = self.timeout;
+
+        let checks = self
+            .resolvers
+            .into_iter()
+            .map(|resolver| {
+                let good = resolver.query(control.clone(), RecordType::A, false, timeout);
+                let bad = resolver.query(nx_control.clone(), RecordType::A, false, timeout);
+                good.join(bad).then(move |res| match res {
+                    Ok((good, bad)) => {
+                        if good.response.response_code() != ResponseCode::NoError
+                            || good.response.answers().is_empty()
+                        {
+                            warn!(
+                                "Dropping resolver {}: control name not resolved",
+                                resolver.address
+                            );
+                            Ok(None)
+                        } else if bad.response.response_code() != ResponseCode::NXDomain
+                            || !bad.response.answers().is_empty()
+                        {
+                            warn!(
+                                "Dropping resolver {}: answers for NXDOMAIN names",
+                                resolver.address
+                            );
+                            Ok(None)
+                        } else {
+                            Ok(Some(resolver))
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Dropping resolver {}: {}", resolver.address, e);
+                        Ok::<_, ()>(None)
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut runtime = tokio::runtime::Runtime::new().map_err(Error::Runtime)?;
+        let resolvers: Vec<Arc<Resolver>> = runtime
+            .block_on(future::join_all(checks))
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .collect();
+        if resolvers.is_empty() {
+            return Err(Error::InvalidOption {
+                name: "resolvers",
+                reason: "no healthy resolvers left".to_owned(),
+            });
+        }
+
+        Ok(ResolverPool {
+            resolvers,
+            next: AtomicUsize::new(0),
+            timeout: self.timeout,
+            retries: self.retries,
+        })
+    }
+}
+
+pub fn build_query(name: Name, record_type: RecordType, dnssec: bool) -> Message {
+    let mut message = Message::new();
+    message
+        .set_id(random_id())
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(Query::query(name, record_type));
+
+    if dnssec {
+        let mut edns = Edns::new();
+        edns.set_max_payload(MAX_RESPONSE_SIZE as u16);
+        edns.set_dnssec_ok(true);
+        message.set_edns(edns);
+    }
+
+    message
+}
+
+pub fn addresses(response: &Message) -> Vec<IpAddr> {
+    response
+        .answers()
+        .iter()
+        .filter_map(|record| match record.rdata() {
+            RData::A(ip) => Some(IpAddr::V4(*ip)),
+            RData::AAAA(ip) => Some(IpAddr::V6(*ip)),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn cname_target(response: &Message) -> Option<String> {
+    response
+        .answers()
+        .iter()
+        .filter_map(|record| match record.rdata() {
+            RData::CNAME(name) => Some(name.to_ascii().trim_end_matches('.').to_owned()),
+            _ => None,
+        })
+        .next_back()
+}
+
+fn https_connector() -> Option<HttpsConnector<HttpConnector>> {
+    let tls_connector = match native_tls::TlsConnector::builder().build() {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("TLS initialization failed: {}", e);
+            return None;
+        }
+    };
+    let mut http_connector = HttpConnector::new(1);
+    http_connector.enforce_http(false);
+    Some(HttpsConnector::from((http_connector, tls_connector)))
+}
+
+pub fn parse_resolver_address(address: &str) -> Option<SocketAddr> {
+    match address.parse::<SocketAddr>() {
+        Ok(v) => Some(v),
+        Err(_) => address
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<IpAddr>()
+            .ok()
+            .map(|ip| SocketAddr::new(ip, DNS_PORT)),
+    }
+}
+
+fn random_id() -> u16 {
+    rand::random()
+}
+
+pub fn random_label() -> String {
+    format!("rustbuster-{:012x}", rand::random::<u64>() >> 16)
+}
@@ -1,27 +1,77 @@
 use serde::{Deserialize, Serialize};
 
+use super::utils::strip_port;
+use crate::matcher::{Matchable, ResultFilter};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SingleDnsScanResult {
+    pub target: Option<String>,
     pub domain: String,
     pub status: bool,
     pub extra: Option<Vec<std::net::SocketAddr>>,
+    pub resolver: Option<String>,
+    pub error: Option<String>,
+    pub ptr: Option<Vec<String>>,
+    pub cname: Option<String>,
+    pub takeover: Option<String>,
+    pub srv: Option<Vec<SrvRecord>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SrvRecord {
+    pub target: String,
+    pub port: u16,
+    pub priority: u16,
+    pub weight: u16,
+}
+
+impl Matchable for SingleDnsScanResult {
+    fn status(&self) -> Option<&str> {
+        match (&self.error, self.status) {
+            (Some(_), _) => None,
+            (None, true) => Some("0 resolved"),
+            (None, false) => Some("3 unresolved"),
+        }
+    }
+
+    fn length(&self) -> Option<usize> {
+        let addresses = self.extra.as_ref().map_or(0, Vec::len);
+        let names = self.ptr.as_ref().map_or(0, Vec::len);
+        let services = self.srv.as_ref().map_or(0, Vec::len);
+        Some(addresses + names + services)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        match name.to_lowercase().as_str() {
+            "domain" => Some(strip_port(&self.domain)),
+            "cname" => self.cname.as_deref(),
+            "takeover" => self.takeover.as_deref(),
+            "resolver" => self.resolver.as_deref(),
+            _ => None,
+        }
+    }
+}
+
 pub struct DnsScanResult {
     pub results: Vec<SingleDnsScanResult>,
+    filter: ResultFilter<SingleDnsScanResult>,
 }
 
 impl DnsScanResult {
-    pub fn new() -> Self {
+    pub fn new(filter: ResultFilter<SingleDnsScanResult>) -> Self {
         DnsScanResult {
             results: Vec::<SingleDnsScanResult>::new(),
+            filter,
         }
     }
 
     pub fn maybe_add_result(&mut self, res: SingleDnsScanResult) -> bool {
         trace!("{:?}", res);
-        self.results.push(res);
-        true
+        if self.filter.is_match(&res) {
+            self.results.push(res);
+            return true;
+        }
+
+        false
     }
 }
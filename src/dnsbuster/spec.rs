@@ -0,0 +1,352 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name dnsbuster;
+
+    use crate::dnsbuster::resolver::{Resolver, ResolverPool};
+    use std::{net::{SocketAddr, UdpSocket}, thread, time::Duration};
+    use trust_dns_proto::{op::{Message, MessageType, ResponseCode}, rr::{Name, RData, Record}};
+
+    fn dns_stub<F>(answer: F) -> SocketAddr
+    where
+        F: Fn(&Message, &mut Message) + Send + 'static,
+    {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            while let Ok((n, peer)) = socket.recv_from(&mut buf) {
+                let query = match Message::from_vec(&buf[..n]) {
+                    Ok(v) => v,
+                    Err(_) => continue,
+                };
+                let mut response = Message::new();
+                response.set_id(query.id()).set_message_type(MessageType::Response).add_queries(query.queries().to_vec());
+                answer(&query, &mut response);
+                let _ = socket.send_to(&response.to_vec().unwrap(), peer);
+            }
+        });
+        address
+    }
+
+    fn a_record(name: &Name) -> Record {
+        Record::from_rdata(name.clone(), 60, RData::A([10, 0, 0, 1].into()))
+    }
+
+    test build_permutations_tokens() {
+        let found = vec!["api-dev.example.com:80".to_owned()];
+        let words = vec!["staging".to_owned()];
+        let permutations = crate::dnsbuster::utils::build_permutations(&found, "example.com", &words);
+        assert!(permutations.contains(&"api-staging.example.com:80".to_owned()));
+        assert!(permutations.contains(&"staging-dev.example.com:80".to_owned()));
+        assert!(permutations.contains(&"dev-api.example.com:80".to_owned()));
+        assert!(permutations.contains(&"api2-dev.example.com:80".to_owned()));
+        assert!(permutations.contains(&"staging.api-dev.example.com:80".to_owned()));
+        assert!(!permutations.contains(&"api-dev.example.com:80".to_owned()));
+    }
+
+    test build_permutations_numbers() {
+        let found = vec!["web2.eu.example.com:80".to_owned()];
+        let permutations = crate::dnsbuster::utils::build_permutations(&found, "example.com", &[]);
+        let expected = vec!["web3.eu.example.com:80".to_owned(), "web1.eu.example.com:80".to_owned()];
+        assert_eq!(expected, permutations);
+
+        let found = vec!["web01.example.com:80".to_owned()];
+        let permutations = crate::dnsbuster::utils::build_permutations(&found, "example.com", &[]);
+        let expected = vec!["web02.example.com:80".to_owned(), "web00.example.com:80".to_owned()];
+        assert_eq!(expected, permutations);
+    }
+
+    test build_permutations_other_domain() {
+        let found = vec!["api.example.org:80".to_owned()];
+        let words = vec!["dev".to_owned()];
+        let permutations = crate::dnsbuster::utils::build_permutations(&found, "example.com", &words);
+        assert!(permutations.is_empty());
+    }
+
+    test build_recursive_domains() {
+        let found = vec!["api.example.com:80".to_owned()];
+        let domains = crate::dnsbuster::utils::build_recursive_domains("./examples/wordlist_short", &found).unwrap();
+        let expected = vec!["1.api.example.com:80".to_owned(), "2.api.example.com:80".to_owned()];
+        assert_eq!(expected, domains);
+    }
+
+    test parse_resolver_address() {
+        let parse = crate::dnsbuster::resolver::parse_resolver_address;
+        assert_eq!(Some("8.8.8.8:53".parse().unwrap()), parse("8.8.8.8"));
+        assert_eq!(Some("1.1.1.1:5353".parse().unwrap()), parse("1.1.1.1:5353"));
+        assert_eq!(Some("[2606:4700::1111]:53".parse().unwrap()), parse("2606:4700::1111"));
+        assert_eq!(None, parse("resolver.local"));
+    }
+
+    test parse_cidr() {
+        let addresses = crate::dnsbuster::utils::parse_cidr("10.0.0.5/30").unwrap();
+        let expected: Vec<std::net::IpAddr> = vec![
+            "10.0.0.4".parse().unwrap(),
+            "10.0.0.5".parse().unwrap(),
+            "10.0.0.6".parse().unwrap(),
+            "10.0.0.7".parse().unwrap(),
+        ];
+        assert_eq!(expected, addresses);
+        assert_eq!(1, crate::dnsbuster::utils::parse_cidr("2001:db8::1").unwrap().len());
+        assert!(crate::dnsbuster::utils::parse_cidr("10.0.0.0/33").is_err());
+        assert!(crate::dnsbuster::utils::parse_cidr("2001:db8::/64").is_err());
+
+        let targets = crate::dnsbuster::utils::build_ptr_targets(&["10.0.0.0/31".to_owned()], &[]).unwrap();
+        assert_eq!(vec!["10.0.0.0".to_owned(), "10.0.0.1".to_owned()], targets);
+        let targets = crate::dnsbuster::utils::build_ptr_targets(&["10.0.0.0/31".to_owned(), "10.0.0/24".to_owned()], &[]);
+        assert!(matches!(targets, Err(crate::error::Error::InvalidOption { name: "range", .. })));
+    }
+
+    test find_fingerprint() {
+        let fingerprints = crate::dnsbuster::takeover::load_fingerprints(None).unwrap();
+        let find = crate::dnsbuster::takeover::find_fingerprint;
+        assert_eq!("GitHub Pages", find(&fingerprints, "phra.github.io.").unwrap().service);
+        assert_eq!("AWS/S3", find(&fingerprints, "b.s3-website-us-east-1.amazonaws.com").unwrap().service);
+        assert_eq!("Heroku", find(&fingerprints, "APP.HerokuApp.com").unwrap().service);
+        assert!(find(&fingerprints, "www.google.com").is_none());
+        assert!(find(&fingerprints, "notgithub.io").is_none());
+        assert!(find(&fingerprints, "s3.amazonaws.com.attacker.tld").is_none());
+        assert_eq!("AWS/S3", find(&fingerprints, "s3.amazonaws.com").unwrap().service);
+
+        let path = std::env::temp_dir().join("rustbuster-invalid-fingerprints.json");
+        std::fs::write(&path, "[{\"service\": \"typo\"").unwrap();
+        let loaded = crate::dnsbuster::takeover::load_fingerprints(path.to_str());
+        assert!(matches!(loaded, Err(crate::error::Error::InvalidOption { .. })));
+    }
+
+    test takeover_http_timeout() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let domain = listener.local_addr().unwrap().to_string();
+        let client = hyper::Client::builder().build(hyper_tls::HttpsConnector::new(1).unwrap());
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let start = std::time::Instant::now();
+        let check = crate::dnsbuster::takeover::check_http(&client, &domain, "unclaimed".to_owned(), "rustbuster", std::time::Duration::from_millis(200));
+        assert_eq!(Ok(false), runtime.block_on(check));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        drop(listener);
+    }
+
+    test result_matchers() {
+        use crate::{dnsbuster::result_processor::SingleDnsScanResult, matcher::{expr::Expression, Matcher, SizeMatcher}};
+
+        let result = SingleDnsScanResult {
+            target: Some("example.com".to_owned()),
+            domain: "blog.example.com:80".to_owned(),
+            status: true,
+            extra: Some(vec!["10.0.0.1:80".parse().unwrap(), "10.0.0.2:80".parse().unwrap()]),
+            resolver: Some("127.0.0.1:53".to_owned()),
+            error: None,
+            ptr: None,
+            cname: Some("example.github.io.".to_owned()),
+            takeover: None,
+            srv: None,
+        };
+        let unresolved = SingleDnsScanResult { status: false, extra: None, cname: None, ..result.clone() };
+
+        assert!(Expression::parse("status == 0").unwrap().matches(&result));
+        assert!(Expression::parse("status == 3").unwrap().matches(&unresolved));
+        assert!(SizeMatcher::exact(2).matches(&result));
+        assert!(Expression::parse("status == 0 && header.cname ~ /github/ && header.domain == \"blog.example.com\"").unwrap().matches(&result));
+        assert!(!Expression::parse("header.cname ~ /github/").unwrap().matches(&unresolved));
+    }
+
+    test build_srv_targets() {
+        let services = vec!["_ldap._tcp".to_owned(), " _sip._udp. ".to_owned(), "".to_owned(), "_ldap._tcp".to_owned()];
+        let targets = crate::dnsbuster::utils::build_srv_targets(&services, "corp.local");
+        let expected = vec!["_ldap._tcp.corp.local".to_owned(), "_sip._udp.corp.local".to_owned()];
+        assert_eq!(expected, targets);
+    }
+
+    test nsec3_hash() {
+        let salt = [0xaa, 0xbb, 0xcc, 0xdd];
+        let hash = crate::dnsbuster::zonewalk::nsec3_hash("example", &salt, 12);
+        assert_eq!("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom", data_encoding::BASE32HEX_NOPAD.encode(&hash).to_lowercase());
+        let hash = crate::dnsbuster::zonewalk::nsec3_hash("A.Example.", &salt, 12);
+        assert_eq!("35mthgpgcu1qg68fab165klnsnk3dpvl", data_encoding::BASE32HEX_NOPAD.encode(&hash).to_lowercase());
+    }
+
+    test nsec3_chain_coverage() {
+        let mut chain = crate::dnsbuster::zonewalk::Nsec3Chain::default();
+        chain.hashes.insert(vec![0x10], vec![0x40]);
+        assert!(chain.is_covered(&[0x20]));
+        assert!(!chain.is_covered(&[0x50]));
+        assert!(!chain.is_covered(&[0x05]));
+        assert!(!chain.is_complete());
+
+        chain.hashes.insert(vec![0x40], vec![0x10]);
+        assert!(chain.is_covered(&[0x50]));
+        assert!(chain.is_covered(&[0x05]));
+        assert!(chain.is_complete());
+    }
+
+    test resolver_from_url() {
+        let from_url = |url| crate::dnsbuster::resolver::Resolver::from_url(url, 0).map(|r| r.address);
+        assert_eq!(Some("8.8.8.8:53".to_owned()), from_url("8.8.8.8"));
+        assert_eq!(Some("https://dns.google/dns-query".to_owned()), from_url("https://dns.google/dns-query"));
+        assert_eq!(Some("tls://1.1.1.1:853".to_owned()), from_url("tls://1.1.1.1"));
+        assert_eq!(Some("tls://dns.quad9.net:8853".to_owned()), from_url("tls://dns.quad9.net:8853/"));
+        assert_eq!(None, from_url("resolver.local"));
+    }
+
+    test target_of() {
+        let target_of = crate::dnsbuster::utils::target_of;
+        let targets = vec!["google.com".to_owned(), "mail.google.com".to_owned()];
+        assert_eq!(Some("google.com".to_owned()), target_of("www.google.com:80", &targets));
+        assert_eq!(Some("mail.google.com".to_owned()), target_of("smtp.mail.google.com:80", &targets));
+        assert_eq!(Some("google.com".to_owned()), target_of("google.com", &targets));
+        assert_eq!(None, target_of("notgoogle.com:80", &targets));
+    }
+
+    test build_scan() {
+        use crate::{
+            dnsbuster::{builder::DnsScan, DnsQueryType},
+            error::Error,
+        };
+
+        let scan = DnsScan::builder()
+            .domain("acme.test")
+            .wordlist("./examples/wordlist_short")
+            .build();
+        assert_eq!(2, scan.unwrap().total());
+
+        let scan = DnsScan::builder()
+            .names(vec!["10.0.0.1".to_owned()])
+            .query_type(DnsQueryType::Ptr)
+            .build();
+        assert!(matches!(scan, Err(Error::MissingOption("resolvers"))));
+
+        let scan = DnsScan::builder()
+            .domain("acme.test")
+            .wordlist("./examples/missing_wordlist")
+            .build();
+        assert!(matches!(scan, Err(Error::Read { .. })));
+    }
+
+    test health_check_drops_poisoned_resolvers() {
+        let honest = dns_stub(|query, response| {
+            let name = query.queries()[0].name().clone();
+            if name == Name::from_ascii("control.test.").unwrap() {
+                response.add_answer(a_record(&name));
+            } else {
+                response.set_response_code(ResponseCode::NXDomain);
+            }
+        });
+        let poisoned = dns_stub(|query, response| {
+            response.add_answer(a_record(query.queries()[0].name()));
+        });
+        let timeout = Duration::from_secs(2);
+
+        let pool = ResolverPool::new(vec![Resolver::new(honest, 0), Resolver::new(poisoned, 0)], timeout, 0).unwrap();
+        let pool = pool.health_check("control.test.").unwrap();
+        assert_eq!(1, pool.len());
+        assert_eq!(honest.to_string(), pool.pick().address);
+
+        let pool = ResolverPool::new(vec![Resolver::new(poisoned, 0)], timeout, 0).unwrap();
+        assert!(pool.health_check("control.test.").is_err());
+        assert!(ResolverPool::new(Vec::new(), timeout, 0).is_err());
+        assert_ne!(crate::dnsbuster::resolver::random_label(), crate::dnsbuster::resolver::random_label());
+    }
+
+    fn zone_pool(address: SocketAddr) -> std::sync::Arc<ResolverPool> {
+        std::sync::Arc::new(ResolverPool::new(vec![Resolver::new(address, 0)], Duration::from_secs(2), 0).unwrap())
+    }
+
+    test walk_nsec_zone() {
+        use trust_dns_proto::rr::dnssec::rdata::{DNSSECRData, NSEC};
+
+        let zone: Vec<Name> = ["walk.test.", "a.walk.test.", "mail.walk.test.", "www.walk.test."].iter().map(|n| Name::from_ascii(n).unwrap()).collect();
+        let stub_zone = zone.clone();
+        let address = dns_stub(move |query, response| {
+            let name = query.queries()[0].name();
+            let nsec = |owner: usize| {
+                let next = stub_zone[(owner + 1) % stub_zone.len()].clone();
+                Record::from_rdata(stub_zone[owner].clone(), 60, RData::DNSSEC(DNSSECRData::NSEC(NSEC::new(next, Vec::new()))))
+            };
+            match stub_zone.iter().position(|owner| owner == name) {
+                Some(owner) => {
+                    response.add_answer(nsec(owner));
+                }
+                None => {
+                    response.set_response_code(ResponseCode::NXDomain).add_name_server(nsec(2));
+                }
+            }
+        });
+
+        let walk = crate::dnsbuster::zonewalk::walk(&zone_pool(address), "walk.test", 100, 4).unwrap();
+        assert_eq!(vec!["walk.test", "a.walk.test", "mail.walk.test", "www.walk.test"], walk.names);
+        assert!(walk.nsec3.is_none());
+
+        let walk = crate::dnsbuster::zonewalk::walk(&zone_pool(address), "walk.test", 2, 4).unwrap();
+        assert_eq!(2, walk.names.len());
+    }
+
+    test walk_nsec3_zone() {
+        use crate::dnsbuster::zonewalk::nsec3_hash;
+        use trust_dns_proto::rr::dnssec::{rdata::{DNSSECRData, NSEC3}, Nsec3HashAlgorithm};
+
+        let salt = vec![0xab, 0xcd];
+        let mut hashes: Vec<Vec<u8>> = ["walk.test", "a.walk.test", "mail.walk.test", "www.walk.test"].iter().map(|n| nsec3_hash(n, &salt, 2)).collect();
+        hashes.sort();
+        let stub_salt = salt.clone();
+        let address = dns_stub(move |query, response| {
+            let hash = nsec3_hash(&query.queries()[0].name().to_ascii(), &stub_salt, 2);
+            let owner = hashes.iter().rposition(|owner| *owner <= hash).unwrap_or(hashes.len() - 1);
+            let next = hashes[(owner + 1) % hashes.len()].clone();
+            let label = data_encoding::BASE32HEX_NOPAD.encode(&hashes[owner]).to_lowercase();
+            let rdata = NSEC3::new(Nsec3HashAlgorithm::SHA1, false, 2, stub_salt.clone(), next, Vec::new());
+            let owner_name = Name::from_ascii(format!("{}.walk.test.", label)).unwrap();
+            response.set_response_code(ResponseCode::NXDomain)
+                .add_name_server(Record::from_rdata(owner_name, 60, RData::DNSSEC(DNSSECRData::NSEC3(rdata))));
+        });
+
+        let walk = crate::dnsbuster::zonewalk::walk(&zone_pool(address), "walk.test", 1000, 8).unwrap();
+        let chain = walk.nsec3.unwrap();
+        assert!(walk.names.is_empty());
+        assert!(chain.is_complete());
+        assert_eq!(4, chain.hashes.len());
+        assert_eq!((salt, 2), (chain.salt.clone(), chain.iterations));
+
+        let words: Vec<String> = ["www", "ftp", "mail", "a"].iter().map(|w| (*w).to_owned()).collect();
+        let mut cracked = chain.crack("walk.test", &words);
+        cracked.sort();
+        assert_eq!(vec!["a.walk.test", "mail.walk.test", "walk.test", "www.walk.test"], cracked);
+    }
+
+    test doh_round_trip() {
+        use futures::{future, Future, Stream};
+        use hyper::{header, service::service_fn, Body, Method, Request, Response, Server, StatusCode};
+        use trust_dns_proto::rr::RecordType;
+
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(|| {
+            service_fn(|request: Request<Body>| {
+                let wire_format = request.method() == Method::POST
+                    && request.headers().get(header::CONTENT_TYPE).is_some_and(|v| v == "application/dns-message");
+                request.into_body().concat2().map(move |body| {
+                    let query = match Message::from_vec(&body) {
+                        Ok(ref query) if wire_format && query.id() == 0 => query.clone(),
+                        _ => return Response::builder().status(StatusCode::BAD_REQUEST).body(Body::empty()).unwrap(),
+                    };
+                    let mut response = Message::new();
+                    response.set_id(0).set_message_type(MessageType::Response).add_queries(query.queries().to_vec());
+                    response.add_answer(a_record(query.queries()[0].name()));
+                    Response::builder()
+                        .header(header::CONTENT_TYPE, "application/dns-message")
+                        .body(Body::from(response.to_vec().unwrap()))
+                        .unwrap()
+                })
+            })
+        });
+        let address = server.local_addr();
+        runtime.spawn(server.map_err(|e| panic!("server error: {}", e)));
+
+        let resolver = Resolver::from_url(&format!("http://{}/dns-query", address), 0).unwrap();
+        let pool = std::sync::Arc::new(ResolverPool::new(vec![resolver], Duration::from_secs(2), 0).unwrap());
+        let name = Name::from_ascii("doh.test.").unwrap();
+        let lookup = runtime.block_on(future::lazy(move || pool.lookup(name, RecordType::A))).unwrap();
+        let expected: Vec<std::net::IpAddr> = vec!["10.0.0.1".parse().unwrap()];
+        assert_eq!(expected, crate::dnsbuster::resolver::addresses(&lookup.response));
+    }
+}
@@ -0,0 +1,106 @@
+use futures::{future, Future, Stream};
+use hyper::{client::HttpConnector, Body, Client, Request};
+use hyper_tls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::timer::Timeout;
+
+use crate::error::{read_file, Error};
+
+const BUNDLED_FINGERPRINTS: &str = include_str!("../../fingerprints/takeover.json");
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Fingerprint {
+    pub service: String,
+    pub cname: Vec<String>,
+    #[serde(default)]
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TakeoverConfig {
+    pub fingerprints: Vec<Fingerprint>,
+    pub http: bool,
+    pub user_agent: String,
+    pub timeout: Duration,
+}
+
+pub fn load_fingerprints(path: Option<&str>) -> Result<Vec<Fingerprint>, Error> {
+    let mut fingerprints: Vec<Fingerprint> =
+        serde_json::from_str(BUNDLED_FINGERPRINTS).expect("Invalid bundled takeover fingerprints");
+
+    if let Some(path) = path {
+        let contents = read_file(path)?;
+        let custom = serde_json::from_str::<Vec<Fingerprint>>(&contents).map_err(|e| {
+            Error::InvalidOption {
+                name: "takeover fingerprints",
+                reason: format!("{}: {}", path, e),
+            }
+        })?;
+        fingerprints.extend(custom);
+    }
+
+    Ok(fingerprints)
+}
+
+pub fn find_fingerprint<'a>(
+    fingerprints: &'a [Fingerprint],
+    cname: &str,
+) -> Option<&'a Fingerprint> {
+    let cname = cname.trim_end_matches('.').to_lowercase();
+    fingerprints.iter().find(|fingerprint| {
+        fingerprint.cname.iter().any(|pattern| {
+            let pattern = pattern.trim_end_matches('.').to_lowercase();
+            cname == pattern || cname.ends_with(&format!(".{}", pattern))
+        })
+    })
+}
+
+fn fetch(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    url: String,
+    user_agent: &str,
+    timeout: Duration,
+) -> Box<dyn Future<Item = String, Error = ()> + Send> {
+    let request = match Request::builder()
+        .header("User-Agent", user_agent)
+        .uri(&url[..])
+        .body(Body::from(""))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            debug!("{} - {}", url, e);
+            return Box::new(future::err(()));
+        }
+    };
+
+    let response = client
+        .request(request)
+        .and_then(|res| res.into_body().concat2())
+        .map(|body| String::from_utf8_lossy(&body).into_owned());
+
+    Box::new(Timeout::new(response, timeout).map_err(move |e| {
+        if e.is_elapsed() {
+            debug!("{} - timed out", url);
+        } else if let Some(e) = e.into_inner() {
+            debug!("{} - {}", url, e);
+        }
+    }))
+}
+
+pub fn check_http(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    domain: &str,
+    fingerprint: String,
+    user_agent: &str,
+    timeout: Duration,
+) -> impl Future<Item = bool, Error = ()> {
+    let client_http = client.clone();
+    let url_http = format!("http://{}/", domain);
+    let user_agent_http = user_agent.to_owned();
+
+    fetch(client, format!("https://{}/", domain), user_agent, timeout)
+        .or_else(move |_| fetch(&client_http, url_http, &user_agent_http, timeout))
+        .map(move |body| body.contains(&fingerprint))
+        .or_else(|_| Ok(false))
+}
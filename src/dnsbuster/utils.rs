@@ -1,17 +1,338 @@
-use std::{fs, io::Write, path};
+use itertools::Itertools;
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path,
+};
 
 use super::result_processor::SingleDnsScanResult;
+use crate::error::{read_file, Error};
 
-pub fn build_domains(wordlist_path: &str, url: &str) -> Vec<String> {
-    debug!("building urls");
-    fs::read_to_string(wordlist_path)
-        .expect("Something went wrong reading the wordlist file")
+pub const MAX_RANGE_SIZE: u128 = 1 << 20;
+
+pub const PERMUTATION_WORDS: &[&str] = &[
+    "dev",
+    "development",
+    "stage",
+    "staging",
+    "test",
+    "testing",
+    "qa",
+    "uat",
+    "prod",
+    "production",
+    "beta",
+    "demo",
+    "internal",
+    "int",
+    "api",
+    "admin",
+    "old",
+    "new",
+    "backup",
+    "v1",
+    "v2",
+];
+
+pub const SRV_SERVICES: &[&str] = &[
+    "_ldap._tcp",
+    "_ldap._tcp.dc._msdcs",
+    "_ldap._tcp.gc._msdcs",
+    "_ldap._tcp.pdc._msdcs",
+    "_ldap._tcp.ForestDnsZones",
+    "_ldap._tcp.DomainDnsZones",
+    "_ldaps._tcp",
+    "_gc._tcp",
+    "_kerberos._tcp",
+    "_kerberos._udp",
+    "_kerberos._tcp.dc._msdcs",
+    "_kerberos-master._tcp",
+    "_kerberos-master._udp",
+    "_kerberos-adm._tcp",
+    "_kpasswd._tcp",
+    "_kpasswd._udp",
+    "_autodiscover._tcp",
+    "_sip._tcp",
+    "_sip._udp",
+    "_sip._tls",
+    "_sips._tcp",
+    "_sipfederationtls._tcp",
+    "_sipinternal._tcp",
+    "_sipinternaltls._tcp",
+    "_xmpp-client._tcp",
+    "_xmpp-server._tcp",
+    "_jabber._tcp",
+    "_h323cs._tcp",
+    "_h323ls._udp",
+    "_stun._tcp",
+    "_stun._udp",
+    "_turn._tcp",
+    "_turn._udp",
+    "_imap._tcp",
+    "_imaps._tcp",
+    "_pop3._tcp",
+    "_pop3s._tcp",
+    "_submission._tcp",
+    "_smtp._tcp",
+    "_caldav._tcp",
+    "_caldavs._tcp",
+    "_carddav._tcp",
+    "_carddavs._tcp",
+    "_http._tcp",
+    "_https._tcp",
+    "_ftp._tcp",
+    "_ssh._tcp",
+    "_nfs._tcp",
+    "_ntp._udp",
+    "_vlmcs._tcp",
+    "_mysql._tcp",
+    "_postgresql._tcp",
+    "_minecraft._tcp",
+    "_matrix._tcp",
+    "_puppet._tcp",
+    "_x-puppet._tcp",
+    "_collab-edge._tls",
+    "_cisco-uds._tcp",
+    "_ciscowlc._tcp",
+    "_avaya-ep-config._tcp",
+    "_mongodb._tcp",
+];
+
+pub fn target_of(domain: &str, targets: &[String]) -> Option<String> {
+    let name = strip_port(domain).trim_end_matches('.').to_lowercase();
+    targets
+        .iter()
+        .filter(|target| !target.is_empty())
+        .filter(|target| name == **target || name.ends_with(&format!(".{}", target)))
+        .max_by_key(|target| target.len())
+        .cloned()
+}
+
+pub fn read_words(wordlist_path: &str) -> Result<Vec<String>, Error> {
+    Ok(read_file(wordlist_path)?
         .lines()
         .filter(|word| !word.starts_with('#') && !word.starts_with(' '))
+        .map(|word| word.to_owned())
+        .collect())
+}
+
+pub fn build_domains(wordlist_path: &str, url: &str) -> Result<Vec<String>, Error> {
+    debug!("building urls");
+    Ok(read_words(wordlist_path)?
+        .iter()
         .map(|word| format!("{}.{}:80", word, url))
+        .collect())
+}
+
+pub fn build_recursive_domains(
+    wordlist_path: &str,
+    found: &[String],
+) -> Result<Vec<String>, Error> {
+    debug!("building recursive urls");
+    let words = read_words(wordlist_path)?;
+    Ok(found
+        .iter()
+        .flat_map(|domain| {
+            words
+                .iter()
+                .map(move |word| format!("{}.{}:80", word, strip_port(domain)))
+        })
+        .collect())
+}
+
+pub fn build_permutations(found: &[String], domain: &str, words: &[String]) -> Vec<String> {
+    debug!("building permutations");
+    let found: Vec<&str> = found.iter().map(|d| strip_port(d)).collect();
+    let found_set: HashSet<&str> = found.iter().cloned().collect();
+    let suffix = format!(".{}", domain);
+    let mut permutations = Vec::new();
+
+    for name in found.iter() {
+        if !name.ends_with(&suffix) {
+            continue;
+        }
+
+        let subdomain = &name[..name.len() - suffix.len()];
+        let (label, rest) = match subdomain.find('.') {
+            Some(index) => (
+                &subdomain[..index],
+                format!("{}{}", &subdomain[index..], suffix),
+            ),
+            None => (subdomain, suffix.clone()),
+        };
+
+        for variation in permute_label(label, words) {
+            permutations.push(format!("{}{}", variation, rest));
+        }
+    }
+
+    permutations
+        .into_iter()
+        .filter(|name| !found_set.contains(name.as_str()))
+        .unique()
+        .map(|name| format!("{}:80", name))
         .collect()
 }
 
+fn permute_label(label: &str, words: &[String]) -> Vec<String> {
+    let tokens: Vec<&str> = label.split('-').filter(|t| !t.is_empty()).collect();
+    let mut variations = Vec::new();
+
+    for word in words {
+        variations.push(format!("{}-{}", word, label));
+        variations.push(format!("{}-{}", label, word));
+        variations.push(format!("{}{}", word, label));
+        variations.push(format!("{}{}", label, word));
+        variations.push(format!("{}.{}", word, label));
+
+        if tokens.len() > 1 {
+            for (i, _) in tokens.iter().enumerate() {
+                let mut replaced = tokens.clone();
+                replaced[i] = word;
+                variations.push(replaced.join("-"));
+            }
+        }
+    }
+
+    for (i, token) in tokens.iter().enumerate() {
+        for number in numeric_variations(token) {
+            let mut replaced: Vec<String> = tokens.iter().map(|t| (*t).to_owned()).collect();
+            replaced[i] = number;
+            variations.push(replaced.join("-"));
+        }
+    }
+
+    for i in 0..tokens.len() {
+        for j in i + 1..tokens.len() {
+            let mut swapped = tokens.clone();
+            swapped.swap(i, j);
+            variations.push(swapped.join("-"));
+        }
+    }
+
+    variations.retain(|v| v != label);
+    variations
+}
+
+fn numeric_variations(token: &str) -> Vec<String> {
+    let digits = token
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    let (prefix, number) = token.split_at(token.len() - digits);
+
+    match number.parse::<u64>() {
+        Ok(n) => {
+            let mut variations = vec![format!("{}{:0width$}", prefix, n + 1, width = digits)];
+            if n > 0 {
+                variations.push(format!("{}{:0width$}", prefix, n - 1, width = digits));
+            }
+            variations
+        }
+        Err(_) => (1..=3).map(|n| format!("{}{}", token, n)).collect(),
+    }
+}
+
+pub fn parse_cidr(range: &str) -> Result<Vec<IpAddr>, String> {
+    let (address, prefix) = match range.find('/') {
+        Some(index) => (&range[..index], Some(&range[index + 1..])),
+        None => (range, None),
+    };
+    let address = address
+        .parse::<IpAddr>()
+        .map_err(|e| format!("invalid address {}: {}", address, e))?;
+    let bits = match address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    };
+    let prefix = match prefix {
+        Some(v) => v
+            .parse::<u32>()
+            .map_err(|e| format!("invalid prefix {}: {}", v, e))?,
+        None => bits,
+    };
+    if prefix > bits {
+        return Err(format!("invalid prefix /{} for {}", prefix, address));
+    }
+
+    let host_bits = bits - prefix;
+    if host_bits >= 128 || (1u128 << host_bits) > MAX_RANGE_SIZE {
+        return Err(format!("range {} is too large", range));
+    }
+
+    let size = 1u128 << host_bits;
+    let addresses = match address {
+        IpAddr::V4(ip) => {
+            let network = u128::from(u32::from(ip)) & !(size - 1);
+            (network..network + size)
+                .map(|n| IpAddr::V4(Ipv4Addr::from(n as u32)))
+                .collect()
+        }
+        IpAddr::V6(ip) => {
+            let network = u128::from(ip) & !(size - 1);
+            (network..network + size)
+                .map(|n| IpAddr::V6(Ipv6Addr::from(n)))
+                .collect()
+        }
+    };
+
+    Ok(addresses)
+}
+
+pub fn build_ptr_targets(
+    ranges: &[String],
+    results: &[SingleDnsScanResult],
+) -> Result<Vec<String>, Error> {
+    debug!("building reverse lookups");
+    let mut targets: Vec<IpAddr> = Vec::new();
+    for range in ranges {
+        let addresses = parse_cidr(range).map_err(|e| Error::InvalidOption {
+            name: "range",
+            reason: format!("{}: {}", range, e),
+        })?;
+        targets.extend(addresses);
+    }
+
+    for result in results {
+        if let Some(addrs) = &result.extra {
+            targets.extend(addrs.iter().map(|addr| addr.ip()));
+        }
+    }
+
+    Ok(targets
+        .into_iter()
+        .unique()
+        .map(|ip| ip.to_string())
+        .collect())
+}
+
+pub fn build_srv_targets(services: &[String], domain: &str) -> Vec<String> {
+    debug!("building service records");
+    services
+        .iter()
+        .map(|service| service.trim().trim_end_matches('.'))
+        .filter(|service| !service.is_empty())
+        .map(|service| format!("{}.{}", service, domain))
+        .unique()
+        .collect()
+}
+
+pub fn load_dns_results(path: &str) -> Result<Vec<SingleDnsScanResult>, Error> {
+    let json_string = read_file(path)?;
+    serde_json::from_str(&json_string).map_err(|e| Error::InvalidOption {
+        name: "results file",
+        reason: format!("{}: {}", path, e),
+    })
+}
+
+pub fn strip_port(domain: &str) -> &str {
+    domain.trim_end_matches(":80")
+}
+
 pub fn save_dns_results(path: &str, results: &Vec<SingleDnsScanResult>) {
     let json_string = serde_json::to_string(&results).unwrap();
 
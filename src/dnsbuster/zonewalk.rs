@@ -0,0 +1,358 @@
+use data_encoding::BASE32HEX_NOPAD;
+use futures::{future, Future};
+use trust_dns_proto::{
+    op::Message,
+    rr::{
+        dnssec::rdata::{DNSSECRData, DNSSECRecordType},
+        Name, RData, RecordType,
+    },
+};
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::Write,
+    path,
+    sync::Arc,
+};
+
+use super::resolver::{random_label, ResolverPool};
+use crate::error::Error;
+
+const MAX_CANDIDATE_TRIES: usize = 4096;
+const MAX_IDLE_ROUNDS: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct Nsec3Chain {
+    pub salt: Vec<u8>,
+    pub iterations: u16,
+    pub hashes: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ZoneWalk {
+    pub names: Vec<String>,
+    pub nsec3: Option<Nsec3Chain>,
+}
+
+enum Denial {
+    Nsec,
+    Nsec3,
+    Unsigned,
+}
+
+impl Nsec3Chain {
+    pub fn is_covered(&self, hash: &[u8]) -> bool {
+        if let Some((owner, next)) = self.hashes.range(..=hash.to_vec()).next_back() {
+            if owner.as_slice() == hash {
+                return true;
+            }
+            if owner < next {
+                return hash < next.as_slice();
+            }
+            return true;
+        }
+
+        match self.hashes.iter().next_back() {
+            Some((owner, next)) => next < owner && hash < next.as_slice(),
+            None => false,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.hashes.is_empty()
+            && self
+                .hashes
+                .values()
+                .all(|next| self.hashes.contains_key(next))
+    }
+
+    pub fn hash(&self, name: &str) -> Vec<u8> {
+        nsec3_hash(name, &self.salt, self.iterations)
+    }
+
+    pub fn crack(&self, domain: &str, words: &[String]) -> Vec<String> {
+        let mut candidates = vec![domain.to_owned()];
+        candidates.extend(words.iter().map(|word| format!("{}.{}", word, domain)));
+
+        candidates
+            .into_iter()
+            .filter(|name| self.hashes.contains_key(&self.hash(name)))
+            .collect()
+    }
+
+    fn merge(&mut self, response: &Message, domain: &Name) -> usize {
+        let mut added = 0;
+        for record in response.name_servers().iter().chain(response.answers()) {
+            let nsec3 = match record.rdata() {
+                RData::DNSSEC(DNSSECRData::NSEC3(nsec3)) => nsec3,
+                _ => continue,
+            };
+            if record.name().base_name() != *domain {
+                continue;
+            }
+
+            let label = record.name().iter().next().unwrap_or_default();
+            let owner = match BASE32HEX_NOPAD.decode(&label.to_ascii_uppercase()) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            if self.hashes.is_empty() {
+                self.salt = nsec3.salt().to_vec();
+                self.iterations = nsec3.iterations();
+            }
+            if self
+                .hashes
+                .insert(owner, nsec3.next_hashed_owner_name().to_vec())
+                .is_none()
+            {
+                added += 1;
+            }
+        }
+
+        added
+    }
+}
+
+pub fn nsec3_hash(name: &str, salt: &[u8], iterations: u16) -> Vec<u8> {
+    let mut wire: Vec<u8> = Vec::new();
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() {
+            continue;
+        }
+        wire.push(label.len() as u8);
+        wire.extend(label.to_lowercase().as_bytes());
+    }
+    wire.push(0);
+
+    let mut digest = wire;
+    for _ in 0..=iterations {
+        let mut hasher = sha1::Sha1::new();
+        hasher.update(&digest);
+        hasher.update(salt);
+        digest = hasher.digest().bytes().to_vec();
+    }
+
+    digest
+}
+
+fn nsec_next(response: &Message, owner: &Name) -> Option<Name> {
+    response
+        .answers()
+        .iter()
+        .chain(response.name_servers())
+        .filter(|record| record.name() == owner)
+        .filter_map(|record| match record.rdata() {
+            RData::DNSSEC(DNSSECRData::NSEC(nsec)) => Some(nsec.next_domain_name().clone()),
+            _ => None,
+        })
+        .next()
+}
+
+fn denial_of(response: &Message) -> Denial {
+    let types: HashSet<RecordType> = response
+        .name_servers()
+        .iter()
+        .chain(response.answers())
+        .map(|record| record.record_type())
+        .collect();
+
+    if types.contains(&RecordType::DNSSEC(DNSSECRecordType::NSEC3)) {
+        Denial::Nsec3
+    } else if types.contains(&RecordType::DNSSEC(DNSSECRecordType::NSEC)) {
+        Denial::Nsec
+    } else {
+        Denial::Unsigned
+    }
+}
+
+fn walk_nsec(
+    runtime: &mut tokio::runtime::Runtime,
+    pool: &Arc<ResolverPool>,
+    domain: &Name,
+    max_queries: usize,
+) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut seen: HashSet<Name> = HashSet::new();
+    let mut current = domain.clone();
+
+    while seen.len() < max_queries && seen.insert(current.clone()) {
+        let lookup = match runtime.block_on(
+            pool.lookup_dnssec(current.clone(), RecordType::DNSSEC(DNSSECRecordType::NSEC)),
+        ) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Zone walk stopped at {}: {}", current, e);
+                break;
+            }
+        };
+
+        names.push(current.to_ascii().trim_end_matches('.').to_owned());
+        current = match nsec_next(&lookup.response, &current) {
+            Some(next) if domain.zone_of(&next) && next != *domain => next,
+            Some(_) => break,
+            None => {
+                warn!("Zone walk stopped at {}: no NSEC record", current);
+                break;
+            }
+        };
+    }
+
+    names
+}
+
+fn walk_nsec3(
+    runtime: &mut tokio::runtime::Runtime,
+    pool: &Arc<ResolverPool>,
+    domain: &Name,
+    max_queries: usize,
+    batch_size: usize,
+    mut chain: Nsec3Chain,
+) -> Nsec3Chain {
+    let domain_str = domain.to_ascii();
+    let mut queries = 0;
+    let mut idle_rounds = 0;
+
+    while !chain.is_complete() && queries < max_queries && idle_rounds < MAX_IDLE_ROUNDS {
+        let mut candidates: Vec<Name> = Vec::new();
+        let mut targeted: HashSet<Vec<u8>> = HashSet::new();
+        for _ in 0..MAX_CANDIDATE_TRIES {
+            if candidates.len() >= batch_size.min(max_queries - queries) {
+                break;
+            }
+
+            let label = random_label();
+            let hash = chain.hash(&format!("{}.{}", label, domain_str));
+            let gap = chain
+                .hashes
+                .range(..=hash.clone())
+                .next_back()
+                .map(|(owner, _)| owner.clone())
+                .unwrap_or_default();
+            if chain.is_covered(&hash) || !targeted.insert(gap) {
+                continue;
+            }
+
+            if let Ok(name) = Name::from_ascii(&label) {
+                candidates.push(name.append_domain(domain));
+            }
+        }
+
+        if candidates.is_empty() {
+            break;
+        }
+
+        queries += candidates.len();
+        let lookups = candidates
+            .into_iter()
+            .map(|name| pool.lookup_dnssec(name, RecordType::A).then(Ok::<_, ()>))
+            .collect::<Vec<_>>();
+
+        let mut added = 0;
+        for lookup in runtime
+            .block_on(future::join_all(lookups))
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+        {
+            added += chain.merge(&lookup.response, domain);
+        }
+
+        idle_rounds = if added == 0 { idle_rounds + 1 } else { 0 };
+        debug!(
+            "collected {} NSEC3 hashes after {} queries",
+            chain.hashes.len(),
+            queries
+        );
+    }
+
+    chain
+}
+
+pub fn walk(
+    pool: &Arc<ResolverPool>,
+    domain: &str,
+    max_queries: usize,
+    batch_size: usize,
+) -> Result<ZoneWalk, Error> {
+    let domain = match Name::from_ascii(domain) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Invalid domain {}: {}", domain, e);
+            return Ok(ZoneWalk::default());
+        }
+    };
+    let probe = match Name::from_ascii(random_label()) {
+        Ok(label) => label.append_domain(&domain),
+        Err(_) => return Ok(ZoneWalk::default()),
+    };
+
+    let mut runtime = tokio::runtime::Runtime::new().map_err(Error::Runtime)?;
+    let response = match runtime.block_on(pool.lookup_dnssec(probe, RecordType::A)) {
+        Ok(lookup) => lookup.response,
+        Err(e) => {
+            warn!("Zone walk probe failed: {}", e);
+            return Ok(ZoneWalk::default());
+        }
+    };
+
+    let walk = match denial_of(&response) {
+        Denial::Nsec => ZoneWalk {
+            names: walk_nsec(&mut runtime, pool, &domain, max_queries),
+            nsec3: None,
+        },
+        Denial::Nsec3 => {
+            let mut chain = Nsec3Chain::default();
+            chain.merge(&response, &domain);
+            ZoneWalk {
+                names: Vec::new(),
+                nsec3: Some(walk_nsec3(
+                    &mut runtime,
+                    pool,
+                    &domain,
+                    max_queries,
+                    batch_size,
+                    chain,
+                )),
+            }
+        }
+        Denial::Unsigned => {
+            warn!("{} does not look like a DNSSEC-signed zone", domain);
+            ZoneWalk::default()
+        }
+    };
+
+    Ok(walk)
+}
+
+pub fn save_nsec3_hashes(path: &str, chains: &[(String, Nsec3Chain)]) {
+    let lines: Vec<String> = chains
+        .iter()
+        .flat_map(|(domain, chain)| {
+            let salt: String = chain.salt.iter().map(|b| format!("{:02x}", b)).collect();
+            chain.hashes.keys().map(move |hash| {
+                format!(
+                    "{}:.{}:{}:{}",
+                    BASE32HEX_NOPAD.encode(hash).to_lowercase(),
+                    domain,
+                    salt,
+                    chain.iterations
+                )
+            })
+        })
+        .collect();
+
+    let mut file = match fs::File::create(path::Path::new(path)) {
+        Ok(f) => f,
+        Err(e) => {
+            error!("Error while creating file: {}\n{}", path, e);
+            return;
+        }
+    };
+
+    match file.write_all(format!("{}\n", lines.join("\n")).as_bytes()) {
+        Ok(_) => debug!("Hashes saved to: {}", path),
+        Err(e) => error!("Error while writing hashes to file: {}\n{}", path, e),
+    };
+}
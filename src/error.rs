@@ -0,0 +1,130 @@
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum Error {
+    Read {
+        path: String,
+        error: io::Error,
+    },
+    InvalidUrl {
+        url: String,
+        reason: String,
+    },
+    InvalidRegex {
+        regex: String,
+        error: regex::Error,
+    },
+    InvalidExpression {
+        expression: String,
+        column: usize,
+        reason: String,
+    },
+    InvalidOption {
+        name: &'static str,
+        reason: String,
+    },
+    MissingOption(&'static str),
+    Tls(native_tls::Error),
+    Runtime(io::Error),
+    Http(hyper::Error),
+    Connect {
+        url: String,
+        reason: String,
+    },
+    Login {
+        url: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Read { path, error } => write!(f, "unable to read {}: {}", path, error),
+            Error::InvalidUrl { url, reason } => write!(f, "invalid URL {}: {}", url, reason),
+            Error::InvalidRegex { regex, error } => {
+                write!(f, "invalid regex {}: {}", regex, error)
+            }
+            Error::InvalidExpression {
+                expression,
+                column,
+                reason,
+            } => write!(
+                f,
+                "invalid expression: {} at column {}\n\t{}\n\t{}^",
+                reason,
+                column,
+                expression,
+                " ".repeat(column - 1)
+            ),
+            Error::InvalidOption { name, reason } => write!(f, "invalid {}: {}", name, reason),
+            Error::MissingOption(name) => write!(f, "missing {}", name),
+            Error::Tls(error) => write!(f, "TLS initialization failed: {}", error),
+            Error::Runtime(error) => write!(f, "runtime initialization failed: {}", error),
+            Error::Http(error) => write!(f, "{}", error),
+            Error::Connect { url, reason } => write!(f, "unable to connect to {}: {}", url, reason),
+            Error::Login { url, reason } => write!(f, "unable to log in via {}: {}", url, reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Read { error, .. } => Some(error),
+            Error::InvalidRegex { error, .. } => Some(error),
+            Error::Tls(error) => Some(error),
+            Error::Runtime(error) => Some(error),
+            Error::Http(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<native_tls::Error> for Error {
+    fn from(error: native_tls::Error) -> Self {
+        Error::Tls(error)
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(error: hyper::Error) -> Self {
+        Error::Http(error)
+    }
+}
+
+pub fn read_file(path: &str) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|error| Error::Read {
+        path: path.to_owned(),
+        error,
+    })
+}
+
+pub fn check_url(url: &str) -> Result<hyper::Uri, Error> {
+    let invalid = |reason: &str| Error::InvalidUrl {
+        url: url.to_owned(),
+        reason: reason.to_owned(),
+    };
+    let uri = url
+        .parse::<hyper::Uri>()
+        .map_err(|e| invalid(&e.to_string()))?;
+
+    match uri.scheme_part().map(|scheme| scheme.as_str()) {
+        Some("http") | Some("https") => (),
+        Some(_) => return Err(invalid("only http:// or https:// are supported")),
+        None => return Err(invalid("missing protocol")),
+    }
+
+    if uri.host().is_none() {
+        return Err(invalid("missing host"));
+    }
+
+    Ok(uri)
+}
+
+pub fn check_regex(regex: &str) -> Result<regex::Regex, Error> {
+    regex::Regex::new(regex).map_err(|error| Error::InvalidRegex {
+        regex: regex.to_owned(),
+        error,
+    })
+}
@@ -0,0 +1,267 @@
+use data_encoding::BASE64;
+use futures::{future, Future};
+use hyper::{client::connect::Connect, header, HeaderMap, StatusCode};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use crate::{
+    error::{check_url, read_file, Error},
+    session::{HttpClient, RequestTemplate},
+};
+
+mod spec;
+
+const BUNDLED_SIGNATURES: &str = include_str!("../../fingerprints/technologies.json");
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Signature {
+    pub name: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub cookies: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Vec<String>,
+    #[serde(default)]
+    pub favicon: Vec<i32>,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub wordlists: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Technology {
+    pub name: String,
+    pub version: Option<String>,
+    pub extensions: Vec<String>,
+    pub wordlists: Vec<String>,
+}
+
+pub fn load_signatures(path: Option<&str>) -> Result<Vec<Signature>, Error> {
+    let mut signatures: Vec<Signature> =
+        serde_json::from_str(BUNDLED_SIGNATURES).expect("Invalid bundled technology signatures");
+
+    if let Some(path) = path {
+        let contents = read_file(path)?;
+        let custom = serde_json::from_str::<Vec<Signature>>(&contents).map_err(|e| {
+            Error::InvalidOption {
+                name: "signatures",
+                reason: format!("{}: {}", path, e),
+            }
+        })?;
+        signatures.extend(custom);
+    }
+
+    Ok(signatures)
+}
+
+fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut hash = seed;
+    let chunks = data.chunks_exact(4);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        hash = (hash ^ mix(k))
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe654_6b64);
+    }
+
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .rev()
+            .fold(0u32, |acc, byte| (acc << 8) | u32::from(*byte));
+        hash ^= mix(k);
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
+
+pub fn favicon_hash(favicon: &[u8]) -> i32 {
+    let encoded = BASE64.encode(favicon);
+    let mut wrapped = String::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for line in encoded.as_bytes().chunks(76) {
+        wrapped.push_str(&String::from_utf8_lossy(line));
+        wrapped.push('\n');
+    }
+
+    murmur3_32(wrapped.as_bytes(), 0) as i32
+}
+
+fn compile(pattern: &str, signature: &str) -> Option<Regex> {
+    match Regex::new(&format!("(?i){}", pattern)) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            warn!("Invalid pattern in signature {}: {}", signature, e);
+            None
+        }
+    }
+}
+
+fn match_value(pattern: &str, value: &str, signature: &str) -> Option<Option<String>> {
+    let re = compile(pattern, signature)?;
+    re.captures(value).map(|captures| {
+        captures
+            .name("version")
+            .map(|version| version.as_str().to_owned())
+    })
+}
+
+fn cookies(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|cookie| {
+            let pair = cookie.split(';').next().unwrap_or_default();
+            let mut parts = pair.splitn(2, '=');
+            let name = parts.next()?.trim();
+            let value = parts.next().unwrap_or_default().trim();
+            if name.is_empty() {
+                None
+            } else {
+                Some((name.to_owned(), value.to_owned()))
+            }
+        })
+        .collect()
+}
+
+pub fn detect(
+    signatures: &[Signature],
+    headers: &HeaderMap,
+    body: &str,
+    favicon: Option<i32>,
+) -> Vec<Technology> {
+    let cookies = cookies(headers);
+    let mut technologies: Vec<Technology> = Vec::new();
+
+    for signature in signatures {
+        let mut matches: Vec<Option<String>> = Vec::new();
+
+        for (name, pattern) in &signature.headers {
+            for value in headers.get_all(name.as_str()) {
+                if let Some(version) = value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| match_value(pattern, value, &signature.name))
+                {
+                    matches.push(version);
+                }
+            }
+        }
+
+        for (name, pattern) in &signature.cookies {
+            let name_re = match compile(name, &signature.name) {
+                Some(v) => v,
+                None => continue,
+            };
+            for (cookie, value) in cookies
+                .iter()
+                .filter(|(cookie, _)| name_re.is_match(cookie))
+            {
+                if let Some(version) = match_value(pattern, value, &signature.name) {
+                    trace!("{} matched cookie {}", signature.name, cookie);
+                    matches.push(version);
+                }
+            }
+        }
+
+        for pattern in &signature.body {
+            if let Some(version) = match_value(pattern, body, &signature.name) {
+                matches.push(version);
+            }
+        }
+
+        if let Some(hash) = favicon {
+            if signature.favicon.contains(&hash) {
+                matches.push(None);
+            }
+        }
+
+        if matches.is_empty() {
+            continue;
+        }
+
+        debug!("{} matched {} rules", signature.name, matches.len());
+        technologies.push(Technology {
+            name: signature.name.clone(),
+            version: matches.into_iter().flatten().next(),
+            extensions: signature.extensions.clone(),
+            wordlists: signature.wordlists.clone(),
+        });
+    }
+
+    technologies
+}
+
+fn fetch<C: Connect + Clone + Sync + 'static>(
+    client: &HttpClient<C>,
+    url: &str,
+    user_agent: &str,
+) -> impl Future<Item = (StatusCode, HeaderMap, Vec<u8>), Error = Error> {
+    let uri = match check_url(url) {
+        Ok(v) => v,
+        Err(e) => return future::Either::A(future::err(e)),
+    };
+    let request = RequestTemplate {
+        method: "GET".to_owned(),
+        uri,
+        headers: vec![("User-Agent".to_owned(), user_agent.to_owned())],
+        body: String::new(),
+    };
+
+    future::Either::B(
+        client
+            .send(&request, None)
+            .map(|response| (response.status, response.headers, response.body.to_vec())),
+    )
+}
+
+pub fn fingerprint<C: Connect + Clone + Sync + 'static>(
+    client: &HttpClient<C>,
+    url: &str,
+    user_agent: &str,
+    signatures: Vec<Signature>,
+) -> impl Future<Item = Vec<Technology>, Error = Error> {
+    let favicon_url = match url.parse::<hyper::Uri>() {
+        Ok(uri) => format!(
+            "{}://{}/favicon.ico",
+            uri.scheme_str().unwrap_or("http"),
+            uri.authority_part().map(|a| a.as_str()).unwrap_or_default()
+        ),
+        Err(_) => format!("{}/favicon.ico", url.trim_end_matches('/')),
+    };
+    let favicon = fetch(client, &favicon_url, user_agent).then(|response| {
+        future::ok(match response {
+            Ok((status, _, body)) if status.is_success() && !body.is_empty() => {
+                Some(favicon_hash(&body))
+            }
+            _ => None,
+        })
+    });
+
+    fetch(client, url, user_agent)
+        .join(favicon)
+        .map(move |((_, headers, body), favicon)| {
+            debug!("favicon hash {:?}", favicon);
+            detect(
+                &signatures,
+                &headers,
+                &String::from_utf8_lossy(&body),
+                favicon,
+            )
+        })
+}
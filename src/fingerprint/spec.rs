@@ -0,0 +1,52 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name fingerprint;
+
+    use hyper::{header, HeaderMap};
+
+    test murmur3_32() {
+        let murmur3_32 = crate::fingerprint::murmur3_32;
+        assert_eq!(0, murmur3_32(b"", 0));
+        assert_eq!(613153351, murmur3_32(b"hello", 0) as i32);
+        assert_eq!(-156908512, murmur3_32(b"foo", 0) as i32);
+    }
+
+    test favicon_hash() {
+        let favicon: Vec<u8> = (0..=255u8).chain(0..=255u8).collect();
+        assert_eq!(-1173581353, crate::fingerprint::favicon_hash(&favicon));
+    }
+
+    test detect() {
+        let signatures = crate::fingerprint::load_signatures(None).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::SERVER, "Microsoft-IIS/8.5".parse().unwrap());
+        headers.insert("X-Powered-By", "PHP/7.4.3".parse().unwrap());
+        headers.append(header::SET_COOKIE, "ASPSESSIONIDQQSRTCBA=abc; path=/".parse().unwrap());
+        let body = "<link rel='stylesheet' href='/wp-content/themes/a.css'>";
+
+        let technologies: Vec<(String, Option<String>)> =
+            crate::fingerprint::detect(&signatures, &headers, body, Some(81586312))
+                .into_iter()
+                .map(|technology| (technology.name, technology.version))
+                .collect();
+        assert_eq!(
+            vec![
+                ("IIS".to_owned(), Some("8.5".to_owned())),
+                ("ASP.NET".to_owned(), None),
+                ("PHP".to_owned(), Some("7.4.3".to_owned())),
+                ("WordPress".to_owned(), None),
+                ("Jenkins".to_owned(), None),
+            ],
+            technologies
+        );
+        assert!(crate::fingerprint::detect(&signatures, &HeaderMap::new(), "", None).is_empty());
+    }
+
+    test invalid_signatures() {
+        let path = std::env::temp_dir().join("rustbuster-invalid-signatures.json");
+        std::fs::write(&path, "[{\"name\": \"typo\"").unwrap();
+        let loaded = crate::fingerprint::load_signatures(path.to_str());
+        assert!(matches!(loaded, Err(crate::error::Error::InvalidOption { name: "signatures", .. })));
+    }
+}
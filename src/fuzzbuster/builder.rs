@@ -0,0 +1,254 @@
+use futures::{sync::mpsc, Stream};
+use std::sync::{mpsc::Sender, Arc};
+
+use super::{result_processor::SingleFuzzScanResult, CsrfPolicy, FuzzBuster, FuzzRequest};
+use crate::{
+    error::{check_url, Error},
+    matcher::expr::Expression,
+    scan::{self, ScanHandle, ScanStream},
+    scheduler::{host_of, FairScheduler},
+    session::{
+        auth::Credentials,
+        macros::{encode_placeholders, Macro},
+        HttpClient, JarScope, SessionConfig, SessionPool,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct FuzzScanBuilder {
+    fuzzbuster: FuzzBuster,
+    targets: Vec<String>,
+    max_per_host: usize,
+    session: Option<SessionConfig>,
+    pre_request: Option<Macro>,
+    csrf_policy: CsrfPolicy,
+    auth: Option<Credentials>,
+}
+
+pub struct FuzzScan {
+    client: HttpClient,
+    requests: Vec<(String, FuzzRequest)>,
+    n_threads: usize,
+    max_per_host: usize,
+    sessions: Option<Arc<SessionPool>>,
+    pre_request: Option<Arc<Macro>>,
+    csrf_policy: Arc<CsrfPolicy>,
+}
+
+impl FuzzScanBuilder {
+    pub fn new() -> Self {
+        FuzzScanBuilder {
+            fuzzbuster: FuzzBuster {
+                n_threads: 10,
+                ignore_certificate: false,
+                http_method: "GET".to_owned(),
+                http_body: "".to_owned(),
+                user_agent: "rustbuster".to_owned(),
+                http_headers: Vec::new(),
+                wordlist_paths: Vec::new(),
+                url: "".to_owned(),
+                csrf_url: None,
+                csrf_regex: None,
+                csrf_headers: None,
+            },
+            targets: Vec::new(),
+            max_per_host: 0,
+            session: None,
+            pre_request: None,
+            csrf_policy: CsrfPolicy::default(),
+            auth: None,
+        }
+    }
+
+    pub fn target(mut self, url: &str) -> Self {
+        self.targets.push(url.to_owned());
+        self
+    }
+
+    pub fn targets(mut self, urls: Vec<String>) -> Self {
+        self.targets.extend(urls);
+        self
+    }
+
+    pub fn wordlist(mut self, path: &str) -> Self {
+        self.fuzzbuster.wordlist_paths.push(path.to_owned());
+        self
+    }
+
+    pub fn threads(mut self, n_threads: usize) -> Self {
+        self.fuzzbuster.n_threads = n_threads;
+        self
+    }
+
+    pub fn max_per_host(mut self, max_per_host: usize) -> Self {
+        self.max_per_host = max_per_host;
+        self
+    }
+
+    pub fn ignore_certificate(mut self, ignore_certificate: bool) -> Self {
+        self.fuzzbuster.ignore_certificate = ignore_certificate;
+        self
+    }
+
+    pub fn method(mut self, http_method: &str) -> Self {
+        self.fuzzbuster.http_method = http_method.to_owned();
+        self
+    }
+
+    pub fn body(mut self, http_body: &str) -> Self {
+        self.fuzzbuster.http_body = http_body.to_owned();
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.fuzzbuster.user_agent = user_agent.to_owned();
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.fuzzbuster
+            .http_headers
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn csrf(mut self, url: &str, regex: &str) -> Self {
+        self.fuzzbuster.csrf_url = Some(url.to_owned());
+        self.fuzzbuster.csrf_regex = Some(regex.to_owned());
+        self
+    }
+
+    pub fn csrf_header(mut self, name: &str, value: &str) -> Self {
+        self.fuzzbuster
+            .csrf_headers
+            .get_or_insert_with(Vec::new)
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn csrf_reuse(mut self, reuse: usize) -> Self {
+        self.csrf_policy.reuse = reuse;
+        self
+    }
+
+    pub fn csrf_invalid(mut self, invalid: Expression) -> Self {
+        self.csrf_policy.invalid = Some(invalid);
+        self
+    }
+
+    pub fn auth(mut self, credentials: Credentials) -> Self {
+        self.auth = Some(credentials);
+        self
+    }
+
+    pub fn session(mut self, session: SessionConfig) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    pub fn pre_request(mut self, pre_request: Macro) -> Self {
+        self.pre_request = Some(pre_request);
+        self
+    }
+
+    pub fn build(self) -> Result<FuzzScan, Error> {
+        if self.targets.is_empty() {
+            return Err(Error::MissingOption("target"));
+        }
+        if self.fuzzbuster.wordlist_paths.is_empty() {
+            return Err(Error::MissingOption("wordlist"));
+        }
+        if self.csrf_policy.reuse == 0 && self.csrf_policy.invalid.is_none() {
+            return Err(Error::InvalidOption {
+                name: "csrf-reuse",
+                reason: "0 requires csrf-invalid".to_owned(),
+            });
+        }
+
+        let mut requests = Vec::new();
+        for target in &self.targets {
+            check_url(&encode_placeholders(target))?;
+            let mut fuzzbuster = self.fuzzbuster.clone();
+            fuzzbuster.url = target.to_owned();
+            requests.extend(
+                fuzzbuster
+                    .build_requests()?
+                    .into_iter()
+                    .map(|request| (target.to_owned(), request)),
+            );
+        }
+
+        let session = match self.session {
+            None if self.fuzzbuster.csrf_url.is_some() || self.pre_request.is_some() => {
+                Some(SessionConfig::new(JarScope::Worker))
+            }
+            session => session,
+        };
+
+        Ok(FuzzScan {
+            client: HttpClient::https(
+                self.fuzzbuster.n_threads,
+                self.fuzzbuster.ignore_certificate,
+            )?
+            .auth(self.auth),
+            requests,
+            n_threads: self.fuzzbuster.n_threads,
+            max_per_host: self.max_per_host,
+            sessions: session.map(|session| Arc::new(SessionPool::new(session))),
+            pre_request: self.pre_request.map(Arc::new),
+            csrf_policy: Arc::new(self.csrf_policy),
+        })
+    }
+}
+
+impl Default for FuzzScanBuilder {
+    fn default() -> Self {
+        FuzzScanBuilder::new()
+    }
+}
+
+impl FuzzScan {
+    pub fn builder() -> FuzzScanBuilder {
+        FuzzScanBuilder::new()
+    }
+
+    pub fn total(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn stream(self) -> Result<(ScanStream<SingleFuzzScanResult>, ScanHandle), Error> {
+        let (tx, rx) = mpsc::unbounded();
+        let total = self.requests.len();
+        let client = self.client;
+        let sessions = self.sessions;
+        let pre_request = self.pre_request;
+        let csrf_policy = self.csrf_policy;
+        let jobs = self
+            .requests
+            .into_iter()
+            .map(|(target, request)| (host_of(&request.uri.to_string()), (target, request)));
+        let scan = FairScheduler::new(
+            futures::stream::iter_ok(jobs),
+            move |(target, request)| {
+                FuzzBuster::make_request_future(
+                    tx.clone(),
+                    client.clone(),
+                    sessions.clone(),
+                    pre_request.clone(),
+                    csrf_policy.clone(),
+                    target,
+                    request,
+                )
+            },
+            self.n_threads,
+            self.max_per_host,
+        )
+        .for_each(Ok);
+
+        Ok(ScanStream::new(scan, rx, Some(total)))
+    }
+
+    pub fn run(self, tx: Sender<SingleFuzzScanResult>) -> Result<(), Error> {
+        scan::forward(self.stream()?.0, tx)
+    }
+}
@@ -1,27 +1,26 @@
-use futures::Stream;
-use hyper::{
-    client::HttpConnector,
-    rt::{self, Future},
-    Body, Client, Request, StatusCode,
-};
-use hyper_tls::{self, HttpsConnector};
+use futures::{future, sync::mpsc::UnboundedSender};
+use hyper::{rt::Future, StatusCode};
 use itertools::Itertools;
-use native_tls;
-use std::sync::mpsc::channel;
-use std::sync::mpsc::Sender;
-use std::thread;
+use regex::Regex;
+use std::sync::Arc;
 
+pub mod builder;
 pub mod result_processor;
 mod spec;
 pub mod utils;
 
-use result_processor::{FuzzScanProcessor, FuzzScanProcessorConfig, SingleFuzzScanResult};
-
-use std::{fs, time::SystemTime};
-
-use indicatif::{ProgressBar, ProgressStyle};
-
-use regex::Regex;
+use crate::{
+    error::{check_regex, check_url, read_file, Error},
+    matcher::{collect_headers, expr::Expression},
+    session::{
+        self,
+        macros::{
+            decode_placeholders, encode_placeholders, substitute, substitute_uri, Macro, Variables,
+        },
+        HttpClient, RequestTemplate, Response, Session, SessionPool,
+    },
+};
+use result_processor::SingleFuzzScanResult;
 
 #[derive(Debug, Clone)]
 pub struct FuzzBuster {
@@ -33,18 +32,35 @@ pub struct FuzzBuster {
     pub http_headers: Vec<(String, String)>,
     pub wordlist_paths: Vec<String>,
     pub url: String,
-    pub include_status_codes: Vec<String>,
-    pub ignore_status_codes: Vec<String>,
-    pub include_body: Vec<String>,
-    pub ignore_body: Vec<String>,
-    pub no_progress_bar: bool,
-    pub exit_on_connection_errors: bool,
-    pub output: String,
     pub csrf_url: Option<String>,
     pub csrf_regex: Option<String>,
     pub csrf_headers: Option<Vec<(String, String)>>,
 }
 
+#[derive(Debug, Clone)]
+pub struct CsrfPolicy {
+    pub reuse: usize,
+    pub invalid: Option<Expression>,
+}
+
+impl CsrfPolicy {
+    pub fn is_invalid(&self, response: &Response) -> bool {
+        match &self.invalid {
+            Some(invalid) => response.matches(invalid),
+            None => false,
+        }
+    }
+}
+
+impl Default for CsrfPolicy {
+    fn default() -> Self {
+        CsrfPolicy {
+            reuse: 1,
+            invalid: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FuzzRequest {
     pub uri: hyper::Uri,
@@ -59,277 +75,247 @@ pub struct FuzzRequest {
 }
 
 impl FuzzBuster {
-    pub fn run(self) {
-        let (tx, rx) = channel::<SingleFuzzScanResult>();
-        let mut tls_connector_builder = native_tls::TlsConnector::builder();
-        tls_connector_builder.danger_accept_invalid_certs(self.ignore_certificate);
-        let tls_connector = tls_connector_builder
-            .build()
-            .expect("TLS initialization failed");
-        let mut http_connector = HttpConnector::new(self.n_threads);
-        http_connector.enforce_http(false);
-        let https_connector = HttpsConnector::from((http_connector, tls_connector));
-        let client = Client::builder().build(https_connector);
-        let n_threads = self.n_threads;
-        let rp_config = FuzzScanProcessorConfig {
-            include: self.include_status_codes.clone(),
-            ignore: self.ignore_status_codes.clone(),
-            include_body: self.include_body.clone(),
-            ignore_body: self.ignore_body.clone(),
-        };
-        let requests = self.build_requests();
-        let mut current_numbers_of_request = 0;
-        let total_numbers_of_request = requests.len();
-        let start_time = SystemTime::now();
-        let mut result_processor = FuzzScanProcessor::new(rp_config);
-        let bar = if self.no_progress_bar {
-            ProgressBar::hidden()
-        } else {
-            ProgressBar::new(requests.len() as u64)
-        };
-        bar.set_draw_delta(100);
-        bar.set_style(ProgressStyle::default_bar()
-            .template("{spinner} [{elapsed_precise}] {bar:40.red/white} {pos:>7}/{len:7} ETA: {eta_precise} req/s: {msg}")
-            .progress_chars("#>-"));
-
-        let stream = futures::stream::iter_ok(requests)
-            .map(move |request| {
-                FuzzBuster::make_request_future(tx.clone(), client.clone(), request)
-            })
-            .buffer_unordered(n_threads)
-            .for_each(Ok)
-            .map_err(|err| eprintln!("Err {:?}", err));
-
-        let _ = thread::spawn(move || rt::run(stream));
-
-        while current_numbers_of_request != total_numbers_of_request {
-            current_numbers_of_request = current_numbers_of_request + 1;
-            bar.inc(1);
-            let seconds_from_start = start_time.elapsed().unwrap().as_millis() / 1000;
-            if seconds_from_start != 0 {
-                bar.set_message(
-                    &(current_numbers_of_request as u64 / seconds_from_start as u64).to_string(),
-                );
-            } else {
-                bar.set_message("warming up...")
-            }
-
-            let msg = match rx.recv() {
-                Ok(msg) => msg,
-                Err(_err) => {
-                    error!("{:?}", _err);
-                    break;
-                }
-            };
-
-            match &msg.error {
-                Some(e) => {
-                    error!("{} - {:?}", msg.url, e);
-                    if current_numbers_of_request == 1 || self.exit_on_connection_errors {
-                        warn!("Check connectivity to the target");
-                        break;
-                    }
-
-                    continue;
-                }
-                None => (),
-            }
-
-            let was_added = result_processor.maybe_add_result(msg.clone());
-            if was_added {
-                let mut extra = msg.extra.unwrap_or("".to_owned());
-
-                if !extra.is_empty() {
-                    extra = format!("\n\t\t\t\t\t\t=> {}", extra)
-                }
-
-                let n_tabs = match msg.status.len() / 8 {
-                    3 => 1,
-                    2 => 2,
-                    1 => 3,
-                    0 => 4,
-                    _ => 0,
-                };
-
-                if self.no_progress_bar {
-                    println!(
-                        "{}\t{}{}{} ({:?}){}",
-                        msg.method,
-                        msg.status,
-                        "\t".repeat(n_tabs),
-                        msg.url,
-                        msg.payload,
-                        extra,
-                    );
-                } else {
-                    bar.println(format!(
-                        "{}\t{}{}{}\n\t\t\t\t\t\t=> PAYLOAD: {:?}{}",
-                        msg.method,
-                        msg.status,
-                        "\t".repeat(n_tabs),
-                        msg.url,
-                        msg.payload,
-                        extra,
-                    ));
-                }
-            }
-        }
-
-        bar.finish();
-        println!("{}", crate::banner::ending_time());
-
-        if !self.output.is_empty() {
-            result_processor.save_fuzz_results(&self.output);
-        }
-    }
-
     fn make_request_future(
-        tx: Sender<SingleFuzzScanResult>,
-        client: Client<HttpsConnector<HttpConnector>>,
+        tx: UnboundedSender<SingleFuzzScanResult>,
+        client: HttpClient,
+        sessions: Option<Arc<SessionPool>>,
+        pre_request: Option<Arc<Macro>>,
+        csrf_policy: Arc<CsrfPolicy>,
+        target_url: String,
         request: FuzzRequest,
-    ) -> impl Future<Item = (), Error = ()> {
+    ) -> impl Future<Item = (), Error = Error> {
         let tx_err = tx.clone();
-        let tx_err2 = tx.clone();
         let mut target = SingleFuzzScanResult {
-            url: request.uri.to_string(),
+            target: target_url,
+            url: decode_placeholders(&request.uri.to_string()),
             method: request.http_method.clone(),
             status: StatusCode::default().to_string(),
             payload: request.payload.clone(),
             body: request.http_body.clone(),
             error: None,
             extra: None,
+            length: None,
+            time: None,
+            headers: Vec::new(),
         };
         let mut target_err = target.clone();
-        let mut target_err2 = target.clone();
-        let mut request_builder = Request::builder();
-
-        for header_tuple in &request.http_headers {
-            request_builder.header(header_tuple.0.as_str(), header_tuple.1.as_str());
-        }
 
-        let csrf_fut = match &request.csrf_uri {
-            None => futures::future::Either::A(futures::future::ok::<
-                (Option<String>, FuzzRequest),
-                _,
-            >((None, request))),
-            Some(uri) => {
-                let mut csrf_request_builder = Request::builder();
-
-                match &request.csrf_headers {
-                    None => (),
-                    Some(v) => {
-                        for header_tuple in v.iter() {
-                            csrf_request_builder
-                                .header(header_tuple.0.as_str(), header_tuple.1.as_str());
-                        }
-                    }
+        let csrf_regex = match &request.csrf_uri {
+            None => None,
+            Some(_) => match request
+                .csrf_regex
+                .as_ref()
+                .ok_or(Error::MissingOption("CSRF regex"))
+                .and_then(|regex| check_regex(regex))
+            {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    target_err.error = Some(e.to_string());
+                    let _ = tx_err.unbounded_send(target_err);
+                    return future::Either::A(future::ok(()));
                 }
+            },
+        };
 
-                let hyper_request = csrf_request_builder
-                    .header("User-Agent", &request.user_agent[..])
-                    .method(hyper::Method::GET)
-                    .uri(uri)
-                    .body(Body::from(""))
-                    .expect("Request builder");
-                let csrf_regex = request.csrf_regex.clone();
-                let csrf_regex = &csrf_regex.expect("Missing regex");
-                match Regex::new(&csrf_regex) {
-                    Ok(re) => futures::future::Either::B(
-                        client
-                            .request(hyper_request)
-                            .and_then(|res| res.into_body().concat2())
-                            .join3(futures::future::ok(re), futures::future::ok(request))
-                            .and_then(|(body, re, request)| {
-                                let vec = body.iter().cloned().collect();
-                                let body = String::from_utf8(vec).unwrap();
-                                match re.captures_iter(&body).take(1).next() {
-                                    Some(v) => Ok((Some(v[1].to_owned()), request)),
-                                    None => {
-                                        warn!("no match for csrf regex");
-                                        Ok((None, request))
-                                    }
-                                }
-                            }),
-                    ),
-                    Err(e) => {
-                        error!("Invalid regex: {}", e);
-                        std::process::exit(-1);
-                    }
+        let exchange_client = client.clone();
+        future::Either::B(
+            session::send(client, sessions, move |session| {
+                FuzzBuster::make_attempt(
+                    session,
+                    &exchange_client,
+                    &request,
+                    csrf_regex.clone(),
+                    pre_request.clone(),
+                    csrf_policy.clone(),
+                )
+            })
+            .and_then(move |response| {
+                target.status = response.status.to_string();
+                if response.status.is_redirection() {
+                    target.extra = response
+                        .headers
+                        .get("Location")
+                        .and_then(|location| location.to_str().ok())
+                        .map(|location| location.to_owned());
                 }
+                target.headers = collect_headers(&response.headers);
+                target.length = Some(response.body.len());
+                target.time = Some(response.time.as_millis() as u64);
+                target.body = String::from_utf8_lossy(&response.body).into_owned();
+                let _ = tx.unbounded_send(target);
+                Ok(())
+            })
+            .or_else(move |e| {
+                target_err.error = Some(e.to_string());
+                let _ = tx_err.unbounded_send(target_err);
+                Ok(())
+            }),
+        )
+    }
+
+    fn make_attempt(
+        session: &Session,
+        client: &HttpClient,
+        request: &FuzzRequest,
+        csrf_regex: Option<Regex>,
+        pre_request: Option<Arc<Macro>>,
+        csrf_policy: Arc<CsrfPolicy>,
+    ) -> impl Future<Item = Response, Error = Error> {
+        let macro_fut = match pre_request {
+            Some(pre_request) => {
+                future::Either::B(pre_request.run(session, client, &request.user_agent))
             }
+            None => future::Either::A(future::ok(Variables::new())),
         };
 
-        csrf_fut
-            .and_then(move |(csrf, request)| {
-                let request = match csrf {
-                    Some(v) => {
-                        trace!("csrf: {}", v);
-                        FuzzBuster::replace_csrf(request, v)
-                    }
-                    _ => request,
-                };
+        let session = session.clone();
+        let client = client.clone();
+        let request = request.clone();
+        macro_fut.and_then(move |variables| {
+            let request = FuzzBuster::replace_variables(request, &variables);
+            FuzzBuster::exchange_with_csrf(session, client, request, csrf_regex, csrf_policy, true)
+        })
+    }
 
-                let request = request_builder
-                    .header("User-Agent", &request.user_agent[..])
-                    .method(&request.http_method[..])
-                    .uri(&request.uri)
-                    .body(Body::from(request.http_body.clone()))
-                    .expect("Request builder");
+    pub fn exchange_with_csrf(
+        session: Session,
+        client: HttpClient,
+        request: FuzzRequest,
+        csrf_regex: Option<Regex>,
+        csrf_policy: Arc<CsrfPolicy>,
+        reuse: bool,
+    ) -> Box<dyn Future<Item = Response, Error = Error> + Send> {
+        let cached = match &csrf_regex {
+            Some(_) if reuse => session.reuse_token(csrf_policy.reuse),
+            _ => None,
+        };
+        let reused = cached.is_some();
+        let csrf_fut = match cached {
+            Some(csrf) => future::Either::A(future::ok(Some(csrf))),
+            None => future::Either::B(FuzzBuster::fetch_csrf(
+                &session,
+                &client,
+                &request,
+                csrf_regex.clone(),
+            )),
+        };
 
-                client
-                    .request(request)
-                    .and_then(move |res| {
-                        let status = res.status();
-                        target.status = status.to_string();
-                        if status.is_redirection() {
-                            target.extra = Some(
-                                res.headers()
-                                    .get("Location")
-                                    .unwrap()
-                                    .to_str()
-                                    .unwrap()
-                                    .to_owned(),
-                            );
+        let retry = (session.clone(), client.clone(), request.clone());
+        Box::new(
+            csrf_fut
+                .and_then(move |csrf| {
+                    let request = match csrf {
+                        Some(v) => {
+                            trace!("csrf: {}", v);
+                            if !reused {
+                                session.store_token(&v);
+                            }
+                            match FuzzBuster::replace_csrf(request, v) {
+                                Ok(v) => v,
+                                Err(e) => return future::Either::A(future::err(e)),
+                            }
                         }
+                        _ => request,
+                    };
+
+                    let mut headers = request.http_headers;
+                    headers.push(("User-Agent".to_owned(), request.user_agent));
+                    future::Either::B(session.exchange(
+                        &client,
+                        &RequestTemplate {
+                            method: request.http_method,
+                            uri: request.uri,
+                            headers,
+                            body: request.http_body,
+                        },
+                    ))
+                })
+                .and_then(move |response| {
+                    let (session, client, request) = retry;
+                    if csrf_regex.is_none() || !csrf_policy.is_invalid(&response) {
+                        return future::Either::A(future::ok(response));
+                    }
 
-                        futures::future::ok(target).join(res.into_body().concat2())
-                    })
-                    .and_then(move |(target, body)| {
-                        let mut target = target;
-                        let vec = body.iter().cloned().collect();
-                        let body = String::from_utf8(vec).unwrap();
-                        target.body = body;
-                        tx.send(target.clone()).unwrap();
-                        Ok(())
-                    })
-                    .or_else(move |e| {
-                        target_err.error = Some(e.to_string());
-                        tx_err.send(target_err).unwrap_or_else(|_| ());
-                        Ok(())
-                    })
-            })
-            .or_else(move |e| {
-                target_err2.error = Some(e.to_string());
-                tx_err2.send(target_err2).unwrap_or_else(|_| ());
-                Ok(())
-            })
+                    session.invalidate_token();
+                    if !reused {
+                        warn!("the csrf token was rejected right after being fetched");
+                        return future::Either::A(future::ok(response));
+                    }
+
+                    debug!("csrf token rejected, fetching a new one");
+                    future::Either::B(FuzzBuster::exchange_with_csrf(
+                        session,
+                        client,
+                        request,
+                        csrf_regex,
+                        csrf_policy,
+                        false,
+                    ))
+                }),
+        )
+    }
+
+    fn fetch_csrf(
+        session: &Session,
+        client: &HttpClient,
+        request: &FuzzRequest,
+        csrf_regex: Option<Regex>,
+    ) -> impl Future<Item = Option<String>, Error = Error> {
+        let (uri, re) = match (&request.csrf_uri, csrf_regex) {
+            (Some(uri), Some(re)) => (uri, re),
+            _ => return future::Either::A(future::ok(None)),
+        };
+        let mut headers = request.csrf_headers.clone().unwrap_or_default();
+        headers.push(("User-Agent".to_owned(), request.user_agent.clone()));
+        let csrf_request = RequestTemplate {
+            method: "GET".to_owned(),
+            uri: uri.clone(),
+            headers,
+            body: String::new(),
+        };
+
+        future::Either::B(
+            session
+                .exchange(client, &csrf_request)
+                .map(move |response| {
+                    let body = String::from_utf8_lossy(&response.body);
+                    match re.captures_iter(&body).take(1).next() {
+                        Some(v) => Some(v[1].to_owned()),
+                        None => {
+                            warn!("no match for csrf regex");
+                            None
+                        }
+                    }
+                }),
+        )
     }
 
-    pub fn build_requests(&self) -> Vec<FuzzRequest> {
+    pub fn build_requests(&self) -> Result<Vec<FuzzRequest>, Error> {
         debug!("building requests");
         let mut requests: Vec<FuzzRequest> = Vec::new();
-        let wordlists_iter = self
+        let csrf_uri = match &self.csrf_url {
+            Some(csrf_url) => {
+                let regex = self
+                    .csrf_regex
+                    .as_ref()
+                    .ok_or(Error::MissingOption("CSRF regex"))?;
+                check_regex(regex)?;
+                Some(check_url(csrf_url)?)
+            }
+            None => None,
+        };
+        let wordlists = self
             .wordlist_paths
             .iter()
             .map(|wordlist| {
-                fs::read_to_string(wordlist)
-                    .expect("Something went wrong reading the wordlist file")
+                Ok(read_file(wordlist)?
                     .lines()
                     .filter(|word| !word.starts_with('#') && !word.starts_with(' '))
                     .map(|x| x.to_owned())
-                    .collect::<Vec<String>>()
+                    .collect::<Vec<String>>())
             })
-            .multi_cartesian_product();
+            .collect::<Result<Vec<Vec<String>>, Error>>()?;
+        let wordlists_iter = wordlists.into_iter().multi_cartesian_product();
 
         for words in wordlists_iter {
             let mut url = self.url.clone();
@@ -357,63 +343,56 @@ impl FuzzBuster {
                 }
             }
 
-            match url.parse::<hyper::Uri>() {
-                Ok(uri) => match &self.csrf_url {
-                    Some(csrf_url) => match csrf_url.parse::<hyper::Uri>() {
-                        Ok(csrf_uri) => {
-                            requests.push(FuzzRequest {
-                                http_body,
-                                uri,
-                                http_headers,
-                                payload,
-                                user_agent: self.user_agent.clone(),
-                                http_method: self.http_method.clone(),
-                                csrf_uri: Some(csrf_uri),
-                                csrf_regex: self.csrf_regex.to_owned(),
-                                csrf_headers: self.csrf_headers.clone(),
-                            });
-                        }
-                        Err(e) => {
-                            debug!("CSRF URI: {}", e);
-                        }
-                    },
-                    None => {
-                        requests.push(FuzzRequest {
-                            http_body,
-                            uri,
-                            http_headers,
-                            payload,
-                            user_agent: self.user_agent.clone(),
-                            http_method: self.http_method.clone(),
-                            csrf_uri: None,
-                            csrf_regex: None,
-                            csrf_headers: None,
-                        });
-                    }
-                },
+            match encode_placeholders(&url).parse::<hyper::Uri>() {
+                Ok(uri) => requests.push(FuzzRequest {
+                    http_body,
+                    uri,
+                    http_headers,
+                    payload,
+                    user_agent: self.user_agent.clone(),
+                    http_method: self.http_method.clone(),
+                    csrf_uri: csrf_uri.clone(),
+                    csrf_regex: csrf_uri.as_ref().and(self.csrf_regex.clone()),
+                    csrf_headers: csrf_uri.as_ref().and(self.csrf_headers.clone()),
+                }),
                 Err(e) => {
                     debug!("URI: {}", e);
                 }
             }
         }
 
-        requests
+        Ok(requests)
     }
 
-    pub fn replace_csrf(request: FuzzRequest, csrf: String) -> FuzzRequest {
+    pub fn replace_variables(request: FuzzRequest, variables: &Variables) -> FuzzRequest {
+        if variables.is_empty() {
+            return request;
+        }
+
         let mut p = request;
-        p.uri = p
-            .uri
-            .to_string()
-            .replace("CSRFCSRF", &csrf)
-            .parse::<hyper::Uri>()
-            .expect("replace csrf in uri");
+        p.uri = substitute_uri(&p.uri, variables);
+        for (header, value) in p.http_headers.iter_mut() {
+            *header = substitute(header, variables);
+            *value = substitute(value, variables);
+        }
+
+        p.http_body = substitute(&p.http_body, variables);
+        p
+    }
+
+    pub fn replace_csrf(request: FuzzRequest, csrf: String) -> Result<FuzzRequest, Error> {
+        let mut p = request;
+        let uri = p.uri.to_string().replace("CSRFCSRF", &csrf);
+        p.uri = uri.parse::<hyper::Uri>().map_err(|e| Error::InvalidUrl {
+            url: uri.clone(),
+            reason: e.to_string(),
+        })?;
         for (header, value) in p.http_headers.iter_mut() {
             *header = header.replace("CSRFCSRF", &csrf);
             *value = value.replace("CSRFCSRF", &csrf);
         }
 
         p.http_body = p.http_body.replace("CSRFCSRF", &csrf);
-        p
+        Ok(p)
     }
 }
@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::Write, path::Path, str};
+use std::{fs::File, io::Write, path::Path, str, time::Duration};
+
+use crate::matcher::{find_header, Matchable, ResultFilter};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SingleFuzzScanResult {
+    pub target: String,
     pub url: String,
     pub method: String,
     pub status: String,
@@ -10,68 +13,49 @@ pub struct SingleFuzzScanResult {
     pub extra: Option<String>,
     pub payload: Vec<String>,
     pub body: String,
+    pub length: Option<usize>,
+    pub time: Option<u64>,
+    pub headers: Vec<(String, String)>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct FuzzScanProcessorConfig {
-    pub include: Vec<String>,
-    pub ignore: Vec<String>,
-    pub include_body: Vec<String>,
-    pub ignore_body: Vec<String>,
+impl Matchable for SingleFuzzScanResult {
+    fn status(&self) -> Option<&str> {
+        Some(&self.status)
+    }
+
+    fn length(&self) -> Option<usize> {
+        self.length
+    }
+
+    fn body(&self) -> Option<&str> {
+        Some(&self.body)
+    }
+
+    fn time(&self) -> Option<Duration> {
+        self.time.map(Duration::from_millis)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FuzzScanProcessor {
     pub results: Vec<SingleFuzzScanResult>,
-    config: FuzzScanProcessorConfig,
+    filter: ResultFilter<SingleFuzzScanResult>,
 }
 
 impl FuzzScanProcessor {
-    pub fn new(config: FuzzScanProcessorConfig) -> Self {
+    pub fn new(filter: ResultFilter<SingleFuzzScanResult>) -> Self {
         FuzzScanProcessor {
             results: Vec::<SingleFuzzScanResult>::new(),
-            config,
+            filter,
         }
     }
 
     pub fn maybe_add_result(&mut self, res: SingleFuzzScanResult) -> bool {
         trace!("{:?}", res);
-
-        if self.config.ignore_body.len() != 0 {
-            for ignore in &self.config.ignore_body {
-                if res.body.contains(ignore) {
-                    return false;
-                }
-            }
-        }
-
-        if self.config.include_body.len() != 0 {
-            for include in &self.config.include_body {
-                if res.body.contains(include) {
-                    self.results.push(res);
-                    return true;
-                }
-            }
-        }
-
-        if self.config.ignore.len() != 0 {
-            for code in &self.config.ignore {
-                if res.status.starts_with(code) {
-                    return false;
-                }
-            }
-        }
-
-        if self.config.include.len() != 0 {
-            for code in &self.config.include {
-                if res.status.starts_with(code) {
-                    self.results.push(res);
-                    return true;
-                }
-            }
-        }
-
-        if self.config.include.len() == 0 && self.config.include_body.len() == 0 {
+        if self.filter.is_match(&res) {
             self.results.push(res);
             return true;
         }
@@ -14,13 +14,6 @@ test_suite! {
                 http_headers: vec![("Header".to_owned(), "Value".to_owned())],
                 wordlist_paths: vec!["./examples/wordlist_short".to_owned()],
                 url: "http://localhost/FUZZ".to_owned(),
-                include_status_codes: vec![],
-                ignore_status_codes: vec!["404".to_owned()],
-                include_body: vec![],
-                ignore_body: vec![],
-                no_progress_bar: true,
-                exit_on_connection_errors: false,
-                output: "".to_owned(),
                 csrf_url: None,
                 csrf_regex: None,
                 csrf_headers: None,
@@ -39,13 +32,6 @@ test_suite! {
                 http_headers: vec![("Header".to_owned(), "Value".to_owned())],
                 wordlist_paths: vec!["./examples/wordlist_short".to_owned(), "./examples/wordlist_short".to_owned()],
                 url: "http://localhost/FUZZ/FUZZ".to_owned(),
-                include_status_codes: vec![],
-                ignore_status_codes: vec!["404".to_owned()],
-                include_body: vec![],
-                ignore_body: vec![],
-                no_progress_bar: true,
-                exit_on_connection_errors: false,
-                output: "".to_owned(),
                 csrf_url: None,
                 csrf_regex: None,
                 csrf_headers: None,
@@ -64,13 +50,6 @@ test_suite! {
                 http_headers: vec![("Header".to_owned(), "FUZZ".to_owned())],
                 wordlist_paths: vec!["./examples/wordlist_short".to_owned()],
                 url: "http://localhost/".to_owned(),
-                include_status_codes: vec![],
-                ignore_status_codes: vec!["404".to_owned()],
-                include_body: vec![],
-                ignore_body: vec![],
-                no_progress_bar: true,
-                exit_on_connection_errors: false,
-                output: "".to_owned(),
                 csrf_url: None,
                 csrf_regex: None,
                 csrf_headers: None,
@@ -89,13 +68,6 @@ test_suite! {
                 http_headers: vec![("FUZZ".to_owned(), "FUZZ".to_owned())],
                 wordlist_paths: vec!["./examples/wordlist_short".to_owned(), "./examples/wordlist_short".to_owned()],
                 url: "http://localhost/".to_owned(),
-                include_status_codes: vec![],
-                ignore_status_codes: vec!["404".to_owned()],
-                include_body: vec![],
-                ignore_body: vec![],
-                no_progress_bar: true,
-                exit_on_connection_errors: false,
-                output: "".to_owned(),
                 csrf_url: None,
                 csrf_regex: None,
                 csrf_headers: None,
@@ -114,13 +86,6 @@ test_suite! {
                 http_headers: vec![("Header".to_owned(), "Value".to_owned())],
                 wordlist_paths: vec!["./examples/wordlist_short".to_owned()],
                 url: "http://localhost/".to_owned(),
-                include_status_codes: vec![],
-                ignore_status_codes: vec!["404".to_owned()],
-                include_body: vec![],
-                ignore_body: vec![],
-                no_progress_bar: true,
-                exit_on_connection_errors: false,
-                output: "".to_owned(),
                 csrf_url: None,
                 csrf_regex: None,
                 csrf_headers: None,
@@ -139,13 +104,6 @@ test_suite! {
                 http_headers: vec![("Header".to_owned(), "Value".to_owned())],
                 wordlist_paths: vec!["./examples/wordlist_short".to_owned(), "./examples/wordlist_short".to_owned()],
                 url: "http://localhost/".to_owned(),
-                include_status_codes: vec![],
-                ignore_status_codes: vec!["404".to_owned()],
-                include_body: vec![],
-                ignore_body: vec![],
-                no_progress_bar: true,
-                exit_on_connection_errors: false,
-                output: "".to_owned(),
                 csrf_url: None,
                 csrf_regex: None,
                 csrf_headers: None,
@@ -211,7 +169,7 @@ test_suite! {
     }
 
     test build_requests_fuzz_url_single(fuzzbuster_url_single()) {
-        let requests = fuzzbuster_url_single.val.build_requests();
+        let requests = fuzzbuster_url_single.val.build_requests().unwrap();
         let expected: Vec<crate::fuzzbuster::FuzzRequest> = vec![
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/1".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "Value".to_owned())], http_body: "body".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/2".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "Value".to_owned())], http_body: "body".to_owned(), user_agent: "ua".to_owned(), payload: vec!["2".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
@@ -220,7 +178,7 @@ test_suite! {
     }
 
     test build_requests_fuzz_url_multiple(fuzzbuster_url_multiple()) {
-        let requests = fuzzbuster_url_multiple.val.build_requests();
+        let requests = fuzzbuster_url_multiple.val.build_requests().unwrap();
         let expected: Vec<crate::fuzzbuster::FuzzRequest> = vec![
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/1/1".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "Value".to_owned())], http_body: "body".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned(), "1".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/1/2".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "Value".to_owned())], http_body: "body".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned(), "2".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
@@ -231,7 +189,7 @@ test_suite! {
     }
 
     test build_requests_fuzz_header_single(fuzzbuster_header_single()) {
-        let requests = fuzzbuster_header_single.val.build_requests();
+        let requests = fuzzbuster_header_single.val.build_requests().unwrap();
         let expected: Vec<crate::fuzzbuster::FuzzRequest> = vec![
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "1".to_owned())], http_body: "body".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "2".to_owned())], http_body: "body".to_owned(), user_agent: "ua".to_owned(), payload: vec!["2".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
@@ -240,7 +198,7 @@ test_suite! {
     }
 
     test build_requests_fuzz_header_multiple(fuzzbuster_header_multiple()) {
-        let requests = fuzzbuster_header_multiple.val.build_requests();
+        let requests = fuzzbuster_header_multiple.val.build_requests().unwrap();
         let expected: Vec<crate::fuzzbuster::FuzzRequest> = vec![
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("1".to_owned(), "1".to_owned())], http_body: "body".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned(), "1".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("1".to_owned(), "2".to_owned())], http_body: "body".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned(), "2".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
@@ -251,7 +209,7 @@ test_suite! {
     }
 
     test build_requests_fuzz_body_single(fuzzbuster_body_single()) {
-        let requests = fuzzbuster_body_single.val.build_requests();
+        let requests = fuzzbuster_body_single.val.build_requests().unwrap();
         let expected: Vec<crate::fuzzbuster::FuzzRequest> = vec![
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "Value".to_owned())], http_body: "1".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "Value".to_owned())], http_body: "2".to_owned(), user_agent: "ua".to_owned(), payload: vec!["2".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
@@ -260,7 +218,7 @@ test_suite! {
     }
 
     test build_requests_fuzz_body_multiple(fuzzbuster_body_multiple()) {
-        let requests = fuzzbuster_body_multiple.val.build_requests();
+        let requests = fuzzbuster_body_multiple.val.build_requests().unwrap();
         let expected: Vec<crate::fuzzbuster::FuzzRequest> = vec![
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "Value".to_owned())], http_body: "1:1".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned(), "1".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
             crate::fuzzbuster::FuzzRequest { uri: "http://localhost/".parse::<hyper::Uri>().unwrap(), http_method: "GET".to_owned(), http_headers: vec![("Header".to_owned(), "Value".to_owned())], http_body: "1:2".to_owned(), user_agent: "ua".to_owned(), payload: vec!["1".to_owned(), "2".to_owned()], csrf_uri: None, csrf_regex: None, csrf_headers: None },
@@ -278,20 +236,74 @@ test_suite! {
 
     test replace_csrf_body(fuzzrequest_csrf_body) {
         let request = fuzzrequest_csrf_body.val;
-        let actual = crate::fuzzbuster::FuzzBuster::replace_csrf(request, "VALUE".to_owned());
+        let actual = crate::fuzzbuster::FuzzBuster::replace_csrf(request, "VALUE".to_owned()).unwrap();
         assert_eq!("VALUE", actual.http_body);
     }
 
     test replace_csrf_header(fuzzrequest_csrf_header) {
         let request = fuzzrequest_csrf_header.val;
-        let actual = crate::fuzzbuster::FuzzBuster::replace_csrf(request, "VALUE".to_owned());
+        let actual = crate::fuzzbuster::FuzzBuster::replace_csrf(request, "VALUE".to_owned()).unwrap();
         let expected = vec![("X-CSRF-Token".to_owned(), "VALUE".to_owned())];
         assert_eq!(expected, actual.http_headers);
     }
 
     test replace_csrf_url(fuzzrequest_csrf_url) {
-        let request = fuzzrequest_csrf_url.val;
-        let actual = crate::fuzzbuster::FuzzBuster::replace_csrf(request, "VALUE".to_owned());
+        let request = fuzzrequest_csrf_url.val.clone();
+        let actual = crate::fuzzbuster::FuzzBuster::replace_csrf(request, "VALUE".to_owned()).unwrap();
         assert_eq!("/VALUE", actual.uri.path());
+
+        let request = fuzzrequest_csrf_url.val;
+        let invalid = crate::fuzzbuster::FuzzBuster::replace_csrf(request, "VAL UE".to_owned());
+        assert!(matches!(invalid, Err(crate::error::Error::InvalidUrl { .. })));
+    }
+
+    test build_scan_errors() {
+        use crate::{error::Error, fuzzbuster::builder::FuzzScan};
+
+        let scan = FuzzScan::builder()
+            .target("http://localhost/FUZZ")
+            .target("http://127.0.0.1/FUZZ")
+            .wordlist("./examples/wordlist_short")
+            .build();
+        assert_eq!(4, scan.unwrap().total());
+
+        let scan = FuzzScan::builder().wordlist("./examples/wordlist_short").build();
+        assert!(matches!(scan, Err(Error::MissingOption("target"))));
+
+        let scan = FuzzScan::builder()
+            .target("localhost/FUZZ")
+            .wordlist("./examples/wordlist_short")
+            .build();
+        assert!(matches!(scan, Err(Error::InvalidUrl { .. })));
+
+        let scan = FuzzScan::builder()
+            .target("http://localhost/FUZZ")
+            .wordlist("./examples/missing_wordlist")
+            .build();
+        assert!(matches!(scan, Err(Error::Read { .. })));
+
+        let scan = FuzzScan::builder()
+            .target("http://localhost/FUZZ")
+            .wordlist("./examples/wordlist_short")
+            .csrf("http://localhost/csrf", "(")
+            .build();
+        assert!(matches!(scan, Err(Error::InvalidRegex { .. })));
+
+        let scan = FuzzScan::builder()
+            .target("http://localhost/FUZZ")
+            .wordlist("./examples/wordlist_short")
+            .csrf("http://localhost/csrf", "(\\w+)")
+            .csrf_reuse(0)
+            .build();
+        assert!(matches!(scan, Err(Error::InvalidOption { name: "csrf-reuse", .. })));
+
+        let scan = FuzzScan::builder()
+            .target("http://localhost/FUZZ")
+            .wordlist("./examples/wordlist_short")
+            .csrf("http://localhost/csrf", "(\\w+)")
+            .csrf_reuse(0)
+            .csrf_invalid(crate::matcher::expr::Expression::parse("status == 403").unwrap())
+            .build();
+        assert!(scan.is_ok());
     }
 }
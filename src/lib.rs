@@ -1,8 +1,19 @@
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate lazy_static;
 
 pub mod banner;
 pub mod dirbuster;
 pub mod dnsbuster;
+pub mod error;
+pub mod fingerprint;
 pub mod fuzzbuster;
+pub mod loginbuster;
+pub mod matcher;
+pub mod probebuster;
+pub mod scan;
+pub mod scheduler;
+pub mod session;
+pub mod tildebuster;
 pub mod vhostbuster;
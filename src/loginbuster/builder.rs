@@ -0,0 +1,332 @@
+use futures::{sync::mpsc, Stream};
+use hyper::rt::Future;
+use std::{
+    sync::{mpsc::Sender, Arc},
+    time::Duration,
+};
+
+use super::{
+    result_processor::SingleLoginScanResult,
+    utils::{parse_combos, read_lines, spray},
+    LoginBuster, LoginContext, LoginType, DEFAULT_LOCKOUT,
+};
+use crate::{
+    error::{check_regex, check_url, Error},
+    matcher::expr::Expression,
+    scan::{self, ScanHandle, ScanStream},
+    scheduler::{host_of, FairScheduler},
+    session::{macros::encode_placeholders, HttpClient},
+};
+
+#[derive(Debug, Clone)]
+pub struct LoginScanBuilder {
+    loginbuster: LoginBuster,
+    http_method: Option<String>,
+    targets: Vec<String>,
+    max_per_host: usize,
+    usernames: Vec<String>,
+    passwords: Vec<String>,
+    combos: Vec<(String, String)>,
+    usernames_paths: Vec<String>,
+    passwords_paths: Vec<String>,
+    combos_paths: Vec<String>,
+}
+
+pub struct LoginScan {
+    context: Arc<LoginContext>,
+    targets: Vec<String>,
+    credentials: Vec<(String, String)>,
+    max_per_host: usize,
+}
+
+impl LoginScanBuilder {
+    pub fn new() -> Self {
+        LoginScanBuilder {
+            loginbuster: LoginBuster {
+                n_threads: 10,
+                ignore_certificate: false,
+                login_type: LoginType::Form,
+                http_method: "POST".to_owned(),
+                http_body: "".to_owned(),
+                user_agent: "rustbuster".to_owned(),
+                http_headers: Vec::new(),
+                csrf_url: None,
+                csrf_regex: None,
+                csrf_headers: None,
+                success: None,
+                lockout: Expression::parse(DEFAULT_LOCKOUT).expect("valid lockout expression"),
+                lockout_pause: Duration::from_secs(60),
+                similarity_threshold: 0.9,
+            },
+            http_method: None,
+            targets: Vec::new(),
+            max_per_host: 0,
+            usernames: Vec::new(),
+            passwords: Vec::new(),
+            combos: Vec::new(),
+            usernames_paths: Vec::new(),
+            passwords_paths: Vec::new(),
+            combos_paths: Vec::new(),
+        }
+    }
+
+    pub fn target(mut self, url: &str) -> Self {
+        self.targets.push(url.to_owned());
+        self
+    }
+
+    pub fn targets(mut self, urls: Vec<String>) -> Self {
+        self.targets.extend(urls);
+        self
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.usernames.push(username.to_owned());
+        self
+    }
+
+    pub fn password(mut self, password: &str) -> Self {
+        self.passwords.push(password.to_owned());
+        self
+    }
+
+    pub fn combo(mut self, username: &str, password: &str) -> Self {
+        self.combos.push((username.to_owned(), password.to_owned()));
+        self
+    }
+
+    pub fn usernames(mut self, path: &str) -> Self {
+        self.usernames_paths.push(path.to_owned());
+        self
+    }
+
+    pub fn passwords(mut self, path: &str) -> Self {
+        self.passwords_paths.push(path.to_owned());
+        self
+    }
+
+    pub fn combos(mut self, path: &str) -> Self {
+        self.combos_paths.push(path.to_owned());
+        self
+    }
+
+    pub fn login_type(mut self, login_type: LoginType) -> Self {
+        self.loginbuster.login_type = login_type;
+        self
+    }
+
+    pub fn threads(mut self, n_threads: usize) -> Self {
+        self.loginbuster.n_threads = n_threads;
+        self
+    }
+
+    pub fn max_per_host(mut self, max_per_host: usize) -> Self {
+        self.max_per_host = max_per_host;
+        self
+    }
+
+    pub fn ignore_certificate(mut self, ignore_certificate: bool) -> Self {
+        self.loginbuster.ignore_certificate = ignore_certificate;
+        self
+    }
+
+    pub fn method(mut self, http_method: &str) -> Self {
+        self.http_method = Some(http_method.to_owned());
+        self
+    }
+
+    pub fn body(mut self, http_body: &str) -> Self {
+        self.loginbuster.http_body = http_body.to_owned();
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.loginbuster.user_agent = user_agent.to_owned();
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.loginbuster
+            .http_headers
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn csrf(mut self, url: &str, regex: &str) -> Self {
+        self.loginbuster.csrf_url = Some(url.to_owned());
+        self.loginbuster.csrf_regex = Some(regex.to_owned());
+        self
+    }
+
+    pub fn csrf_header(mut self, name: &str, value: &str) -> Self {
+        self.loginbuster
+            .csrf_headers
+            .get_or_insert_with(Vec::new)
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn success(mut self, success: Expression) -> Self {
+        self.loginbuster.success = Some(success);
+        self
+    }
+
+    pub fn lockout(mut self, lockout: Expression) -> Self {
+        self.loginbuster.lockout = lockout;
+        self
+    }
+
+    pub fn lockout_pause(mut self, lockout_pause: Duration) -> Self {
+        self.loginbuster.lockout_pause = lockout_pause;
+        self
+    }
+
+    pub fn similarity_threshold(mut self, similarity_threshold: f64) -> Self {
+        self.loginbuster.similarity_threshold = similarity_threshold;
+        self
+    }
+
+    pub fn build(self) -> Result<LoginScan, Error> {
+        if self.targets.is_empty() {
+            return Err(Error::MissingOption("target"));
+        }
+        for target in &self.targets {
+            check_url(&encode_placeholders(target))?;
+        }
+
+        let mut usernames = self.usernames;
+        let mut passwords = self.passwords;
+        let mut credentials = self.combos;
+        for path in &self.usernames_paths {
+            usernames.extend(read_lines(path)?);
+        }
+        for path in &self.passwords_paths {
+            passwords.extend(read_lines(path)?);
+        }
+        for path in &self.combos_paths {
+            credentials.extend(parse_combos(&read_lines(path)?));
+        }
+        match (usernames.is_empty(), passwords.is_empty()) {
+            (false, true) => return Err(Error::MissingOption("passwords")),
+            (true, false) => return Err(Error::MissingOption("usernames")),
+            _ => credentials.extend(spray(&usernames, &passwords)),
+        }
+        if credentials.is_empty() {
+            return Err(Error::MissingOption("credentials"));
+        }
+
+        let (csrf_uri, csrf_regex) = match &self.loginbuster.csrf_url {
+            Some(csrf_url) => {
+                let regex = self
+                    .loginbuster
+                    .csrf_regex
+                    .as_ref()
+                    .ok_or(Error::MissingOption("CSRF regex"))?;
+                (Some(check_url(csrf_url)?), Some(check_regex(regex)?))
+            }
+            None => (None, None),
+        };
+
+        let mut loginbuster = self.loginbuster;
+        let login_type = loginbuster.login_type;
+        loginbuster.http_method = match (self.http_method, login_type.auth_type()) {
+            (Some(http_method), _) => http_method,
+            (None, Some(_)) => "GET".to_owned(),
+            (None, None) => "POST".to_owned(),
+        };
+        if loginbuster.http_body.is_empty() {
+            loginbuster.http_body = login_type.default_body().to_owned();
+        }
+        if let Some(content_type) = login_type.content_type() {
+            if !loginbuster
+                .http_headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            {
+                loginbuster
+                    .http_headers
+                    .push(("Content-Type".to_owned(), content_type.to_owned()));
+            }
+        }
+
+        let client = HttpClient::https(loginbuster.n_threads, loginbuster.ignore_certificate)?;
+
+        Ok(LoginScan {
+            context: Arc::new(LoginContext::new(loginbuster, client, csrf_uri, csrf_regex)),
+            targets: self.targets,
+            credentials,
+            max_per_host: self.max_per_host,
+        })
+    }
+}
+
+impl Default for LoginScanBuilder {
+    fn default() -> Self {
+        LoginScanBuilder::new()
+    }
+}
+
+impl LoginScan {
+    pub fn builder() -> LoginScanBuilder {
+        LoginScanBuilder::new()
+    }
+
+    pub fn total(&self) -> usize {
+        self.targets.len() * self.credentials.len()
+    }
+
+    pub fn stream(self) -> Result<(ScanStream<SingleLoginScanResult>, ScanHandle), Error> {
+        let (tx, rx) = mpsc::unbounded();
+        let total = self.total();
+        let context = self.context;
+        let targets = self.targets;
+        let credentials = self.credentials;
+        let n_threads = context.login.n_threads;
+        let max_per_host = self.max_per_host;
+        let baselines = targets
+            .iter()
+            .map(|target| LoginContext::make_baseline_future(context.clone(), target.to_owned()))
+            .collect::<Vec<_>>();
+
+        let scan = futures::future::join_all(baselines).and_then(move |baselines| {
+            let baselines: Vec<_> = baselines.into_iter().map(|b| b.map(Arc::new)).collect();
+            let hosts: Vec<String> = targets
+                .iter()
+                .map(|target| host_of(&encode_placeholders(target)))
+                .collect();
+            let jobs: Vec<(String, (usize, String, String))> = credentials
+                .into_iter()
+                .flat_map(|(username, password)| {
+                    hosts
+                        .clone()
+                        .into_iter()
+                        .enumerate()
+                        .map(move |(i, host)| (host, (i, username.clone(), password.clone())))
+                })
+                .collect();
+
+            FairScheduler::new(
+                futures::stream::iter_ok(jobs),
+                move |(i, username, password)| {
+                    LoginContext::make_attempt_future(
+                        context.clone(),
+                        tx.clone(),
+                        targets[i].clone(),
+                        baselines[i].clone(),
+                        username,
+                        password,
+                    )
+                },
+                n_threads,
+                max_per_host,
+            )
+            .for_each(Ok)
+        });
+
+        Ok(ScanStream::new(scan, rx, Some(total)))
+    }
+
+    pub fn run(self, tx: Sender<SingleLoginScanResult>) -> Result<(), Error> {
+        scan::forward(self.stream()?.0, tx)
+    }
+}
@@ -0,0 +1,435 @@
+use futures::{future, sync::mpsc::UnboundedSender, Future};
+use hyper::{header, StatusCode, Uri};
+use regex::Regex;
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+pub mod builder;
+pub mod result_processor;
+mod spec;
+pub mod utils;
+
+use crate::{
+    dnsbuster::resolver::random_label,
+    error::{check_url, Error},
+    fuzzbuster::{CsrfPolicy, FuzzBuster, FuzzRequest},
+    matcher::{collect_headers, expr::Expression},
+    session::{
+        auth::{AuthType, Credentials},
+        macros::{decode_placeholders, substitute, Variables},
+        HttpClient, Response, Session,
+    },
+    vhostbuster::utils::similarity,
+};
+use result_processor::SingleLoginScanResult;
+use utils::{form_encode, json_escape};
+
+pub const DEFAULT_LOCKOUT: &str = "status == 429 || body ~ /captcha|account (is |has been )?locked|too many (failed |login )?(attempts|requests)/i";
+const LOCKOUT_RETRIES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoginType {
+    Form,
+    Json,
+    Basic,
+    Digest,
+    Ntlm,
+}
+
+impl FromStr for LoginType {
+    type Err = Error;
+
+    fn from_str(login_type: &str) -> Result<Self, Error> {
+        match login_type {
+            "form" => Ok(LoginType::Form),
+            "json" => Ok(LoginType::Json),
+            "basic" => Ok(LoginType::Basic),
+            "digest" => Ok(LoginType::Digest),
+            "ntlm" => Ok(LoginType::Ntlm),
+            _ => Err(Error::InvalidOption {
+                name: "login type",
+                reason: format!(
+                    "{} is not one of form, json, basic, digest or ntlm",
+                    login_type
+                ),
+            }),
+        }
+    }
+}
+
+impl LoginType {
+    pub fn auth_type(self) -> Option<AuthType> {
+        match self {
+            LoginType::Basic => Some(AuthType::Basic),
+            LoginType::Digest => Some(AuthType::Digest),
+            LoginType::Ntlm => Some(AuthType::Ntlm),
+            _ => None,
+        }
+    }
+
+    pub fn default_body(self) -> &'static str {
+        match self {
+            LoginType::Form => "username={{username}}&password={{password}}",
+            LoginType::Json => r#"{"username":"{{username}}","password":"{{password}}"}"#,
+            _ => "",
+        }
+    }
+
+    pub fn content_type(self) -> Option<&'static str> {
+        match self {
+            LoginType::Form => Some("application/x-www-form-urlencoded"),
+            LoginType::Json => Some("application/json"),
+            _ => None,
+        }
+    }
+
+    fn encode(self, value: &str) -> String {
+        match self {
+            LoginType::Form => form_encode(value),
+            LoginType::Json => json_escape(value),
+            _ => value.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginBuster {
+    pub n_threads: usize,
+    pub ignore_certificate: bool,
+    pub login_type: LoginType,
+    pub http_method: String,
+    pub http_body: String,
+    pub user_agent: String,
+    pub http_headers: Vec<(String, String)>,
+    pub csrf_url: Option<String>,
+    pub csrf_regex: Option<String>,
+    pub csrf_headers: Option<Vec<(String, String)>>,
+    pub success: Option<Expression>,
+    pub lockout: Expression,
+    pub lockout_pause: Duration,
+    pub similarity_threshold: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginBaseline {
+    pub status: StatusCode,
+    pub location: Option<String>,
+    pub body: String,
+}
+
+fn location(response: &Response) -> Option<String> {
+    response
+        .headers
+        .get(header::LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_owned())
+}
+
+fn normalize(response: &Response, username: &str, password: &str) -> String {
+    let body = String::from_utf8_lossy(&response.body).replace(username, "");
+    match password {
+        "" => body,
+        _ => body.replace(password, ""),
+    }
+}
+
+impl LoginBaseline {
+    pub fn new(response: &Response, username: &str, password: &str) -> Self {
+        LoginBaseline {
+            status: response.status,
+            location: location(response),
+            body: normalize(response, username, password),
+        }
+    }
+
+    pub fn differs(
+        &self,
+        response: &Response,
+        username: &str,
+        password: &str,
+        similarity_threshold: f64,
+    ) -> bool {
+        response.status != self.status
+            || location(response) != self.location
+            || similarity(&self.body, &normalize(response, username, password))
+                < similarity_threshold
+    }
+}
+
+pub struct LoginContext {
+    pub login: LoginBuster,
+    client: HttpClient,
+    csrf_uri: Option<Uri>,
+    csrf_regex: Option<Regex>,
+    paused_until: Mutex<Option<Instant>>,
+    found: Mutex<HashSet<(String, String)>>,
+}
+
+impl LoginContext {
+    pub fn new(
+        login: LoginBuster,
+        client: HttpClient,
+        csrf_uri: Option<Uri>,
+        csrf_regex: Option<Regex>,
+    ) -> Self {
+        LoginContext {
+            login,
+            client,
+            csrf_uri,
+            csrf_regex,
+            paused_until: Mutex::new(None),
+            found: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn build_request(
+        &self,
+        target: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<FuzzRequest, Error> {
+        let login_type = self.login.login_type;
+        let variables = |encode: &dyn Fn(&str) -> String| -> Variables {
+            vec![
+                ("username".to_owned(), encode(username)),
+                ("password".to_owned(), encode(password)),
+            ]
+            .into_iter()
+            .collect()
+        };
+        let raw = variables(&|value| value.to_owned());
+
+        Ok(FuzzRequest {
+            uri: check_url(&substitute(
+                &decode_placeholders(target),
+                &variables(&form_encode),
+            ))?,
+            http_method: self.login.http_method.clone(),
+            http_headers: self
+                .login
+                .http_headers
+                .iter()
+                .map(|(name, value)| (substitute(name, &raw), substitute(value, &raw)))
+                .collect(),
+            http_body: substitute(
+                &self.login.http_body,
+                &variables(&|value| login_type.encode(value)),
+            ),
+            user_agent: self.login.user_agent.clone(),
+            payload: vec![username.to_owned(), password.to_owned()],
+            csrf_uri: self.csrf_uri.clone(),
+            csrf_regex: self.csrf_uri.as_ref().and(self.login.csrf_regex.clone()),
+            csrf_headers: self.csrf_uri.as_ref().and(self.login.csrf_headers.clone()),
+        })
+    }
+
+    pub fn client_for(&self, username: &str, password: &str) -> Result<HttpClient, Error> {
+        match self.login.login_type.auth_type() {
+            Some(auth_type) => Ok(self.client.clone().auth(Some(Credentials::new(
+                auth_type,
+                &format!("{}:{}", username, password),
+            )?))),
+            None => Ok(self.client.clone()),
+        }
+    }
+
+    pub fn is_success(
+        &self,
+        response: &Response,
+        baseline: Option<&LoginBaseline>,
+        username: &str,
+        password: &str,
+    ) -> bool {
+        match (&self.login.success, baseline) {
+            (Some(success), _) => response.matches(success),
+            (None, Some(baseline)) => baseline.differs(
+                response,
+                username,
+                password,
+                self.login.similarity_threshold,
+            ),
+            (None, None) => false,
+        }
+    }
+
+    pub fn is_found(&self, target: &str, username: &str) -> bool {
+        self.found
+            .lock()
+            .unwrap()
+            .contains(&(target.to_owned(), username.to_owned()))
+    }
+
+    fn pause(&self) {
+        let until = Instant::now() + self.login.lockout_pause;
+        let mut paused_until = self.paused_until.lock().unwrap();
+        if !paused_until.is_some_and(|paused_until| paused_until >= until) {
+            *paused_until = Some(until);
+        }
+    }
+
+    fn wait(&self) -> impl Future<Item = (), Error = Error> {
+        match *self.paused_until.lock().unwrap() {
+            Some(until) if until > Instant::now() => {
+                future::Either::A(Delay::new(until).then(|_| Ok(())))
+            }
+            _ => future::Either::B(future::ok(())),
+        }
+    }
+
+    fn send(
+        context: Arc<LoginContext>,
+        client: HttpClient,
+        request: FuzzRequest,
+        retries: usize,
+    ) -> Box<dyn Future<Item = (Response, bool), Error = Error> + Send> {
+        let retry = (context.clone(), client.clone(), request.clone());
+        let csrf_regex = context.csrf_regex.clone();
+
+        Box::new(
+            context
+                .wait()
+                .and_then(move |_| {
+                    FuzzBuster::exchange_with_csrf(
+                        Session::new(Vec::new()),
+                        client,
+                        request,
+                        csrf_regex,
+                        Arc::new(CsrfPolicy::default()),
+                        false,
+                    )
+                })
+                .and_then(move |response| {
+                    let (context, client, request) = retry;
+                    if !response.matches(&context.login.lockout) {
+                        return future::Either::A(future::ok((response, false)));
+                    }
+                    if retries == 0 {
+                        warn!(
+                            "still locked out after pausing, giving up on {}",
+                            request.payload[0]
+                        );
+                        return future::Either::A(future::ok((response, true)));
+                    }
+
+                    warn!(
+                        "lockout or captcha response for {}, pausing for {}s",
+                        request.payload[0],
+                        context.login.lockout_pause.as_secs()
+                    );
+                    context.pause();
+                    future::Either::B(LoginContext::send(context, client, request, retries - 1))
+                }),
+        )
+    }
+
+    pub fn make_baseline_future(
+        context: Arc<LoginContext>,
+        target: String,
+    ) -> impl Future<Item = Option<LoginBaseline>, Error = Error> {
+        if context.login.success.is_some() {
+            return future::Either::A(future::ok(None));
+        }
+
+        let username = random_label();
+        let password = random_label();
+        let prepared = context
+            .build_request(&target, &username, &password)
+            .and_then(|request| Ok((context.client_for(&username, &password)?, request)));
+        let (client, request) = match prepared {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Unable to build the baseline login: {}", e);
+                return future::Either::A(future::ok(None));
+            }
+        };
+
+        future::Either::B(
+            LoginContext::send(context, client, request, LOCKOUT_RETRIES)
+                .map(move |(response, _)| {
+                    debug!(
+                        "baseline {} - {} - {} bytes",
+                        username,
+                        response.status,
+                        response.body.len()
+                    );
+                    Some(LoginBaseline::new(&response, &username, &password))
+                })
+                .or_else(move |e| {
+                    warn!("Unable to request the baseline login on {}: {}", target, e);
+                    Ok(None)
+                }),
+        )
+    }
+
+    pub fn make_attempt_future(
+        context: Arc<LoginContext>,
+        tx: UnboundedSender<SingleLoginScanResult>,
+        target: String,
+        baseline: Option<Arc<LoginBaseline>>,
+        username: String,
+        password: String,
+    ) -> impl Future<Item = (), Error = Error> {
+        if context.is_found(&target, &username) {
+            trace!("skipping {}:{}, already found", username, password);
+            return future::Either::A(future::ok(()));
+        }
+
+        let mut result = SingleLoginScanResult {
+            target: target.clone(),
+            url: target.clone(),
+            method: context.login.http_method.clone(),
+            username: username.clone(),
+            password: password.clone(),
+            status: StatusCode::default().to_string(),
+            error: None,
+            success: false,
+            locked: false,
+            length: None,
+            time: None,
+            headers: Vec::new(),
+            body: String::new(),
+        };
+        let prepared = context
+            .build_request(&target, &username, &password)
+            .and_then(|request| Ok((context.client_for(&username, &password)?, request)));
+        let (client, request) = match prepared {
+            Ok(v) => v,
+            Err(e) => {
+                result.error = Some(e.to_string());
+                let _ = tx.unbounded_send(result);
+                return future::Either::A(future::ok(()));
+            }
+        };
+        result.url = decode_placeholders(&request.uri.to_string());
+        let mut result_err = result.clone();
+        let tx_err = tx.clone();
+
+        future::Either::B(
+            LoginContext::send(context.clone(), client, request, LOCKOUT_RETRIES)
+                .map(move |(response, locked)| {
+                    result.status = response.status.to_string();
+                    result.locked = locked;
+                    result.success = !locked
+                        && context.is_success(&response, baseline.as_deref(), &username, &password);
+                    if result.success {
+                        context.found.lock().unwrap().insert((target, username));
+                    }
+                    result.headers = collect_headers(&response.headers);
+                    result.length = Some(response.body.len());
+                    result.time = Some(response.time.as_millis() as u64);
+                    result.body = String::from_utf8_lossy(&response.body).into_owned();
+                    let _ = tx.unbounded_send(result);
+                })
+                .or_else(move |e| {
+                    result_err.error = Some(e.to_string());
+                    let _ = tx_err.unbounded_send(result_err);
+                    Ok(())
+                }),
+        )
+    }
+}
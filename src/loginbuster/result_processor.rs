@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::{fs::File, io::Write, path::Path, time::Duration};
+
+use crate::matcher::{find_header, Matchable, ResultFilter};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SingleLoginScanResult {
+    pub target: String,
+    pub url: String,
+    pub method: String,
+    pub username: String,
+    pub password: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub success: bool,
+    pub locked: bool,
+    pub length: Option<usize>,
+    pub time: Option<u64>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Matchable for SingleLoginScanResult {
+    fn status(&self) -> Option<&str> {
+        Some(&self.status)
+    }
+
+    fn length(&self) -> Option<usize> {
+        self.length
+    }
+
+    fn body(&self) -> Option<&str> {
+        Some(&self.body)
+    }
+
+    fn time(&self) -> Option<Duration> {
+        self.time.map(Duration::from_millis)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+pub struct LoginScanProcessor {
+    pub results: Vec<SingleLoginScanResult>,
+    filter: ResultFilter<SingleLoginScanResult>,
+}
+
+impl LoginScanProcessor {
+    pub fn new(filter: ResultFilter<SingleLoginScanResult>) -> Self {
+        LoginScanProcessor {
+            results: Vec::<SingleLoginScanResult>::new(),
+            filter,
+        }
+    }
+
+    pub fn maybe_add_result(&mut self, res: SingleLoginScanResult) -> bool {
+        trace!("{:?}", res);
+        if res.success && self.filter.is_match(&res) {
+            self.results.push(res);
+            return true;
+        }
+
+        false
+    }
+
+    pub fn save_login_results(self, path: &str) {
+        let json_string = serde_json::to_string(&self.results).unwrap();
+
+        let mut file = match File::create(Path::new(path)) {
+            Ok(f) => f,
+            Err(e) => {
+                error!("Error while creating file: {}\n{}", path, e);
+                return;
+            }
+        };
+
+        match file.write_all(json_string.as_bytes()) {
+            Ok(_) => debug!("Results saved to: {}", path),
+            Err(e) => error!("Error while writing results to file: {}\n{}", path, e),
+        };
+    }
+}
@@ -0,0 +1,105 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name loginbuster;
+
+    use crate::loginbuster::{builder::LoginScan, utils::*, LoginBaseline, LoginContext, LoginType, DEFAULT_LOCKOUT};
+    use crate::{matcher::expr::Expression, session::{HttpClient, Response}};
+    use hyper::{header::HeaderValue, HeaderMap, StatusCode};
+    use std::time::Duration;
+
+    fn response(status: StatusCode, location: Option<&str>, body: &str) -> Response {
+        let mut headers = HeaderMap::new();
+        if let Some(location) = location {
+            headers.insert("Location", HeaderValue::from_str(location).unwrap());
+        }
+        Response { status, headers, body: body.to_owned().into(), length: body.len(), time: Duration::from_millis(1) }
+    }
+
+    fn context(login_type: LoginType, http_body: &str) -> LoginContext {
+        let login = crate::loginbuster::LoginBuster {
+            n_threads: 1,
+            ignore_certificate: false,
+            login_type,
+            http_method: "POST".to_owned(),
+            http_body: http_body.to_owned(),
+            user_agent: "rustbuster".to_owned(),
+            http_headers: vec![("X-User".to_owned(), "{{username}}".to_owned())],
+            csrf_url: None,
+            csrf_regex: None,
+            csrf_headers: None,
+            success: None,
+            lockout: Expression::parse(DEFAULT_LOCKOUT).unwrap(),
+            lockout_pause: Duration::from_secs(1),
+            similarity_threshold: 0.9,
+        };
+        LoginContext::new(login, HttpClient::https(1, false).unwrap(), None, None)
+    }
+
+    test encoding() {
+        assert_eq!("a+b%26c%3Dd%25", form_encode("a b&c=d%"));
+        assert_eq!("p%C3%A4ss", form_encode("päss"));
+        assert_eq!(r#"a\"b\\c"#, json_escape(r#"a"b\c"#));
+    }
+
+    test combos_and_spray() {
+        let lines = vec!["admin:pa:ss".to_owned(), "broken".to_owned(), "root:".to_owned()];
+        assert_eq!(vec![("admin".to_owned(), "pa:ss".to_owned()), ("root".to_owned(), "".to_owned())], parse_combos(&lines));
+
+        let users = vec!["a".to_owned(), "b".to_owned()];
+        let passwords = vec!["1".to_owned(), "2".to_owned()];
+        let pairs: Vec<String> = spray(&users, &passwords).into_iter().map(|(u, p)| format!("{}:{}", u, p)).collect();
+        assert_eq!(vec!["a:1", "b:1", "a:2", "b:2"], pairs);
+    }
+
+    test login_type() {
+        assert_eq!(LoginType::Json, "json".parse::<LoginType>().unwrap());
+        assert_eq!(None, LoginType::Form.auth_type());
+        assert!(LoginType::Ntlm.auth_type().is_some());
+        assert!("oauth".parse::<LoginType>().is_err());
+    }
+
+    test build_request() {
+        let form = context(LoginType::Form, "user={{username}}&pass={{password}}&csrf=CSRFCSRF");
+        let request = form.build_request("http://127.0.0.1/login?u={{username}}", "jo hn", "p&ss").unwrap();
+        assert_eq!("user=jo+hn&pass=p%26ss&csrf=CSRFCSRF", request.http_body);
+        assert_eq!("/login?u=jo+hn", request.uri.path_and_query().unwrap().as_str());
+        assert_eq!(vec![("X-User".to_owned(), "jo hn".to_owned())], request.http_headers);
+        assert_eq!(vec!["jo hn".to_owned(), "p&ss".to_owned()], request.payload);
+
+        let json = context(LoginType::Json, LoginType::Json.default_body());
+        let request = json.build_request("http://127.0.0.1/login", "admin", "p\"ss").unwrap();
+        assert_eq!(r#"{"username":"admin","password":"p\"ss"}"#, request.http_body);
+    }
+
+    test baseline_differs() {
+        let baseline = LoginBaseline::new(&response(StatusCode::OK, None, "Invalid password for bogus"), "bogus", "x");
+        assert!(!baseline.differs(&response(StatusCode::OK, None, "Invalid password for admin"), "admin", "secret", 0.9));
+        assert!(baseline.differs(&response(StatusCode::FOUND, None, "Invalid password for admin"), "admin", "secret", 0.9));
+        assert!(baseline.differs(&response(StatusCode::OK, Some("/home"), "Invalid password for admin"), "admin", "secret", 0.9));
+        assert!(baseline.differs(&response(StatusCode::OK, None, "Welcome back admin"), "admin", "secret", 0.9));
+
+        let context = context(LoginType::Form, "");
+        let ok = response(StatusCode::OK, None, "Welcome back admin");
+        assert!(context.is_success(&ok, Some(&baseline), "admin", "secret"));
+        assert!(!context.is_success(&ok, None, "admin", "secret"));
+    }
+
+    test default_lockout() {
+        let lockout = Expression::parse(DEFAULT_LOCKOUT).unwrap();
+        assert!(response(StatusCode::TOO_MANY_REQUESTS, None, "").matches(&lockout));
+        assert!(response(StatusCode::OK, None, "Your account has been locked").matches(&lockout));
+        assert!(response(StatusCode::OK, None, "Please solve the CAPTCHA").matches(&lockout));
+        assert!(!response(StatusCode::UNAUTHORIZED, None, "Invalid user/password").matches(&lockout));
+    }
+
+    test builder_errors() {
+        let missing = |builder: crate::loginbuster::builder::LoginScanBuilder| builder.build().err().unwrap().to_string();
+        assert!(missing(LoginScan::builder().username("admin").password("admin")).contains("target"));
+        assert!(missing(LoginScan::builder().target("http://127.0.0.1/")).contains("credentials"));
+        assert!(missing(LoginScan::builder().target("http://127.0.0.1/").username("admin")).contains("passwords"));
+
+        let scan = LoginScan::builder().target("http://127.0.0.1/").combo("root", "toor").username("a").username("b").password("1").build().unwrap();
+        assert_eq!(3, scan.total());
+    }
+}
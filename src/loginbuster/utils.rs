@@ -0,0 +1,51 @@
+use crate::error::{read_file, Error};
+
+pub fn form_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => {
+                encoded.push(byte as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+pub fn json_escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).expect("strings serialize to JSON");
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+pub fn read_lines(path: &str) -> Result<Vec<String>, Error> {
+    Ok(read_file(path)?
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_owned())
+        .collect())
+}
+
+pub fn parse_combos(contents: &[String]) -> Vec<(String, String)> {
+    let mut combos = Vec::new();
+    for line in contents {
+        match line.find(':') {
+            Some(i) => combos.push((line[..i].to_owned(), line[i + 1..].to_owned())),
+            None => warn!("skipping the combo {}: expected user:password", line),
+        }
+    }
+    combos
+}
+
+pub fn spray(usernames: &[String], passwords: &[String]) -> Vec<(String, String)> {
+    passwords
+        .iter()
+        .flat_map(|password| {
+            usernames
+                .iter()
+                .map(move |username| (username.to_owned(), password.to_owned()))
+        })
+        .collect()
+}
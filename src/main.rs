@@ -3,37 +3,62 @@ extern crate log;
 #[macro_use]
 extern crate clap;
 
-use clap::{App, SubCommand};
+use clap::{App, AppSettings, SubCommand};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::{sync::mpsc::channel, thread, time::SystemTime};
+use std::{
+    collections::HashSet,
+    sync::{mpsc::channel, Arc},
+    thread,
+    time::{Duration, SystemTime},
+};
 
 mod args;
-mod banner;
-mod dirbuster;
-mod dnsbuster;
-mod fuzzbuster;
-mod tildebuster;
-mod vhostbuster;
+mod config;
+
+use librustbuster::{
+    banner, dirbuster, dnsbuster, fingerprint, fuzzbuster, loginbuster,
+    matcher::{ResultFilter, StatusMatcher},
+    probebuster, tildebuster, vhostbuster,
+};
 
 use args::*;
 use dirbuster::{
-    result_processor::{ResultProcessorConfig, ScanResult, SingleDirScanResult},
+    result_processor::{ScanResult, SingleDirScanResult},
     utils::*,
     DirConfig,
 };
 use dnsbuster::{
+    resolver::ResolverPool,
     result_processor::{DnsScanResult, SingleDnsScanResult},
+    takeover::{load_fingerprints, TakeoverConfig},
+    utils::*,
+    zonewalk::{self, save_nsec3_hashes},
+    DnsConfig, DnsQueryType,
+};
+use probebuster::{
+    builder::ProbeScan,
+    result_processor::{ProbeScanResult, SingleProbeScanResult},
     utils::*,
-    DnsConfig,
 };
-use tildebuster::TildeBuster;
+use tildebuster::{
+    builder::TildeScan,
+    result_processor::{FSObject, SingleTildeScanResult, TildeRequest, TildeScanProcessor},
+};
 use vhostbuster::{
+    builder::VhostScan,
     result_processor::{SingleVhostScanResult, VhostScanResult},
     utils::*,
-    VhostConfig,
 };
 
-use fuzzbuster::FuzzBuster;
+use fingerprint::load_signatures;
+use fuzzbuster::{
+    builder::FuzzScan,
+    result_processor::{FuzzScanProcessor, SingleFuzzScanResult},
+};
+use loginbuster::{
+    builder::LoginScan,
+    result_processor::{LoginScanProcessor, SingleLoginScanResult},
+};
 
 fn main() {
     if std::env::vars()
@@ -42,63 +67,18 @@ fn main() {
         .len()
         == 0
     {
-        std::env::set_var("RUST_LOG", "rustbuster=warn");
+        std::env::set_var("RUST_LOG", "rustbuster=warn,librustbuster=warn");
     }
 
     pretty_env_logger::init();
-    let matches = App::new("rustbuster")
-        .version(crate_version!())
-        .author("by phra & ps1dr3x")
-        .about("DirBuster for rust")
-        .after_help("EXAMPLES:
-    1. Dir mode:
-        rustbuster dir -u http://localhost:3000/ -w examples/wordlist -e php
-    2. Dns mode:
-        rustbuster dns -d google.com -w examples/wordlist
-    3. Vhost mode:
-        rustbuster vhost -u http://localhost:3000/ -w examples/wordlist -d test.local -x \"Hello\"
-    4. Fuzz mode:
-        rustbuster fuzz -u http://localhost:3000/login \\
-            -X POST \\
-            -H \"Content-Type: application/json\" \\
-            -b '{\"user\":\"FUZZ\",\"password\":\"FUZZ\",\"csrf\":\"CSRFCSRF\"}' \\
-            -w examples/wordlist \\
-            -w /usr/share/seclists/Passwords/Common-Credentials/10-million-password-list-top-10000.txt \\
-            -s 200 \\
-            --csrf-url \"http://localhost:3000/csrf\" \\
-            --csrf-regex '\\{\"csrf\":\"(\\w+)\"\\}'
-    5. Tilde mode:
-        rustbuster tilde -u http://localhost:3000/ -e aspx -X OPTIONS
-")
-        .subcommand(set_wordlist_args(set_dir_args(set_http_args(set_common_args(SubCommand::with_name("dir")))))
-            .about("Directories and files enumeration mode")
-            .after_help("EXAMPLE:
-    rustbuster dir -u http://localhost:3000/ -w examples/wordlist -e php"))
-        .subcommand(set_wordlist_args(set_dns_args(set_common_args(SubCommand::with_name("dns"))))
-            .about("A/AAAA entries enumeration mode")
-            .after_help("EXAMPLE:
-    rustbuster dns -d google.com -w examples/wordlist"))
-        .subcommand(set_wordlist_args(set_vhost_args(set_http_args(set_common_args(SubCommand::with_name("vhost")))))
-            .about("Virtual hosts enumeration mode")
-            .after_help("EXAMPLE:
-    rustbuster vhost -u http://localhost:3000/ -w examples/wordlist -d test.local -x \"Hello\""))
-        .subcommand(set_tilde_args(set_http_args(set_common_args(SubCommand::with_name("tilde"))))
-            .about("IIS 8.3 shortname enumeration mode")
-            .after_help("EXAMPLE:
-    rustbuster tilde -u http://localhost:3000/ -e aspx -X OPTIONS"))
-        .subcommand(set_wordlist_args(set_fuzz_args(set_body_args(set_http_args(set_common_args(SubCommand::with_name("fuzz"))))))
-            .about("Custom fuzzing enumeration mode")
-            .after_help("EXAMPLE:
-    rustbuster fuzz -u http://localhost:3000/login \\
-        -X POST \\
-        -H \"Content-Type: application/json\" \\
-        -b '{\"user\":\"FUZZ\",\"password\":\"FUZZ\",\"csrf\":\"CSRFCSRF\"}' \\
-        -w examples/wordlist \\
-        -w /usr/share/seclists/Passwords/Common-Credentials/10-million-password-list-top-10000.txt \\
-        -s 200 \\
-        --csrf-url \"http://localhost:3000/csrf\" \\
-        --csrf-regex '\\{\"csrf\":\"(\\w+)\"\\}'"))
-        .get_matches();
+    let argv = match config::expand_args(std::env::args().collect(), app) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Invalid configuration: {}", e);
+            return;
+        }
+    };
+    let matches = app().get_matches_from(argv);
 
     let mode = matches.subcommand_name().unwrap_or("dir");
     let submatches = match matches.subcommand_matches(mode) {
@@ -136,20 +116,40 @@ fn main() {
                 Ok(v) => v,
             };
 
-            let http_args = extract_http_args(submatches);
-            if !url_is_valid(&http_args.url) {
+            let http_args = match extract_http_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let body_args = extract_body_args(submatches);
+            let filter_args = match extract_filter_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let session_args = match extract_session_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let auth_args = match extract_auth_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets_args = match extract_targets_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets = url_targets(&targets_args.targets, &http_args.url);
+            if targets.is_empty() {
                 return;
             }
 
             let dir_args = extract_dir_args(submatches);
-            let urls = build_urls(
-                &wordlist_args.wordlist_paths[0],
-                &http_args.url,
-                dir_args.extensions,
-                dir_args.append_slash,
-            );
-            let total_numbers_of_request = urls.len();
-            let (tx, rx) = channel::<SingleDirScanResult>();
+            let filter = ResultFilter::new()
+                .status_codes(
+                    &http_args.include_status_codes,
+                    &http_args.ignore_status_codes,
+                )
+                .body_strings(&body_args.include_strings, &body_args.ignore_strings)
+                .expressions(&filter_args.matches, &filter_args.filters);
             let config = DirConfig {
                 n_threads: common_args.n_threads,
                 ignore_certificate: http_args.ignore_certificate,
@@ -157,12 +157,101 @@ fn main() {
                 http_body: http_args.http_body.to_owned(),
                 user_agent: http_args.user_agent.to_owned(),
                 http_headers: http_args.http_headers.clone(),
+                max_per_host: targets_args.max_per_host,
+                session: session_args.session.clone(),
+                auth: auth_args.auth.clone(),
+                read_body: filter.needs_body(),
             };
-            let rp_config = ResultProcessorConfig {
-                include: http_args.include_status_codes,
-                ignore: http_args.ignore_status_codes,
+            let signatures = if dir_args.fingerprint {
+                match load_signatures(dir_args.signatures.as_deref()) {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        error!("{}", e);
+                        return;
+                    }
+                }
+            } else {
+                None
             };
-            let mut result_processor = ScanResult::new(rp_config);
+
+            let mut urls: Vec<(String, hyper::Uri)> = Vec::new();
+            for target in &targets {
+                let mut extensions = dir_args.extensions.clone();
+                let mut wordlists = vec![wordlist_args.wordlist_paths[0].to_owned()];
+
+                if let Some(signatures) = &signatures {
+                    let technologies =
+                        match dirbuster::fingerprint_target(target, &config, signatures.clone()) {
+                            Ok(v) => v,
+                            Err(e) => {
+                                error!("{}", e);
+                                return;
+                            }
+                        };
+                    let names: Vec<String> = technologies
+                        .iter()
+                        .map(|technology| match &technology.version {
+                            Some(version) => format!("{} {}", technology.name, version),
+                            None => technology.name.to_owned(),
+                        })
+                        .collect();
+                    println!(
+                        "[+] Technologies\t: {} ({})",
+                        if names.is_empty() {
+                            "none".to_owned()
+                        } else {
+                            names.join(", ")
+                        },
+                        target
+                    );
+
+                    for technology in technologies {
+                        for extension in technology.extensions {
+                            if !extensions.contains(&extension) {
+                                extensions.push(extension);
+                            }
+                        }
+                        for wordlist in technology.wordlists {
+                            if wordlists.contains(&wordlist) {
+                                continue;
+                            }
+                            if std::fs::metadata(&wordlist).is_ok() {
+                                wordlists.push(wordlist);
+                            } else {
+                                debug!("Skipping missing wordlist {}", wordlist);
+                            }
+                        }
+                    }
+                    info!(
+                        "Using extensions {:?} and wordlists {:?} for {}",
+                        extensions, wordlists, target
+                    );
+                }
+
+                let mut seen = HashSet::new();
+                for wordlist in &wordlists {
+                    let wordlist_urls = match build_urls(
+                        wordlist,
+                        target,
+                        extensions.clone(),
+                        dir_args.append_slash,
+                    ) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            error!("{}", e);
+                            return;
+                        }
+                    };
+                    for url in wordlist_urls {
+                        if seen.insert(url.to_string()) {
+                            urls.push((target.to_owned(), url));
+                        }
+                    }
+                }
+            }
+            let total_numbers_of_request = urls.len();
+            let (tx, rx) = channel::<SingleDirScanResult>();
+            let mut result_processor = ScanResult::new(filter);
             let bar = if common_args.no_progress_bar {
                 ProgressBar::hidden()
             } else {
@@ -173,7 +262,11 @@ fn main() {
                 .template("{spinner} [{elapsed_precise}] {bar:40.red/white} {pos:>7}/{len:7} ETA: {eta_precise} req/s: {msg}")
                 .progress_chars("#>-"));
 
-            thread::spawn(move || dirbuster::run(tx, urls, config));
+            thread::spawn(move || {
+                if let Err(e) = dirbuster::run(tx, urls, config) {
+                    error!("{}", e);
+                }
+            });
 
             while current_numbers_of_request != total_numbers_of_request {
                 current_numbers_of_request = current_numbers_of_request + 1;
@@ -199,7 +292,8 @@ fn main() {
                 match &msg.error {
                     Some(e) => {
                         error!("{} - {:?}", msg.url, e);
-                        if current_numbers_of_request == 1 || common_args.exit_on_connection_errors
+                        if (current_numbers_of_request == 1 && targets.len() == 1)
+                            || common_args.exit_on_connection_errors
                         {
                             warn!("Check connectivity to the target");
                             break;
@@ -255,20 +349,201 @@ fn main() {
                 save_dir_results(&common_args.output, &result_processor.results);
             }
         }
-        "dns" => {
-            let wordlist_args = match extract_wordlist_args(submatches) {
+        "dns" | "ptr" | "srv" => {
+            let dns_args = match extract_dns_args(submatches) {
                 Err(_) => return,
                 Ok(v) => v,
             };
+            let filter_args = match extract_filter_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let resolver_args = extract_resolver_args(submatches);
+            let targets_args = match extract_targets_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets = domain_targets(&targets_args.targets, &dns_args.domain);
+            let mut query_type = DnsQueryType::Host;
+            let mut wordlist_path = String::new();
+            let mut domains = if mode == "ptr" {
+                let ptr_args = extract_ptr_args(submatches);
+                let results = match ptr_args.input.as_deref().map(load_dns_results) {
+                    Some(Ok(v)) => v,
+                    Some(Err(e)) => {
+                        error!("{}", e);
+                        return;
+                    }
+                    None => Vec::new(),
+                };
+                query_type = DnsQueryType::Ptr;
+                match build_ptr_targets(&ptr_args.ranges, &results) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("{}", e);
+                        return;
+                    }
+                }
+            } else if mode == "srv" {
+                let srv_args = extract_srv_args(submatches);
+                let services = match srv_args.services.as_deref().map(read_words) {
+                    Some(Ok(v)) => v,
+                    Some(Err(e)) => {
+                        error!("{}", e);
+                        return;
+                    }
+                    None => SRV_SERVICES.iter().map(|s| (*s).to_owned()).collect(),
+                };
+                query_type = DnsQueryType::Srv;
+                build_srv_targets(&services, &dns_args.domain)
+            } else {
+                let wordlist_args = match extract_wordlist_args(submatches) {
+                    Err(_) => return,
+                    Ok(v) => v,
+                };
+                wordlist_path = wordlist_args.wordlist_paths[0].clone();
+                let mut domains = Vec::new();
+                for target in targets.iter() {
+                    match build_domains(&wordlist_path, target) {
+                        Ok(v) => domains.extend(v),
+                        Err(e) => {
+                            error!("{}", e);
+                            return;
+                        }
+                    }
+                }
+                domains
+            };
+            let takeover = if dns_args.takeover {
+                let fingerprints =
+                    match load_fingerprints(dns_args.takeover_fingerprints.as_deref()) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            error!("{}", e);
+                            return;
+                        }
+                    };
+                Some(Arc::new(TakeoverConfig {
+                    fingerprints,
+                    http: dns_args.takeover_http,
+                    user_agent: dns_args.user_agent.clone(),
+                    timeout: Duration::from_millis(dns_args.takeover_timeout),
+                }))
+            } else {
+                None
+            };
+            let permutation_words = match dns_args.permutation_wordlist.as_deref().map(read_words) {
+                Some(Ok(v)) => v,
+                Some(Err(e)) => {
+                    error!("{}", e);
+                    return;
+                }
+                None => PERMUTATION_WORDS.iter().map(|w| (*w).to_owned()).collect(),
+            };
+            let mut queried: HashSet<String> = domains.iter().cloned().collect();
+            let mut total_numbers_of_request = domains.len();
+            let mut depth = 0;
+            let mut permutations_pending = dns_args.permutations;
+            let mut reverse_pending = dns_args.reverse;
+            let mut all_found: HashSet<String> = HashSet::new();
+            let timeout = Duration::from_millis(resolver_args.resolver_timeout);
+            let resolvers = match &resolver_args.resolvers {
+                Some(path) => {
+                    let pool = match ResolverPool::from_file(
+                        path,
+                        resolver_args.resolver_qps,
+                        timeout,
+                        resolver_args.resolver_retries,
+                    )
+                    .and_then(|pool| pool.health_check(&resolver_args.control_domain))
+                    {
+                        Ok(v) => v,
+                        Err(e) => {
+                            error!("{} in {}", e, path);
+                            return;
+                        }
+                    };
 
-            let dns_args = extract_dns_args(submatches);
-            let domains = build_domains(&wordlist_args.wordlist_paths[0], &dns_args.domain);
-            let total_numbers_of_request = domains.len();
-            let (tx, rx) = channel::<SingleDnsScanResult>();
-            let config = DnsConfig {
-                n_threads: common_args.n_threads,
+                    info!("Using {} resolvers", pool.len());
+                    Some(Arc::new(pool))
+                }
+                None if query_type != DnsQueryType::Host
+                    || reverse_pending
+                    || dns_args.takeover
+                    || dns_args.zone_walk =>
+                {
+                    let pool = match ResolverPool::from_system(
+                        resolver_args.resolver_qps,
+                        timeout,
+                        resolver_args.resolver_retries,
+                    ) {
+                        Ok(v) => v,
+                        Err(_) => {
+                            error!(
+                                "Unable to find the system resolvers, consider using --resolvers"
+                            );
+                            return;
+                        }
+                    };
+
+                    Some(Arc::new(pool))
+                }
+                None => None,
             };
-            let mut result_processor = DnsScanResult::new();
+            if let (true, Some(pool)) = (dns_args.zone_walk, &resolvers) {
+                let mut chains = Vec::new();
+                for target in targets.iter() {
+                    let walk = match zonewalk::walk(
+                        pool,
+                        target,
+                        dns_args.zone_walk_queries,
+                        common_args.n_threads,
+                    ) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            error!("{}", e);
+                            return;
+                        }
+                    };
+                    let mut names = walk.names;
+                    if let Some(chain) = walk.nsec3 {
+                        let words = match read_words(&wordlist_path) {
+                            Ok(v) => v,
+                            Err(e) => {
+                                error!("{}", e);
+                                return;
+                            }
+                        };
+                        let cracked = chain.crack(target, &words);
+                        info!(
+                            "Collected {} NSEC3 hashes of {}, cracked {}",
+                            chain.hashes.len(),
+                            target,
+                            cracked.len()
+                        );
+                        names.extend(cracked);
+                        chains.push((target.to_owned(), chain));
+                    } else if !names.is_empty() {
+                        info!("Walked {} names of {}", names.len(), target);
+                    } else {
+                        continue;
+                    }
+
+                    domains.retain(|domain| target_of(domain, &targets).as_ref() != Some(target));
+                    domains.extend(names.iter().map(|name| format!("{}:80", name)));
+                }
+
+                if let Some(path) = &dns_args.nsec3_hashes {
+                    if !chains.is_empty() {
+                        save_nsec3_hashes(path, &chains);
+                    }
+                }
+                queried = domains.iter().cloned().collect();
+                total_numbers_of_request = domains.len();
+            }
+            let mut result_processor = DnsScanResult::new(
+                ResultFilter::new().expressions(&filter_args.matches, &filter_args.filters),
+            );
 
             let bar = if common_args.no_progress_bar {
                 ProgressBar::hidden()
@@ -280,66 +555,189 @@ fn main() {
                 .template("{spinner} [{elapsed_precise}] {bar:40.red/white} {pos:>7}/{len:7} ETA: {eta_precise} req/s: {msg}")
                 .progress_chars("#>-"));
 
-            thread::spawn(move || dnsbuster::run(tx, domains, config));
+            loop {
+                let (tx, rx) = channel::<SingleDnsScanResult>();
+                let config = DnsConfig {
+                    n_threads: common_args.n_threads,
+                    resolvers: resolvers.clone(),
+                    query_type,
+                    takeover: takeover.clone(),
+                    targets: targets.clone(),
+                    max_per_host: targets_args.max_per_host,
+                };
+                let mut found: Vec<String> = Vec::new();
 
-            while current_numbers_of_request != total_numbers_of_request {
-                current_numbers_of_request = current_numbers_of_request + 1;
-                bar.inc(1);
+                thread::spawn(move || {
+                    if let Err(e) = dnsbuster::run(tx, domains, config) {
+                        error!("{}", e);
+                    }
+                });
 
-                let seconds_from_start = start_time.elapsed().unwrap().as_millis() / 1000;
-                if seconds_from_start != 0 {
-                    bar.set_message(
-                        &(current_numbers_of_request as u64 / seconds_from_start as u64)
-                            .to_string(),
-                    );
-                } else {
-                    bar.set_message("warming up...")
-                }
+                while current_numbers_of_request != total_numbers_of_request {
+                    current_numbers_of_request = current_numbers_of_request + 1;
+                    bar.inc(1);
 
-                let msg = match rx.recv() {
-                    Ok(msg) => msg,
-                    Err(_err) => {
-                        error!("{:?}", _err);
-                        break;
+                    let seconds_from_start = start_time.elapsed().unwrap().as_millis() / 1000;
+                    if seconds_from_start != 0 {
+                        bar.set_message(
+                            &(current_numbers_of_request as u64 / seconds_from_start as u64)
+                                .to_string(),
+                        );
+                    } else {
+                        bar.set_message("warming up...")
                     }
-                };
 
-                result_processor.maybe_add_result(msg.clone());
-                match msg.status {
-                    true => {
-                        if common_args.no_progress_bar {
-                            println!("OK\t{}", &msg.domain[..msg.domain.len() - 3]);
-                        } else {
-                            bar.println(format!("OK\t{}", &msg.domain[..msg.domain.len() - 3]));
+                    let msg = match rx.recv() {
+                        Ok(msg) => msg,
+                        Err(_err) => {
+                            error!("{:?}", _err);
+                            break;
+                        }
+                    };
+
+                    let kept = result_processor.maybe_add_result(msg.clone());
+                    if !msg.status {
+                        if let (true, Some(cname), Some(takeover)) =
+                            (kept, &msg.cname, &msg.takeover)
+                        {
+                            print_line(
+                                &bar,
+                                common_args.no_progress_bar,
+                                format!("TAKEOVER\t{}", strip_port(&msg.domain)),
+                            );
+                            print_line(
+                                &bar,
+                                common_args.no_progress_bar,
+                                format!("\t\tCNAME: {}", cname),
+                            );
+                            print_line(
+                                &bar,
+                                common_args.no_progress_bar,
+                                format!("\t\tService: {}", takeover),
+                            );
+                        }
+                        continue;
+                    }
+
+                    let names = match &msg.ptr {
+                        Some(names) => names
+                            .iter()
+                            .filter(|name| mode == "ptr" || !all_found.contains(name.as_str()))
+                            .cloned()
+                            .collect(),
+                        None => {
+                            found.push(msg.domain.clone());
+                            vec![strip_port(&msg.domain).to_owned()]
                         }
+                    };
+
+                    if !kept {
+                        continue;
+                    }
 
-                        match msg.extra {
-                            Some(v) => {
-                                for addr in v {
-                                    let string_repr = addr.ip().to_string();
-                                    match addr.is_ipv4() {
-                                        true => {
-                                            if common_args.no_progress_bar {
-                                                println!("\t\tIPv4: {}", string_repr);
-                                            } else {
-                                                bar.println(format!("\t\tIPv4: {}", string_repr));
-                                            }
-                                        }
-                                        false => {
-                                            if common_args.no_progress_bar {
-                                                println!("\t\tIPv6: {}", string_repr);
-                                            } else {
-                                                bar.println(format!("\t\tIPv6: {}", string_repr));
-                                            }
-                                        }
-                                    }
+                    for name in names {
+                        print_line(&bar, common_args.no_progress_bar, format!("OK\t{}", name));
+
+                        if let Some(addrs) = &msg.extra {
+                            for addr in addrs {
+                                let string_repr = addr.ip().to_string();
+                                match addr.is_ipv4() {
+                                    true => print_line(
+                                        &bar,
+                                        common_args.no_progress_bar,
+                                        format!("\t\tIPv4: {}", string_repr),
+                                    ),
+                                    false => print_line(
+                                        &bar,
+                                        common_args.no_progress_bar,
+                                        format!("\t\tIPv6: {}", string_repr),
+                                    ),
                                 }
                             }
-                            None => (),
+                        }
+
+                        if let Some(records) = &msg.srv {
+                            for record in records {
+                                print_line(
+                                    &bar,
+                                    common_args.no_progress_bar,
+                                    format!(
+                                        "\t\tTarget: {}:{} (priority {}, weight {})",
+                                        record.target, record.port, record.priority, record.weight
+                                    ),
+                                );
+                            }
+                        }
+
+                        if let Some(cname) = &msg.cname {
+                            print_line(
+                                &bar,
+                                common_args.no_progress_bar,
+                                format!("\t\tCNAME: {}", cname),
+                            );
+                        }
+
+                        if let Some(takeover) = &msg.takeover {
+                            print_line(
+                                &bar,
+                                common_args.no_progress_bar,
+                                format!("\t\tTakeover: {}", takeover),
+                            );
+                        }
+
+                        if let Some(resolver) = &msg.resolver {
+                            print_line(
+                                &bar,
+                                common_args.no_progress_bar,
+                                format!("\t\tResolver: {}", resolver),
+                            );
                         }
                     }
-                    false => (),
                 }
+
+                if current_numbers_of_request != total_numbers_of_request {
+                    break;
+                }
+
+                all_found.extend(found.iter().map(|domain| strip_port(domain).to_owned()));
+                let is_host_round = query_type == DnsQueryType::Host;
+                domains = if is_host_round && depth < dns_args.recursive_depth && !found.is_empty()
+                {
+                    depth += 1;
+                    debug!("recursing into {} subdomains, depth {}", found.len(), depth);
+                    match build_recursive_domains(&wordlist_path, &found) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            error!("{}", e);
+                            break;
+                        }
+                    }
+                } else if is_host_round && permutations_pending && !all_found.is_empty() {
+                    permutations_pending = false;
+                    debug!("permuting {} subdomains", all_found.len());
+                    targets
+                        .iter()
+                        .flat_map(|target| {
+                            let found: Vec<String> = all_found
+                                .iter()
+                                .filter(|name| target_of(name, &targets).as_ref() == Some(target))
+                                .cloned()
+                                .collect();
+                            build_permutations(&found, target, &permutation_words)
+                        })
+                        .collect()
+                } else if reverse_pending {
+                    reverse_pending = false;
+                    query_type = DnsQueryType::Ptr;
+                    debug!("reverse lookups of the discovered addresses");
+                    build_ptr_targets(&[], &result_processor.results).unwrap_or_default()
+                } else {
+                    break;
+                };
+
+                domains.retain(|domain| queried.insert(domain.clone()));
+                total_numbers_of_request += domains.len();
+                bar.set_length(total_numbers_of_request as u64);
             }
 
             bar.finish();
@@ -350,10 +748,26 @@ fn main() {
             }
         }
         "vhost" => {
-            let dns_args = extract_dns_args(submatches);
+            let dns_args = match extract_dns_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let vhost_args = extract_vhost_args(submatches);
             let body_args = extract_body_args(submatches);
-            let http_args = extract_http_args(submatches);
-            if !url_is_valid(&http_args.url) {
+            let filter_args = match extract_filter_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let http_args = match extract_http_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets_args = match extract_targets_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets = url_targets(&targets_args.targets, &http_args.url);
+            if targets.is_empty() {
                 return;
             }
 
@@ -361,19 +775,57 @@ fn main() {
                 Err(_) => return,
                 Ok(v) => v,
             };
+            let auth_args = match extract_auth_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let session_args = match extract_session_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
 
-            let vhosts = build_vhosts(&wordlist_args.wordlist_paths[0], &dns_args.domain);
-            let total_numbers_of_request = vhosts.len();
-            let (tx, rx) = channel::<SingleVhostScanResult>();
-            let config = VhostConfig {
-                n_threads: common_args.n_threads,
-                ignore_certificate: http_args.ignore_certificate,
-                http_method: http_args.http_method.to_owned(),
-                user_agent: http_args.user_agent.to_owned(),
-                ignore_strings: body_args.ignore_strings,
-                original_url: http_args.url.to_owned(),
+            let mut builder = VhostScan::builder()
+                .targets(targets.clone())
+                .domain(&dns_args.domain)
+                .wordlist(&wordlist_args.wordlist_paths[0])
+                .similarity_threshold(vhost_args.similarity_threshold)
+                .sni(vhost_args.sni)
+                .harvest(vhost_args.harvest)
+                .threads(common_args.n_threads)
+                .max_per_host(targets_args.max_per_host)
+                .ignore_certificate(http_args.ignore_certificate)
+                .method(&http_args.http_method)
+                .body(&http_args.http_body)
+                .user_agent(&http_args.user_agent);
+            for (name, value) in &http_args.http_headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(session) = session_args.session {
+                builder = builder.session(session);
+            }
+            if let Some(auth) = auth_args.auth {
+                builder = builder.auth(auth);
+            }
+            let scan = match builder.build() {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("{}", e);
+                    return;
+                }
             };
-            let mut result_processor = VhostScanResult::new();
+            let multiple_targets = targets.len() > 1;
+
+            let total_numbers_of_request = scan.total();
+            let (tx, rx) = channel::<SingleVhostScanResult>();
+            let mut result_processor = VhostScanResult::new(
+                ResultFilter::new()
+                    .status_codes(
+                        &http_args.include_status_codes,
+                        &http_args.ignore_status_codes,
+                    )
+                    .body_strings(&body_args.include_strings, &body_args.ignore_strings)
+                    .expressions(&filter_args.matches, &filter_args.filters),
+            );
             let bar = if common_args.no_progress_bar {
                 ProgressBar::hidden()
             } else {
@@ -384,7 +836,11 @@ fn main() {
                 .template("{spinner} [{elapsed_precise}] {bar:40.red/white} {pos:>7}/{len:7} ETA: {eta_precise} req/s: {msg}")
                 .progress_chars("#>-"));
 
-            thread::spawn(move || vhostbuster::run(tx, vhosts, config));
+            thread::spawn(move || {
+                if let Err(e) = scan.run(tx) {
+                    error!("{}", e);
+                }
+            });
 
             while current_numbers_of_request != total_numbers_of_request {
                 current_numbers_of_request = current_numbers_of_request + 1;
@@ -409,8 +865,9 @@ fn main() {
 
                 match &msg.error {
                     Some(e) => {
-                        error!("{} - {:?}", msg.vhost, e);
-                        if current_numbers_of_request == 1 || common_args.exit_on_connection_errors
+                        error!("{} - {} - {:?}", msg.target, msg.vhost, e);
+                        if (current_numbers_of_request == 1 && !multiple_targets)
+                            || common_args.exit_on_connection_errors
                         {
                             warn!("Check connectivity to the target");
                             break;
@@ -429,8 +886,7 @@ fn main() {
                     _ => 0,
                 };
 
-                if !msg.ignored {
-                    result_processor.maybe_add_result(msg.clone());
+                if !msg.ignored && result_processor.maybe_add_result(msg.clone()) {
                     if common_args.no_progress_bar {
                         println!(
                             "{}\t{}{}{}",
@@ -448,6 +904,40 @@ fn main() {
                             msg.vhost
                         ));
                     }
+
+                    if multiple_targets {
+                        print_line(
+                            &bar,
+                            common_args.no_progress_bar,
+                            format!("\t\tTarget: {}", msg.target),
+                        );
+                    }
+
+                    if let Some(source) = &msg.source {
+                        print_line(
+                            &bar,
+                            common_args.no_progress_bar,
+                            format!("\t\tSource: {}", source),
+                        );
+                    }
+
+                    if let Some(certificate) = &msg.certificate {
+                        print_line(
+                            &bar,
+                            common_args.no_progress_bar,
+                            format!(
+                                "\t\tCertificate: {} ({})",
+                                certificate.subject, certificate.fingerprint
+                            ),
+                        );
+                        if !certificate.sans.is_empty() {
+                            print_line(
+                                &bar,
+                                common_args.no_progress_bar,
+                                format!("\t\tSANs: {}", certificate.sans.join(", ")),
+                            );
+                        }
+                    }
                 }
             }
 
@@ -458,70 +948,741 @@ fn main() {
                 save_vhost_results(&common_args.output, &result_processor.results);
             }
         }
-        "fuzz" => {
-            let http_args = extract_http_args(submatches);
-            if !url_is_valid(&http_args.url) {
-                return;
-            }
-
-            let wordlist_args = match extract_wordlist_args(submatches) {
+        "probe" => {
+            let probe_args = match extract_probe_args(submatches) {
                 Err(_) => return,
                 Ok(v) => v,
             };
-
-            let body_args = extract_body_args(submatches);
-            let fuzz_args = extract_fuzz_args(submatches);
-
-            let fuzzbuster = FuzzBuster {
-                n_threads: common_args.n_threads,
-                ignore_certificate: http_args.ignore_certificate,
-                http_method: http_args.http_method.to_owned(),
-                http_body: http_args.http_body.to_owned(),
-                user_agent: http_args.user_agent.to_owned(),
-                http_headers: http_args.http_headers,
-                wordlist_paths: wordlist_args.wordlist_paths,
-                url: http_args.url.to_owned(),
-                ignore_status_codes: http_args.ignore_status_codes,
-                include_status_codes: http_args.include_status_codes,
-                no_progress_bar: common_args.no_progress_bar,
-                exit_on_connection_errors: common_args.exit_on_connection_errors,
-                output: common_args.output.to_owned(),
-                include_body: body_args.include_strings,
-                ignore_body: body_args.ignore_strings,
-                csrf_url: fuzz_args.csrf_url,
-                csrf_regex: fuzz_args.csrf_regex,
-                csrf_headers: fuzz_args.csrf_headers,
-            };
-
-            debug!("FuzzBuster {:#?}", fuzzbuster);
-
-            fuzzbuster.run();
+            let filter_args = match extract_filter_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets_args = match extract_targets_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let auth_args = match extract_auth_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let session_args = match extract_session_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let hosts: Vec<String> = probe_args
+                .hosts
+                .iter()
+                .chain(targets_args.targets.iter())
+                .cloned()
+                .collect();
+            let mut builder = ProbeScan::builder()
+                .hosts(hosts)
+                .ports(probe_args.ports.clone())
+                .threads(common_args.n_threads)
+                .max_per_host(targets_args.max_per_host)
+                .user_agent(&probe_args.user_agent)
+                .timeout(Duration::from_millis(probe_args.timeout));
+            for (name, value) in &probe_args.http_headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(session) = session_args.session {
+                builder = builder.session(session);
+            }
+            if let Some(auth) = auth_args.auth {
+                builder = builder.auth(auth);
+            }
+            let scan = match builder.build() {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("{}", e);
+                    return;
+                }
+            };
+
+            let total_numbers_of_request = scan.total();
+            let (tx, rx) = channel::<SingleProbeScanResult>();
+            let mut result_processor = ProbeScanResult::new(
+                ResultFilter::new().expressions(&filter_args.matches, &filter_args.filters),
+            );
+
+            let bar = if common_args.no_progress_bar {
+                ProgressBar::hidden()
+            } else {
+                ProgressBar::new(total_numbers_of_request as u64)
+            };
+            bar.set_draw_delta(100);
+            bar.set_style(ProgressStyle::default_bar()
+                .template("{spinner} [{elapsed_precise}] {bar:40.red/white} {pos:>7}/{len:7} ETA: {eta_precise} req/s: {msg}")
+                .progress_chars("#>-"));
+
+            thread::spawn(move || {
+                if let Err(e) = scan.run(tx) {
+                    error!("{}", e);
+                }
+            });
+
+            while current_numbers_of_request != total_numbers_of_request {
+                current_numbers_of_request += 1;
+                bar.inc(1);
+                let seconds_from_start = start_time.elapsed().unwrap().as_millis() / 1000;
+                if seconds_from_start != 0 {
+                    bar.set_message(
+                        &(current_numbers_of_request as u64 / seconds_from_start as u64)
+                            .to_string(),
+                    );
+                } else {
+                    bar.set_message("warming up...")
+                }
+
+                let msg = match rx.recv() {
+                    Ok(msg) => msg,
+                    Err(_err) => {
+                        error!("{:?}", _err);
+                        break;
+                    }
+                };
+
+                if !result_processor.maybe_add_result(msg.clone()) {
+                    continue;
+                }
+
+                let status = msg.status.unwrap_or_default();
+                let n_tabs = match status.len() / 8 {
+                    3 => 1,
+                    2 => 2,
+                    1 => 3,
+                    0 => 4,
+                    _ => 0,
+                };
+                print_line(
+                    &bar,
+                    common_args.no_progress_bar,
+                    format!("{}{}{}", status, "\t".repeat(n_tabs), msg.url),
+                );
+
+                let details = vec![
+                    ("Title", msg.title),
+                    ("Server", msg.server),
+                    ("Length", msg.length.map(|length| length.to_string())),
+                    ("Redirect", msg.redirect),
+                ];
+                for (name, value) in details {
+                    if let Some(value) = value {
+                        print_line(
+                            &bar,
+                            common_args.no_progress_bar,
+                            format!("\t\t{}: {}", name, value),
+                        );
+                    }
+                }
+
+                if let Some(certificate) = &msg.certificate {
+                    print_line(
+                        &bar,
+                        common_args.no_progress_bar,
+                        format!(
+                            "\t\tCertificate: {} ({})",
+                            certificate.subject, certificate.fingerprint
+                        ),
+                    );
+                    if !certificate.sans.is_empty() {
+                        print_line(
+                            &bar,
+                            common_args.no_progress_bar,
+                            format!("\t\tSANs: {}", certificate.sans.join(", ")),
+                        );
+                    }
+                }
+            }
+
+            bar.finish();
+            println!("{}", banner::ending_time());
+
+            if !common_args.output.is_empty() {
+                save_probe_results(&common_args.output, &result_processor.results);
+            }
+
+            if let Some(path) = &probe_args.alive_output {
+                save_alive_targets(path, &result_processor.results);
+            }
+        }
+        "fuzz" => {
+            let http_args = match extract_http_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets_args = match extract_targets_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets = url_targets(&targets_args.targets, &http_args.url);
+            if targets.is_empty() {
+                return;
+            }
+
+            let wordlist_args = match extract_wordlist_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+
+            let body_args = extract_body_args(submatches);
+            let filter_args = match extract_filter_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let session_args = match extract_session_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let fuzz_args = match extract_fuzz_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let auth_args = match extract_auth_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+
+            let mut builder = FuzzScan::builder()
+                .targets(targets.clone())
+                .threads(common_args.n_threads)
+                .max_per_host(targets_args.max_per_host)
+                .ignore_certificate(http_args.ignore_certificate)
+                .method(&http_args.http_method)
+                .body(&http_args.http_body)
+                .user_agent(&http_args.user_agent);
+            for wordlist in &wordlist_args.wordlist_paths {
+                builder = builder.wordlist(wordlist);
+            }
+            for (name, value) in &http_args.http_headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(csrf_url) = &fuzz_args.csrf_url {
+                builder = builder.csrf(csrf_url, fuzz_args.csrf_regex.as_deref().unwrap_or(""));
+            }
+            for (name, value) in fuzz_args.csrf_headers.iter().flatten() {
+                builder = builder.csrf_header(name, value);
+            }
+            builder = builder.csrf_reuse(fuzz_args.csrf_reuse);
+            if let Some(csrf_invalid) = fuzz_args.csrf_invalid.clone() {
+                builder = builder.csrf_invalid(csrf_invalid);
+            }
+            if let Some(pre_request) = fuzz_args.pre_request.clone() {
+                builder = builder.pre_request(pre_request);
+            }
+            if let Some(session) = session_args.session {
+                builder = builder.session(session);
+            }
+            if let Some(auth) = auth_args.auth {
+                builder = builder.auth(auth);
+            }
+
+            debug!("FuzzScanBuilder {:#?}", builder);
+
+            let scan = match builder.build() {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("{}", e);
+                    return;
+                }
+            };
+
+            let total_numbers_of_request = scan.total();
+            let (tx, rx) = channel::<SingleFuzzScanResult>();
+            let mut result_processor = FuzzScanProcessor::new(
+                ResultFilter::new()
+                    .status_codes(
+                        &http_args.include_status_codes,
+                        &http_args.ignore_status_codes,
+                    )
+                    .body_strings(&body_args.include_strings, &body_args.ignore_strings)
+                    .expressions(&filter_args.matches, &filter_args.filters),
+            );
+            let bar = if common_args.no_progress_bar {
+                ProgressBar::hidden()
+            } else {
+                ProgressBar::new(total_numbers_of_request as u64)
+            };
+            bar.set_draw_delta(100);
+            bar.set_style(ProgressStyle::default_bar()
+                .template("{spinner} [{elapsed_precise}] {bar:40.red/white} {pos:>7}/{len:7} ETA: {eta_precise} req/s: {msg}")
+                .progress_chars("#>-"));
+
+            thread::spawn(move || {
+                if let Err(e) = scan.run(tx) {
+                    error!("{}", e);
+                }
+            });
+
+            while current_numbers_of_request != total_numbers_of_request {
+                current_numbers_of_request += 1;
+                bar.inc(1);
+                let seconds_from_start = start_time.elapsed().unwrap().as_millis() / 1000;
+                if seconds_from_start != 0 {
+                    bar.set_message(
+                        &(current_numbers_of_request as u64 / seconds_from_start as u64)
+                            .to_string(),
+                    );
+                } else {
+                    bar.set_message("warming up...")
+                }
+
+                let msg = match rx.recv() {
+                    Ok(msg) => msg,
+                    Err(_err) => {
+                        error!("{:?}", _err);
+                        break;
+                    }
+                };
+
+                if let Some(e) = &msg.error {
+                    error!("{} - {:?}", msg.url, e);
+                    if (current_numbers_of_request == 1 && targets.len() == 1)
+                        || common_args.exit_on_connection_errors
+                    {
+                        warn!("Check connectivity to the target");
+                        break;
+                    }
+
+                    continue;
+                }
+
+                if result_processor.maybe_add_result(msg.clone()) {
+                    let mut extra = msg.extra.unwrap_or_default();
+
+                    if !extra.is_empty() {
+                        extra = format!("\n\t\t\t\t\t\t=> {}", extra)
+                    }
+
+                    let n_tabs = match msg.status.len() / 8 {
+                        3 => 1,
+                        2 => 2,
+                        1 => 3,
+                        0 => 4,
+                        _ => 0,
+                    };
+
+                    if common_args.no_progress_bar {
+                        println!(
+                            "{}\t{}{}{} ({:?}){}",
+                            msg.method,
+                            msg.status,
+                            "\t".repeat(n_tabs),
+                            msg.url,
+                            msg.payload,
+                            extra,
+                        );
+                    } else {
+                        bar.println(format!(
+                            "{}\t{}{}{}\n\t\t\t\t\t\t=> PAYLOAD: {:?}{}",
+                            msg.method,
+                            msg.status,
+                            "\t".repeat(n_tabs),
+                            msg.url,
+                            msg.payload,
+                            extra,
+                        ));
+                    }
+                }
+            }
+
+            bar.finish();
+            println!("{}", banner::ending_time());
+
+            if !common_args.output.is_empty() {
+                result_processor.save_fuzz_results(&common_args.output);
+            }
+        }
+        "login" => {
+            let http_args = match extract_http_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets_args = match extract_targets_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets = url_targets(&targets_args.targets, &http_args.url);
+            if targets.is_empty() {
+                return;
+            }
+
+            let filter_args = match extract_filter_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let csrf_args = extract_csrf_args(submatches);
+            let login_args = match extract_login_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+
+            let mut builder = LoginScan::builder()
+                .targets(targets)
+                .threads(common_args.n_threads)
+                .max_per_host(targets_args.max_per_host)
+                .ignore_certificate(http_args.ignore_certificate)
+                .login_type(login_args.login_type)
+                .body(&http_args.http_body)
+                .user_agent(&http_args.user_agent)
+                .lockout_pause(Duration::from_secs(login_args.lockout_pause))
+                .similarity_threshold(login_args.similarity_threshold);
+            if submatches.occurrences_of("http-method") > 0 {
+                builder = builder.method(&http_args.http_method);
+            }
+            for path in &login_args.usernames {
+                builder = builder.usernames(path);
+            }
+            for path in &login_args.passwords {
+                builder = builder.passwords(path);
+            }
+            for path in &login_args.combos {
+                builder = builder.combos(path);
+            }
+            for (name, value) in &http_args.http_headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(csrf_url) = &csrf_args.csrf_url {
+                builder = builder.csrf(csrf_url, csrf_args.csrf_regex.as_deref().unwrap_or(""));
+            }
+            for (name, value) in csrf_args.csrf_headers.iter().flatten() {
+                builder = builder.csrf_header(name, value);
+            }
+            if let Some(success) = login_args.success {
+                builder = builder.success(success);
+            }
+            if let Some(lockout) = login_args.lockout {
+                builder = builder.lockout(lockout);
+            }
+
+            debug!("LoginScanBuilder {:#?}", builder);
+
+            let scan = match builder.build() {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("{}", e);
+                    return;
+                }
+            };
+
+            let total_numbers_of_request = scan.total();
+            let (tx, rx) = channel::<SingleLoginScanResult>();
+            let mut result_processor = LoginScanProcessor::new(
+                ResultFilter::new()
+                    .status_codes(
+                        &http_args.include_status_codes,
+                        &http_args.ignore_status_codes,
+                    )
+                    .expressions(&filter_args.matches, &filter_args.filters),
+            );
+            let bar = if common_args.no_progress_bar {
+                ProgressBar::hidden()
+            } else {
+                ProgressBar::new(total_numbers_of_request as u64)
+            };
+            bar.set_draw_delta(10);
+            bar.set_style(ProgressStyle::default_bar()
+                .template("{spinner} [{elapsed_precise}] {bar:40.red/white} {pos:>7}/{len:7} ETA: {eta_precise} req/s: {msg}")
+                .progress_chars("#>-"));
+
+            thread::spawn(move || {
+                if let Err(e) = scan.run(tx) {
+                    error!("{}", e);
+                }
+            });
+
+            for msg in rx {
+                current_numbers_of_request += 1;
+                bar.inc(1);
+                let seconds_from_start = start_time.elapsed().unwrap().as_millis() / 1000;
+                if seconds_from_start != 0 {
+                    bar.set_message(
+                        &(current_numbers_of_request as u64 / seconds_from_start as u64)
+                            .to_string(),
+                    );
+                } else {
+                    bar.set_message("warming up...")
+                }
+
+                if let Some(e) = &msg.error {
+                    error!("{} - {:?}", msg.url, e);
+                    if common_args.exit_on_connection_errors {
+                        warn!("Check connectivity to the target");
+                        break;
+                    }
+
+                    continue;
+                }
+
+                if result_processor.maybe_add_result(msg.clone()) {
+                    print_line(
+                        &bar,
+                        common_args.no_progress_bar,
+                        format!(
+                            "{}\t{}\t{} ({}:{})",
+                            msg.method, msg.status, msg.url, msg.username, msg.password
+                        ),
+                    );
+                }
+            }
+
+            bar.finish();
+            println!("{}", banner::ending_time());
+
+            if !common_args.output.is_empty() {
+                result_processor.save_login_results(&common_args.output);
+            }
         }
         "tilde" => {
-            let http_args = extract_http_args(submatches);
-            if !url_is_valid(&http_args.url) {
+            let http_args = match extract_http_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets_args = match extract_targets_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let targets = url_targets(&targets_args.targets, &http_args.url);
+            if targets.is_empty() {
                 return;
             }
 
             let tilde_args = extract_tilde_args(submatches);
-            let tildebuster = TildeBuster {
-                n_threads: common_args.n_threads,
-                ignore_certificate: http_args.ignore_certificate,
-                http_method: http_args.http_method.to_owned(),
-                http_body: http_args.http_body.to_owned(),
-                user_agent: http_args.user_agent.to_owned(),
-                http_headers: http_args.http_headers,
-                url: http_args.url.to_owned(),
-                no_progress_bar: common_args.no_progress_bar,
-                exit_on_connection_errors: common_args.exit_on_connection_errors,
-                output: common_args.output.to_owned(),
-                extension: tilde_args.extension,
+            let auth_args = match extract_auth_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let session_args = match extract_session_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let filter_args = match extract_filter_args(submatches) {
+                Err(_) => return,
+                Ok(v) => v,
+            };
+            let ignore_status_codes = if submatches.occurrences_of("ignore-status-codes") == 0 {
+                StatusMatcher::default()
+            } else {
+                http_args.ignore_status_codes.clone()
             };
+            let filter = ResultFilter::new()
+                .status_codes(&http_args.include_status_codes, &ignore_status_codes)
+                .expressions(&filter_args.matches, &filter_args.filters);
+            let mut builder = TildeScan::builder()
+                .targets(targets.clone())
+                .threads(common_args.n_threads)
+                .max_per_host(targets_args.max_per_host)
+                .ignore_certificate(http_args.ignore_certificate)
+                .method(&http_args.http_method)
+                .body(&http_args.http_body)
+                .user_agent(&http_args.user_agent);
+            for (name, value) in &http_args.http_headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(extension) = &tilde_args.extension {
+                builder = builder.extension(extension);
+            }
+            if let Some(session) = session_args.session {
+                builder = builder.session(session);
+            }
+            if let Some(auth) = auth_args.auth {
+                builder = builder.auth(auth);
+            }
 
-            debug!("TildeBuster {:#?}", tildebuster);
+            debug!("TildeScanBuilder {:#?}", builder);
 
-            tildebuster.run();
+            let scan = match builder.build() {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("{}", e);
+                    return;
+                }
+            };
+
+            let (tx, rx) = channel::<SingleTildeScanResult>();
+            let mut result_processor = TildeScanProcessor::new(filter);
+            let multiple_targets = targets.len() > 1;
+            let location = |request: &TildeRequest| {
+                if multiple_targets {
+                    request.url.clone()
+                } else {
+                    String::new()
+                }
+            };
+            let bar = if common_args.no_progress_bar {
+                ProgressBar::hidden()
+            } else {
+                ProgressBar::new_spinner()
+            };
+            bar.set_style(
+                ProgressStyle::default_spinner().template("{spinner} [{elapsed_precise}] {msg}"),
+            );
+
+            thread::spawn(move || {
+                if let Err(e) = scan.run(tx) {
+                    error!("{}", e);
+                }
+            });
+
+            for msg in rx {
+                current_numbers_of_request += 1;
+                bar.inc(1);
+                let seconds_from_start = start_time.elapsed().unwrap().as_millis() / 1000;
+                if seconds_from_start != 0 {
+                    bar.set_message(&format!(
+                        "{} requests done | req/s: {}",
+                        current_numbers_of_request,
+                        current_numbers_of_request as u64 / seconds_from_start as u64,
+                    ));
+                } else {
+                    bar.set_message("warming up...")
+                }
+
+                if let Some(e) = &msg.error {
+                    error!("{} - {:?}", msg.request.url, e);
+                    if (current_numbers_of_request == 1 && !multiple_targets)
+                        || common_args.exit_on_connection_errors
+                    {
+                        warn!("Check connectivity to the target");
+                        break;
+                    }
+
+                    continue;
+                }
+
+                match msg.kind {
+                    FSObject::NotVulnerable => {
+                        error!("{} doesn't seem to be vulnerable", msg.request.url);
+                        warn!("Try setting HTTP method to OPTIONS or add an extension like aspx");
+                    }
+                    FSObject::File | FSObject::DuplicateFile => {
+                        let line = format!(
+                            "File\t\t{}{}~{}.{}",
+                            location(&msg.request),
+                            msg.request.filename,
+                            msg.request.duplicate_index,
+                            msg.request.extension,
+                        );
+                        if result_processor.maybe_add_result(msg) {
+                            print_line(&bar, common_args.no_progress_bar, line);
+                        }
+                    }
+                    FSObject::Directory | FSObject::DuplicateDirectory => {
+                        let line = format!(
+                            "Directory\t{}{}~{}",
+                            location(&msg.request),
+                            msg.request.filename,
+                            msg.request.duplicate_index,
+                        );
+                        if result_processor.maybe_add_result(msg) {
+                            print_line(&bar, common_args.no_progress_bar, line);
+                        }
+                    }
+                    _ => trace!("{:?}", msg),
+                }
+            }
+
+            bar.finish();
+            println!("{}", banner::ending_time());
+
+            if !common_args.output.is_empty() {
+                result_processor.save_tilde_results(&common_args.output);
+            }
         }
         _ => (),
     }
 }
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("rustbuster")
+        .global_setting(AppSettings::AllArgsOverrideSelf)
+        .version(crate_version!())
+        .author("by phra & ps1dr3x")
+        .about("DirBuster for rust")
+        .after_help("EXAMPLES:
+    1. Dir mode:
+        rustbuster dir -u http://localhost:3000/ -w examples/wordlist -e php
+    2. Dns mode:
+        rustbuster dns -d google.com -w examples/wordlist
+    3. Vhost mode:
+        rustbuster vhost -u http://localhost:3000/ -w examples/wordlist -d test.local -x \"Hello\"
+    4. Fuzz mode:
+        rustbuster fuzz -u http://localhost:3000/login \\
+            -X POST \\
+            -H \"Content-Type: application/json\" \\
+            -b '{\"user\":\"FUZZ\",\"password\":\"FUZZ\",\"csrf\":\"CSRFCSRF\"}' \\
+            -w examples/wordlist \\
+            -w /usr/share/seclists/Passwords/Common-Credentials/10-million-password-list-top-10000.txt \\
+            -s 200 \\
+            --csrf-url \"http://localhost:3000/csrf\" \\
+            --csrf-regex '\\{\"csrf\":\"(\\w+)\"\\}'
+    5. Tilde mode:
+        rustbuster tilde -u http://localhost:3000/ -e aspx -X OPTIONS
+    6. Ptr mode:
+        rustbuster ptr --range 192.168.1.0/24
+    7. Srv mode:
+        rustbuster srv -d corp.local
+    8. Probe mode:
+        rustbuster probe --hosts 192.168.1.0/24 -p 80,443,8080 --alive-output alive.txt
+    9. Login mode:
+        rustbuster login -u http://localhost:3000/login --combos combos.txt --login-type json
+")
+        .subcommand(set_targets_args(set_wordlist_args(set_dir_args(set_auth_args(set_session_args(set_filter_args(set_body_args(set_http_args(set_common_args(SubCommand::with_name("dir"))))))))))
+            .about("Directories and files enumeration mode")
+            .after_help("EXAMPLE:
+    rustbuster dir -u http://localhost:3000/ -w examples/wordlist -e php"))
+        .subcommand(set_targets_args(set_wordlist_args(set_resolver_args(set_dns_args(set_filter_args(set_common_args(SubCommand::with_name("dns")))))))
+            .about("A/AAAA entries enumeration mode")
+            .after_help("EXAMPLE:
+    rustbuster dns -d google.com -w examples/wordlist"))
+        .subcommand(set_resolver_args(set_ptr_args(set_filter_args(set_common_args(SubCommand::with_name("ptr")))))
+            .about("PTR entries enumeration mode")
+            .after_help("EXAMPLE:
+    rustbuster ptr --range 192.168.1.0/24"))
+        .subcommand(set_resolver_args(set_srv_args(set_filter_args(set_common_args(SubCommand::with_name("srv")))))
+            .about("SRV entries enumeration mode")
+            .after_help("EXAMPLE:
+    rustbuster srv -d corp.local"))
+        .subcommand(set_targets_args(set_wordlist_args(set_vhost_args(set_auth_args(set_session_args(set_filter_args(set_body_args(set_http_args(set_common_args(SubCommand::with_name("vhost"))))))))))
+            .about("Virtual hosts enumeration mode")
+            .after_help("EXAMPLE:
+    rustbuster vhost -u http://localhost:3000/ -w examples/wordlist -d test.local -x \"Hello\""))
+        .subcommand(set_targets_args(set_tilde_args(set_auth_args(set_session_args(set_filter_args(set_http_args(set_common_args(SubCommand::with_name("tilde"))))))))
+            .about("IIS 8.3 shortname enumeration mode")
+            .after_help("EXAMPLE:
+    rustbuster tilde -u http://localhost:3000/ -e aspx -X OPTIONS"))
+        .subcommand(set_targets_args(set_filter_args(set_auth_args(set_session_args(set_probe_args(set_common_args(SubCommand::with_name("probe")))))))
+            .about("HTTP and HTTPS services discovery mode")
+            .after_help("EXAMPLE:
+    rustbuster probe --hosts 192.168.1.0/24 -p 80,443,8080 --alive-output alive.txt"))
+        .subcommand(set_targets_args(set_wordlist_args(set_fuzz_args(set_csrf_args(set_auth_args(set_session_args(set_filter_args(set_body_args(set_http_args(set_common_args(SubCommand::with_name("fuzz")))))))))))
+            .about("Custom fuzzing enumeration mode")
+            .after_help("EXAMPLE:
+    rustbuster fuzz -u http://localhost:3000/login \\
+        -X POST \\
+        -H \"Content-Type: application/json\" \\
+        -b '{\"user\":\"FUZZ\",\"password\":\"FUZZ\",\"csrf\":\"CSRFCSRF\"}' \\
+        -w examples/wordlist \\
+        -w /usr/share/seclists/Passwords/Common-Credentials/10-million-password-list-top-10000.txt \\
+        -s 200 \\
+        --csrf-url \"http://localhost:3000/csrf\" \\
+        --csrf-regex '\\{\"csrf\":\"(\\w+)\"\\}'"))
+        .subcommand(set_targets_args(set_login_args(set_csrf_args(set_filter_args(set_http_args(set_common_args(SubCommand::with_name("login")))))))
+            .about("Login bruteforce and password spraying mode")
+            .after_help("EXAMPLE:
+    rustbuster login -u http://localhost:3000/login \\
+        --login-type json \\
+        -b '{\"user\":\"{{username}}\",\"password\":\"{{password}}\",\"csrf\":\"CSRFCSRF\"}' \\
+        --usernames examples/wordlist \\
+        --passwords /usr/share/seclists/Passwords/Common-Credentials/10-million-password-list-top-10000.txt \\
+        --csrf-url \"http://localhost:3000/csrf\" \\
+        --csrf-regex '\\{\"csrf\":\"(\\w+)\"\\}'"))
+}
+
+fn print_line(bar: &ProgressBar, no_progress_bar: bool, line: String) {
+    if no_progress_bar {
+        println!("{}", line);
+    } else {
+        bar.println(line);
+    }
+}
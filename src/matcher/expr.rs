@@ -0,0 +1,600 @@
+use regex::{Regex, RegexBuilder};
+
+use std::{str::FromStr, time::Duration};
+
+use super::{status_code, Matchable, Matcher};
+use crate::error::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(u64),
+    Duration(Duration),
+    Regex(String, String),
+    Str(String),
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+    And,
+    Or,
+    Not,
+    In,
+    Range,
+    Comma,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => format!("`{}`", name),
+            Token::Number(n) => format!("`{}`", n),
+            Token::Duration(d) => format!("`{:?}`", d),
+            Token::Regex(regex, flags) => format!("`/{}/{}`", regex, flags),
+            Token::Str(s) => format!("`\"{}\"`", s),
+            Token::Eq => "`==`".to_owned(),
+            Token::Ne => "`!=`".to_owned(),
+            Token::Lt => "`<`".to_owned(),
+            Token::Le => "`<=`".to_owned(),
+            Token::Gt => "`>`".to_owned(),
+            Token::Ge => "`>=`".to_owned(),
+            Token::Match => "`~`".to_owned(),
+            Token::NotMatch => "`!~`".to_owned(),
+            Token::And => "`&&`".to_owned(),
+            Token::Or => "`||`".to_owned(),
+            Token::Not => "`!`".to_owned(),
+            Token::In => "`in`".to_owned(),
+            Token::Range => "`..`".to_owned(),
+            Token::Comma => "`,`".to_owned(),
+            Token::LParen => "`(`".to_owned(),
+            Token::RParen => "`)`".to_owned(),
+            Token::LBracket => "`[`".to_owned(),
+            Token::RBracket => "`]`".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Field {
+    Status,
+    Size,
+    Time,
+    Body,
+    Header(String),
+}
+
+#[derive(Debug, Clone)]
+enum Test {
+    Compare(Token, u64),
+    Range(u64, u64),
+    List(Vec<u64>),
+    Regex(Regex),
+    Equals(String),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Test(Field, Test, bool),
+}
+
+#[derive(Debug, Clone)]
+pub struct Expression {
+    expression: String,
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(expression: &str) -> Result<Self, Error> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            expression,
+            tokens,
+            position: 0,
+        };
+        let root = parser.parse_or()?;
+        if let Some((column, token)) = parser.tokens.get(parser.position) {
+            return Err(parser.error_at(
+                *column,
+                format!("unexpected {}, expected `&&` or `||`", token.describe()),
+            ));
+        }
+
+        Ok(Expression {
+            expression: expression.to_owned(),
+            root,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.expression
+    }
+}
+
+impl FromStr for Expression {
+    type Err = Error;
+
+    fn from_str(expression: &str) -> Result<Self, Error> {
+        Expression::parse(expression)
+    }
+}
+
+impl<T: Matchable> Matcher<T> for Expression {
+    fn matches(&self, result: &T) -> bool {
+        eval(&self.root, result)
+    }
+
+    fn needs_body(&self) -> bool {
+        uses_body(&self.root)
+    }
+}
+
+fn uses_body(node: &Node) -> bool {
+    match node {
+        Node::And(left, right) | Node::Or(left, right) => uses_body(left) || uses_body(right),
+        Node::Not(node) => uses_body(node),
+        Node::Test(field, _, _) => matches!(field, Field::Body),
+    }
+}
+
+fn eval<T: Matchable>(node: &Node, result: &T) -> bool {
+    match node {
+        Node::And(left, right) => eval(left, result) && eval(right, result),
+        Node::Or(left, right) => eval(left, result) || eval(right, result),
+        Node::Not(node) => !eval(node, result),
+        Node::Test(field, test, negated) => {
+            let matched = match field {
+                Field::Status => status_code(result).map(|code| test_number(test, u64::from(code))),
+                Field::Size => result.length().map(|size| test_number(test, size as u64)),
+                Field::Time => result
+                    .time()
+                    .map(|time| test_number(test, time.as_millis() as u64)),
+                Field::Body => result.body().map(|body| test_text(test, body)),
+                Field::Header(name) => result.header(name).map(|value| test_text(test, value)),
+            };
+
+            match matched {
+                Some(matched) => matched != *negated,
+                None => false,
+            }
+        }
+    }
+}
+
+fn test_number(test: &Test, value: u64) -> bool {
+    match test {
+        Test::Compare(Token::Eq, n) => value == *n,
+        Test::Compare(Token::Ne, n) => value != *n,
+        Test::Compare(Token::Lt, n) => value < *n,
+        Test::Compare(Token::Le, n) => value <= *n,
+        Test::Compare(Token::Gt, n) => value > *n,
+        Test::Compare(Token::Ge, n) => value >= *n,
+        Test::Range(min, max) => value >= *min && value <= *max,
+        Test::List(values) => values.contains(&value),
+        _ => false,
+    }
+}
+
+fn test_text(test: &Test, value: &str) -> bool {
+    match test {
+        Test::Regex(regex) => regex.is_match(value),
+        Test::Equals(string) => value == string,
+        _ => false,
+    }
+}
+
+fn invalid(expression: &str, column: usize, reason: String) -> Error {
+    Error::InvalidExpression {
+        expression: expression.to_owned(),
+        column,
+        reason,
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>, Error> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let next = chars.get(i + 1).cloned();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let (token, len) = match (c, next) {
+            ('=', Some('=')) => (Token::Eq, 2),
+            ('!', Some('=')) => (Token::Ne, 2),
+            ('!', Some('~')) => (Token::NotMatch, 2),
+            ('<', Some('=')) => (Token::Le, 2),
+            ('>', Some('=')) => (Token::Ge, 2),
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('.', Some('.')) => (Token::Range, 2),
+            ('!', _) => (Token::Not, 1),
+            ('<', _) => (Token::Lt, 1),
+            ('>', _) => (Token::Gt, 1),
+            ('~', _) => (Token::Match, 1),
+            (',', _) => (Token::Comma, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('[', _) => (Token::LBracket, 1),
+            (']', _) => (Token::RBracket, 1),
+            ('/', _) | ('"', _) => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j) {
+                        None => {
+                            let kind = if c == '/' { "regex" } else { "string" };
+                            return Err(invalid(
+                                expression,
+                                column,
+                                format!("unterminated {}", kind),
+                            ));
+                        }
+                        Some('\\') if chars.get(j + 1) == Some(&c) => {
+                            value.push(c);
+                            j += 2;
+                        }
+                        Some(&d) if d == c => break,
+                        Some(&d) => {
+                            value.push(d);
+                            j += 1;
+                        }
+                    }
+                }
+                j += 1;
+
+                if c == '"' {
+                    (Token::Str(value), j - i)
+                } else {
+                    let mut flags = String::new();
+                    while let Some(&flag) = chars.get(j).filter(|f| f.is_ascii_alphabetic()) {
+                        if !"imsx".contains(flag) {
+                            return Err(invalid(
+                                expression,
+                                j + 1,
+                                format!("unknown regex flag `{}`, expected i, m, s or x", flag),
+                            ));
+                        }
+                        flags.push(flag);
+                        j += 1;
+                    }
+                    (Token::Regex(value, flags), j - i)
+                }
+            }
+            (c, _) if c.is_ascii_digit() => {
+                let digits: String = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect();
+                let unit: String = chars[i + digits.len()..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphabetic())
+                    .collect();
+                let value = digits
+                    .parse::<u64>()
+                    .map_err(|e| invalid(expression, column, e.to_string()))?;
+                let token = match unit.as_str() {
+                    "" => Token::Number(value),
+                    "ms" => Token::Duration(Duration::from_millis(value)),
+                    "s" => Token::Duration(Duration::from_secs(value)),
+                    "m" => match value.checked_mul(60) {
+                        Some(secs) => Token::Duration(Duration::from_secs(secs)),
+                        None => {
+                            return Err(invalid(
+                                expression,
+                                column,
+                                "duration too large".to_owned(),
+                            ))
+                        }
+                    },
+                    _ => {
+                        return Err(invalid(
+                            expression,
+                            i + digits.len() + 1,
+                            format!("unknown unit `{}`, expected ms, s or m", unit),
+                        ))
+                    }
+                };
+                (token, digits.len() + unit.len())
+            }
+            (c, _) if c.is_ascii_alphabetic() => {
+                let word: String = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || "_-.".contains(**c))
+                    .collect();
+                let word = word.trim_end_matches('.').to_owned();
+                let len = word.len();
+                match word.as_str() {
+                    "in" => (Token::In, len),
+                    "and" => (Token::And, len),
+                    "or" => (Token::Or, len),
+                    "not" => (Token::Not, len),
+                    _ => (Token::Ident(word), len),
+                }
+            }
+            _ => {
+                return Err(invalid(
+                    expression,
+                    column,
+                    format!("unexpected character `{}`", c),
+                ))
+            }
+        };
+
+        tokens.push((column, token));
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    expression: &'a str,
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error_at(&self, column: usize, reason: String) -> Error {
+        invalid(self.expression, column, reason)
+    }
+
+    fn column(&self) -> usize {
+        match self.tokens.get(self.position) {
+            Some((column, _)) => *column,
+            None => self.expression.chars().count() + 1,
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<(usize, Token), Error> {
+        match self.tokens.get(self.position).cloned() {
+            Some(token) => {
+                self.position += 1;
+                Ok(token)
+            }
+            None => Err(self.error_at(
+                self.column(),
+                format!("unexpected end of expression, expected {}", expected),
+            )),
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), Error> {
+        let (column, found) = self.next(&token.describe())?;
+        if found != token {
+            return Err(self.error_at(
+                column,
+                format!("expected {}, found {}", token.describe(), found.describe()),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Node, Error> {
+        let mut node = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, Error> {
+        let mut node = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            node = Node::And(Box::new(node), Box::new(self.parse_unary()?));
+        }
+
+        Ok(node)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, Error> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.position += 1;
+                Ok(Node::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::LParen) => {
+                self.position += 1;
+                let node = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(node)
+            }
+            _ => self.parse_test(),
+        }
+    }
+
+    fn parse_test(&mut self) -> Result<Node, Error> {
+        let expected_field = "a field such as status, size, time, body or header.<name>";
+        let (column, token) = self.next(expected_field)?;
+        let field = match token {
+            Token::Ident(ref name) => match name.as_str() {
+                "status" => Field::Status,
+                "size" | "length" => Field::Size,
+                "time" => Field::Time,
+                "body" => Field::Body,
+                _ if name.starts_with("header.") && name.len() > "header.".len() => {
+                    Field::Header(name["header.".len()..].to_owned())
+                }
+                _ => {
+                    return Err(self.error_at(
+                        column,
+                        format!(
+                            "unknown field {}, expected {}",
+                            token.describe(),
+                            expected_field
+                        ),
+                    ))
+                }
+            },
+            _ => {
+                return Err(self.error_at(
+                    column,
+                    format!("expected {}, found {}", expected_field, token.describe()),
+                ))
+            }
+        };
+
+        let (column, operator) = self.next("an operator")?;
+        match field {
+            Field::Body | Field::Header(_) => self.parse_text_test(field, column, operator),
+            _ => self.parse_number_test(field, column, operator),
+        }
+    }
+
+    fn parse_number_test(
+        &mut self,
+        field: Field,
+        column: usize,
+        operator: Token,
+    ) -> Result<Node, Error> {
+        let test = match operator {
+            Token::Eq | Token::Ne | Token::Lt | Token::Le | Token::Gt | Token::Ge => {
+                Test::Compare(operator, self.parse_number(&field)?)
+            }
+            Token::In => {
+                if self.peek() == Some(&Token::LBracket) {
+                    self.position += 1;
+                    let mut values = vec![self.parse_number(&field)?];
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        values.push(self.parse_number(&field)?);
+                    }
+                    self.expect(Token::RBracket)?;
+                    Test::List(values)
+                } else {
+                    let min = self.parse_number(&field)?;
+                    self.expect(Token::Range)?;
+                    let max_column = self.column();
+                    let max = self.parse_number(&field)?;
+                    if max < min {
+                        return Err(self.error_at(
+                            max_column,
+                            "the end of the range is lower than its start".to_owned(),
+                        ));
+                    }
+                    Test::Range(min, max)
+                }
+            }
+            _ => {
+                return Err(self.error_at(
+                    column,
+                    format!(
+                        "operator {} cannot be used with {}, expected ==, !=, <, <=, >, >= or in",
+                        operator.describe(),
+                        field_name(&field)
+                    ),
+                ))
+            }
+        };
+
+        Ok(Node::Test(field, test, false))
+    }
+
+    fn parse_number(&mut self, field: &Field) -> Result<u64, Error> {
+        let expected = match field {
+            Field::Time => "a duration such as 500ms or 2s",
+            _ => "a number",
+        };
+        let (column, token) = self.next(expected)?;
+        match (field, token) {
+            (Field::Time, Token::Duration(duration)) => Ok(duration.as_millis() as u64),
+            (Field::Time, token) => Err(self.error_at(
+                column,
+                format!("expected {}, found {}", expected, token.describe()),
+            )),
+            (_, Token::Number(n)) => Ok(n),
+            (_, token) => Err(self.error_at(
+                column,
+                format!("expected {}, found {}", expected, token.describe()),
+            )),
+        }
+    }
+
+    fn parse_text_test(
+        &mut self,
+        field: Field,
+        column: usize,
+        operator: Token,
+    ) -> Result<Node, Error> {
+        let negated = match operator {
+            Token::Match | Token::Eq => false,
+            Token::NotMatch | Token::Ne => true,
+            _ => {
+                return Err(self.error_at(
+                    column,
+                    format!(
+                        "operator {} cannot be used with {}, expected ~, !~, == or !=",
+                        operator.describe(),
+                        field_name(&field)
+                    ),
+                ))
+            }
+        };
+        let expected = "a regex such as /admin/i or a quoted string";
+        let (value_column, value) = self.next(expected)?;
+        let test = match (&operator, value) {
+            (Token::Match, Token::Regex(regex, flags))
+            | (Token::NotMatch, Token::Regex(regex, flags)) => {
+                let regex = build_regex(&regex, &flags)
+                    .map_err(|e| self.error_at(value_column, format!("invalid regex: {}", e)))?;
+                Test::Regex(regex)
+            }
+            (Token::Match, Token::Str(string)) | (Token::NotMatch, Token::Str(string)) => {
+                Test::Regex(Regex::new(&regex::escape(&string)).unwrap())
+            }
+            (_, Token::Str(string)) => Test::Equals(string),
+            (_, token) => {
+                return Err(self.error_at(
+                    value_column,
+                    format!("expected {}, found {}", expected, token.describe()),
+                ))
+            }
+        };
+
+        Ok(Node::Test(field, test, negated))
+    }
+}
+
+fn build_regex(regex: &str, flags: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(regex)
+        .case_insensitive(flags.contains('i'))
+        .multi_line(flags.contains('m'))
+        .dot_matches_new_line(flags.contains('s'))
+        .ignore_whitespace(flags.contains('x'))
+        .build()
+}
+
+fn field_name(field: &Field) -> String {
+    match field {
+        Field::Status => "status".to_owned(),
+        Field::Size => "size".to_owned(),
+        Field::Time => "time".to_owned(),
+        Field::Body => "body".to_owned(),
+        Field::Header(name) => format!("header.{}", name),
+    }
+}
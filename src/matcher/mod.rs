@@ -0,0 +1,437 @@
+use regex::Regex;
+
+use std::{str::FromStr, time::Duration};
+
+pub mod expr;
+mod spec;
+
+use self::expr::Expression;
+use crate::error::{check_regex, Error};
+
+pub trait Matchable {
+    fn status(&self) -> Option<&str> {
+        None
+    }
+
+    fn length(&self) -> Option<usize> {
+        None
+    }
+
+    fn body(&self) -> Option<&str> {
+        None
+    }
+
+    fn time(&self) -> Option<Duration> {
+        None
+    }
+
+    fn header(&self, _name: &str) -> Option<&str> {
+        None
+    }
+}
+
+pub fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+pub fn collect_headers(headers: &hyper::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+pub trait Matcher<T>: Send + Sync {
+    fn matches(&self, result: &T) -> bool;
+
+    fn needs_body(&self) -> bool {
+        true
+    }
+}
+
+impl<T, F> Matcher<T> for F
+where
+    F: Fn(&T) -> bool + Send + Sync,
+{
+    fn matches(&self, result: &T) -> bool {
+        self(result)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatusMatcher {
+    include: Vec<(u16, u16)>,
+    exclude: Vec<(u16, u16)>,
+}
+
+impl StatusMatcher {
+    pub fn new<S: AsRef<str>>(specs: &[S]) -> Result<Self, Error> {
+        let mut matcher = StatusMatcher::default();
+        for spec in specs.iter().flat_map(|spec| spec.as_ref().split(',')) {
+            let spec = spec.trim();
+            if spec.is_empty() {
+                continue;
+            }
+
+            let (negated, codes) = match spec.strip_prefix('!') {
+                Some(codes) => (true, codes.trim()),
+                None => (false, spec),
+            };
+            let range = parse_status_range(codes).ok_or_else(|| Error::InvalidOption {
+                name: "status codes",
+                reason: format!(
+                    "{} is not a status code, a class like 2xx or a range like 300-399",
+                    spec
+                ),
+            })?;
+
+            if negated {
+                matcher.exclude.push(range);
+            } else {
+                matcher.include.push(range);
+            }
+        }
+
+        Ok(matcher)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches_code(&self, code: u16) -> bool {
+        let contains = |&(min, max): &(u16, u16)| code >= min && code <= max;
+        (self.include.is_empty() || self.include.iter().any(contains))
+            && !self.exclude.iter().any(contains)
+    }
+}
+
+fn parse_status_range(spec: &str) -> Option<(u16, u16)> {
+    let parse_code = |code: &str| match code.trim().parse::<u16>() {
+        Ok(code) if (100..=999).contains(&code) => Some(code),
+        _ => None,
+    };
+
+    let lowercase = spec.to_lowercase();
+    let wildcards = lowercase.chars().rev().take_while(|&c| c == 'x').count();
+    if wildcards > 0 {
+        let digits = &lowercase[..lowercase.len() - wildcards];
+        if lowercase.len() != 3 || digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let scale = 10u16.pow(wildcards as u32);
+        let min = digits.parse::<u16>().ok()? * scale;
+        return parse_code(&min.to_string()).map(|min| (min, min + scale - 1));
+    }
+
+    let mut bounds = spec.splitn(2, '-');
+    let min = parse_code(bounds.next()?)?;
+    match bounds.next() {
+        Some(max) => {
+            let max = parse_code(max)?;
+            if max < min {
+                return None;
+            }
+            Some((min, max))
+        }
+        None => Some((min, min)),
+    }
+}
+
+impl FromStr for StatusMatcher {
+    type Err = Error;
+
+    fn from_str(spec: &str) -> Result<Self, Error> {
+        StatusMatcher::new(&[spec])
+    }
+}
+
+impl<T: Matchable> Matcher<T> for StatusMatcher {
+    fn matches(&self, result: &T) -> bool {
+        match status_code(result) {
+            Some(code) => self.matches_code(code),
+            None => false,
+        }
+    }
+
+    fn needs_body(&self) -> bool {
+        false
+    }
+}
+
+pub fn status_code<T: Matchable>(result: &T) -> Option<u16> {
+    result
+        .status()
+        .and_then(|status| status.split_whitespace().next())
+        .and_then(|code| code.parse::<u16>().ok())
+}
+
+#[derive(Debug, Clone)]
+pub struct SizeMatcher {
+    min: usize,
+    max: usize,
+}
+
+impl SizeMatcher {
+    pub fn new(min: usize, max: usize) -> Self {
+        SizeMatcher { min, max }
+    }
+
+    pub fn exact(size: usize) -> Self {
+        SizeMatcher::new(size, size)
+    }
+}
+
+impl<T: Matchable> Matcher<T> for SizeMatcher {
+    fn matches(&self, result: &T) -> bool {
+        match result.length() {
+            Some(length) => length >= self.min && length <= self.max,
+            None => false,
+        }
+    }
+
+    fn needs_body(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegexMatcher {
+    regex: Regex,
+}
+
+impl RegexMatcher {
+    pub fn new(regex: &str) -> Result<Self, Error> {
+        Ok(RegexMatcher {
+            regex: check_regex(regex)?,
+        })
+    }
+
+    pub fn contains(string: &str) -> Self {
+        RegexMatcher {
+            regex: Regex::new(&regex::escape(string)).unwrap(),
+        }
+    }
+}
+
+impl<T: Matchable> Matcher<T> for RegexMatcher {
+    fn matches(&self, result: &T) -> bool {
+        match result.body() {
+            Some(body) => self.regex.is_match(body),
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeMatcher {
+    min: Duration,
+    max: Duration,
+}
+
+impl TimeMatcher {
+    pub fn new(min: Duration, max: Duration) -> Self {
+        TimeMatcher { min, max }
+    }
+
+    pub fn longer_than(time: Duration) -> Self {
+        TimeMatcher::new(time, Duration::from_secs(u64::MAX))
+    }
+
+    pub fn shorter_than(time: Duration) -> Self {
+        TimeMatcher::new(Duration::from_secs(0), time)
+    }
+}
+
+impl<T: Matchable> Matcher<T> for TimeMatcher {
+    fn matches(&self, result: &T) -> bool {
+        match result.time() {
+            Some(time) => time >= self.min && time <= self.max,
+            None => false,
+        }
+    }
+
+    fn needs_body(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderMatcher {
+    name: String,
+    value: Option<Regex>,
+}
+
+impl HeaderMatcher {
+    pub fn new(name: &str, value: Option<&str>) -> Result<Self, Error> {
+        Ok(HeaderMatcher {
+            name: name.to_owned(),
+            value: match value {
+                Some(value) => Some(check_regex(value)?),
+                None => None,
+            },
+        })
+    }
+}
+
+impl<T: Matchable> Matcher<T> for HeaderMatcher {
+    fn matches(&self, result: &T) -> bool {
+        match (result.header(&self.name), &self.value) {
+            (Some(value), Some(regex)) => regex.is_match(value),
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    fn needs_body(&self) -> bool {
+        false
+    }
+}
+
+pub struct Not<M>(pub M);
+
+impl<T, M: Matcher<T>> Matcher<T> for Not<M> {
+    fn matches(&self, result: &T) -> bool {
+        !self.0.matches(result)
+    }
+
+    fn needs_body(&self) -> bool {
+        self.0.needs_body()
+    }
+}
+
+pub struct AllOf<T> {
+    matchers: Vec<Box<dyn Matcher<T>>>,
+}
+
+impl<T> AllOf<T> {
+    pub fn new(matchers: Vec<Box<dyn Matcher<T>>>) -> Self {
+        AllOf { matchers }
+    }
+}
+
+impl<T> Matcher<T> for AllOf<T> {
+    fn matches(&self, result: &T) -> bool {
+        self.matchers.iter().all(|matcher| matcher.matches(result))
+    }
+
+    fn needs_body(&self) -> bool {
+        self.matchers.iter().any(|matcher| matcher.needs_body())
+    }
+}
+
+pub struct AnyOf<T> {
+    matchers: Vec<Box<dyn Matcher<T>>>,
+}
+
+impl<T> AnyOf<T> {
+    pub fn new(matchers: Vec<Box<dyn Matcher<T>>>) -> Self {
+        AnyOf { matchers }
+    }
+}
+
+impl<T> Matcher<T> for AnyOf<T> {
+    fn matches(&self, result: &T) -> bool {
+        self.matchers.iter().any(|matcher| matcher.matches(result))
+    }
+
+    fn needs_body(&self) -> bool {
+        self.matchers.iter().any(|matcher| matcher.needs_body())
+    }
+}
+
+pub struct ResultFilter<T> {
+    matchers: Vec<Box<dyn Matcher<T>>>,
+    filters: Vec<Box<dyn Matcher<T>>>,
+}
+
+impl<T> ResultFilter<T> {
+    pub fn new() -> Self {
+        ResultFilter {
+            matchers: Vec::new(),
+            filters: Vec::new(),
+        }
+    }
+
+    pub fn matcher<M: Matcher<T> + 'static>(mut self, matcher: M) -> Self {
+        self.matchers.push(Box::new(matcher));
+        self
+    }
+
+    pub fn filter<M: Matcher<T> + 'static>(mut self, filter: M) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    pub fn is_match(&self, result: &T) -> bool {
+        if self.filters.iter().any(|filter| filter.matches(result)) {
+            return false;
+        }
+
+        self.matchers.iter().all(|matcher| matcher.matches(result))
+    }
+
+    pub fn needs_body(&self) -> bool {
+        self.matchers
+            .iter()
+            .chain(self.filters.iter())
+            .any(|matcher| matcher.needs_body())
+    }
+}
+
+impl<T: Matchable + 'static> ResultFilter<T> {
+    pub fn status_codes(mut self, include: &StatusMatcher, ignore: &StatusMatcher) -> Self {
+        if !include.is_empty() {
+            self = self.matcher(include.clone());
+        }
+        if !ignore.is_empty() {
+            self = self.filter(ignore.clone());
+        }
+        self
+    }
+
+    pub fn body_strings(mut self, include: &[String], ignore: &[String]) -> Self {
+        if !include.is_empty() {
+            self = self.matcher(AnyOf::new(
+                include
+                    .iter()
+                    .map(|string| Box::new(RegexMatcher::contains(string)) as Box<dyn Matcher<T>>)
+                    .collect(),
+            ));
+        }
+        for string in ignore {
+            self = self.filter(RegexMatcher::contains(string));
+        }
+        self
+    }
+
+    pub fn expressions(mut self, matches: &[Expression], filters: &[Expression]) -> Self {
+        if !matches.is_empty() {
+            self = self.matcher(AnyOf::new(
+                matches
+                    .iter()
+                    .map(|expression| Box::new(expression.clone()) as Box<dyn Matcher<T>>)
+                    .collect(),
+            ));
+        }
+        for expression in filters {
+            self = self.filter(expression.clone());
+        }
+        self
+    }
+}
+
+impl<T> Default for ResultFilter<T> {
+    fn default() -> Self {
+        ResultFilter::new()
+    }
+}
@@ -0,0 +1,171 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name matcher;
+
+    use std::time::Duration;
+    use crate::matcher::{*, expr::Expression};
+
+    struct Response {
+        status: &'static str,
+        body: &'static str,
+        time: u64,
+        headers: Vec<(String, String)>,
+    }
+
+    impl Matchable for Response {
+        fn status(&self) -> Option<&str> {
+            Some(self.status)
+        }
+
+        fn length(&self) -> Option<usize> {
+            Some(self.body.len())
+        }
+
+        fn body(&self) -> Option<&str> {
+            Some(self.body)
+        }
+
+        fn time(&self) -> Option<Duration> {
+            Some(Duration::from_millis(self.time))
+        }
+
+        fn header(&self, name: &str) -> Option<&str> {
+            find_header(&self.headers, name)
+        }
+    }
+
+    fixture response() -> Response {
+        setup(&mut self) {
+            Response {
+                status: "200 OK",
+                body: "<h1>Admin panel</h1>",
+                time: 1500,
+                headers: vec![("Server".to_owned(), "Microsoft-IIS/10.0".to_owned())],
+            }
+        }
+    }
+
+    test matchers(response) {
+        let response = response.val;
+        assert!("2xx".parse::<StatusMatcher>().unwrap().matches(&response));
+        assert!(!"404".parse::<StatusMatcher>().unwrap().matches(&response));
+        assert!(SizeMatcher::exact(20).matches(&response));
+        assert!(!SizeMatcher::new(0, 19).matches(&response));
+        assert!(RegexMatcher::new("(?i)admin").unwrap().matches(&response));
+        assert!(RegexMatcher::contains("<h1>").matches(&response));
+        assert!(TimeMatcher::longer_than(Duration::from_secs(1)).matches(&response));
+        assert!(!TimeMatcher::shorter_than(Duration::from_secs(1)).matches(&response));
+        assert!(HeaderMatcher::new("server", Some("IIS")).unwrap().matches(&response));
+        assert!(!HeaderMatcher::new("X-Powered-By", None).unwrap().matches(&response));
+        assert!(RegexMatcher::new("(").is_err());
+    }
+
+    test status_codes() {
+        let matcher = StatusMatcher::new(&["2xx", "!204", "300-302,418"]).unwrap();
+
+        assert!(matcher.matches_code(200));
+        assert!(matcher.matches_code(299));
+        assert!(!matcher.matches_code(204));
+        assert!(matcher.matches_code(302));
+        assert!(!matcher.matches_code(303));
+        assert!(matcher.matches_code(418));
+        assert!(!matcher.matches_code(20));
+        assert!(StatusMatcher::new(&["!404"]).unwrap().matches_code(500));
+        assert!(StatusMatcher::new(&["40x"]).unwrap().matches_code(409));
+        assert!(!StatusMatcher::new(&["40x"]).unwrap().matches_code(410));
+        assert!(StatusMatcher::new(&[""]).unwrap().is_empty());
+
+        for spec in &["20", "2yy", "x00", "399-300", "1000", "abc", "!"] {
+            assert!(StatusMatcher::new(&[spec]).is_err(), "{} should be rejected", spec);
+        }
+    }
+
+    test combinators(response) {
+        let response = response.val;
+        let all: AllOf<Response> = AllOf::new(vec![
+            Box::new("200".parse::<StatusMatcher>().unwrap()),
+            Box::new(Not(SizeMatcher::exact(1234))),
+        ]);
+        let any: AnyOf<Response> = AnyOf::new(vec![
+            Box::new("404".parse::<StatusMatcher>().unwrap()),
+            Box::new(|response: &Response| response.body.contains("panel")),
+        ]);
+
+        assert!(all.matches(&response));
+        assert!(any.matches(&response));
+        assert!(!Not(any).matches(&response));
+    }
+
+    test result_filter(response) {
+        let response = response.val;
+        let keep_all: ResultFilter<Response> = ResultFilter::new();
+        let none = StatusMatcher::default();
+        let ignored = ResultFilter::new().status_codes(&none, &"200".parse().unwrap());
+        let included = ResultFilter::new()
+            .status_codes(&"200".parse().unwrap(), &none)
+            .body_strings(&["Login".to_owned(), "Admin".to_owned()], &[]);
+        let all_groups = ResultFilter::new()
+            .status_codes(&"404".parse().unwrap(), &none)
+            .body_strings(&["Admin".to_owned()], &[]);
+        let any_match = ResultFilter::new()
+            .expressions(&[Expression::parse("status == 404").unwrap(), Expression::parse("body ~ /panel/").unwrap()], &[]);
+        let filter_wins = ResultFilter::new()
+            .body_strings(&["Admin".to_owned()], &["panel".to_owned()]);
+
+        assert!(keep_all.is_match(&response));
+        assert!(!ignored.is_match(&response));
+        assert!(included.is_match(&response));
+        assert!(!all_groups.is_match(&response));
+        assert!(any_match.is_match(&response));
+        assert!(!filter_wins.is_match(&response));
+    }
+
+    test needs_body() {
+        let none = StatusMatcher::default();
+        let status: ResultFilter<Response> = ResultFilter::new()
+            .status_codes(&"200".parse().unwrap(), &none)
+            .matcher(Not(SizeMatcher::exact(0)))
+            .expressions(&[Expression::parse("status == 200 && header.server ~ /IIS/").unwrap()], &[]);
+        let strings: ResultFilter<Response> = ResultFilter::new().body_strings(&[], &["Admin".to_owned()]);
+        let expression: ResultFilter<Response> = ResultFilter::new()
+            .expressions(&[], &[Expression::parse("size > 10 || !(body ~ /panel/)").unwrap()]);
+        let closure: ResultFilter<Response> = ResultFilter::new().matcher(|response: &Response| response.time > 0);
+
+        assert!(!status.needs_body());
+        assert!(strings.needs_body());
+        assert!(expression.needs_body());
+        assert!(closure.needs_body());
+    }
+
+    test expression(response) {
+        let response = response.val;
+        let matches = |expression: &str| Expression::parse(expression).unwrap().matches(&response);
+
+        assert!(matches("status in 200..299 && size != 1234 && body ~ /admin/i && time > 1s"));
+        assert!(matches("status == 404 || (header.server ~ \"IIS\" && !(time < 500ms))"));
+        assert!(matches("status in [200, 302] and not body !~ /panel/"));
+        assert!(matches("size <= 20 && header.Server == \"Microsoft-IIS/10.0\""));
+        assert!(!matches("body ~ /admin/"));
+        assert!(!matches("time >= 2s || header.x-powered-by ~ /PHP/"));
+        assert!(!matches("header.x-powered-by !~ /PHP/"));
+    }
+
+    test expression_errors() {
+        let error = |expression: &str| Expression::parse(expression).unwrap_err().to_string();
+
+        assert_eq!(
+            error("status in 200..299 && code == 1"),
+            "invalid expression: unknown field `code`, expected a field such as status, size, time, body or header.<name> at column 23\n\tstatus in 200..299 && code == 1\n\t                      ^"
+        );
+        assert!(error("time > 2").contains("expected a duration such as 500ms or 2s, found `2` at column 8"));
+        assert!(error("body > 3").contains("operator `>` cannot be used with body"));
+        assert!(error("body ~ /admin").contains("unterminated regex at column 8"));
+        assert!(error("body ~ /admin/q").contains("unknown regex flag `q`"));
+        assert!(error("body ~ /(/").contains("invalid regex"));
+        assert!(error("(status == 200").contains("unexpected end of expression, expected `)` at column 15"));
+        assert!(error("status == 200 size == 3").contains("unexpected `size`, expected `&&` or `||` at column 15"));
+        assert!(error("status in 299..200").contains("lower than its start"));
+        assert!(error("time > 999999999999999999m").contains("duration too large at column 8"));
+    }
+}
@@ -0,0 +1,149 @@
+use std::{sync::mpsc::Sender, time::Duration};
+
+use super::{
+    result_processor::SingleProbeScanResult,
+    utils::{build_probe_targets, parse_ports},
+    ProbeConfig,
+};
+use crate::{
+    error::Error,
+    scan::{ScanHandle, ScanStream},
+    session::{auth::Credentials, SessionConfig},
+};
+
+#[derive(Debug, Clone)]
+pub struct ProbeScanBuilder {
+    config: ProbeConfig,
+    hosts: Vec<String>,
+    ports: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeScan {
+    config: ProbeConfig,
+    targets: Vec<(String, u16)>,
+}
+
+impl ProbeScanBuilder {
+    pub fn new() -> Self {
+        ProbeScanBuilder {
+            config: ProbeConfig {
+                n_threads: 10,
+                user_agent: "rustbuster".to_owned(),
+                http_headers: Vec::new(),
+                timeout: Duration::from_millis(5000),
+                max_per_host: 0,
+                auth: None,
+                session: None,
+            },
+            hosts: Vec::new(),
+            ports: vec![80, 443, 8000, 8080, 8443],
+        }
+    }
+
+    pub fn host(mut self, host: &str) -> Self {
+        self.hosts.push(host.to_owned());
+        self
+    }
+
+    pub fn hosts(mut self, hosts: Vec<String>) -> Self {
+        self.hosts.extend(hosts);
+        self
+    }
+
+    pub fn ports(mut self, ports: Vec<u16>) -> Self {
+        self.ports = ports;
+        self
+    }
+
+    pub fn ports_spec(self, spec: &str) -> Result<Self, Error> {
+        let ports = parse_ports(spec).map_err(|reason| Error::InvalidOption {
+            name: "ports",
+            reason,
+        })?;
+        Ok(self.ports(ports))
+    }
+
+    pub fn threads(mut self, n_threads: usize) -> Self {
+        self.config.n_threads = n_threads;
+        self
+    }
+
+    pub fn max_per_host(mut self, max_per_host: usize) -> Self {
+        self.config.max_per_host = max_per_host;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.config.user_agent = user_agent.to_owned();
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.config
+            .http_headers
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn auth(mut self, credentials: Credentials) -> Self {
+        self.config.auth = Some(credentials);
+        self
+    }
+
+    pub fn session(mut self, session: SessionConfig) -> Self {
+        self.config.session = Some(session);
+        self
+    }
+
+    pub fn build(self) -> Result<ProbeScan, Error> {
+        if self.hosts.is_empty() {
+            return Err(Error::MissingOption("host"));
+        }
+        if self.ports.is_empty() {
+            return Err(Error::MissingOption("port"));
+        }
+
+        let targets = build_probe_targets(&self.hosts, &self.ports);
+        if targets.is_empty() {
+            return Err(Error::InvalidOption {
+                name: "hosts",
+                reason: "no valid host specified".to_owned(),
+            });
+        }
+
+        Ok(ProbeScan {
+            config: self.config,
+            targets,
+        })
+    }
+}
+
+impl Default for ProbeScanBuilder {
+    fn default() -> Self {
+        ProbeScanBuilder::new()
+    }
+}
+
+impl ProbeScan {
+    pub fn builder() -> ProbeScanBuilder {
+        ProbeScanBuilder::new()
+    }
+
+    pub fn total(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn stream(self) -> Result<(ScanStream<SingleProbeScanResult>, ScanHandle), Error> {
+        super::stream(self.targets, self.config)
+    }
+
+    pub fn run(self, tx: Sender<SingleProbeScanResult>) -> Result<(), Error> {
+        super::run(tx, self.targets, self.config)
+    }
+}
@@ -0,0 +1,210 @@
+use futures::{
+    future,
+    sync::mpsc::{self, UnboundedSender},
+    Stream,
+};
+use hyper::{client::connect::Connect, header, rt::Future, Chunk, HeaderMap, StatusCode, Uri};
+use tokio::timer::Timeout;
+
+use std::{
+    collections::HashMap,
+    sync::{mpsc::Sender, Arc, Mutex},
+    time::Duration,
+};
+
+pub mod builder;
+pub mod result_processor;
+mod spec;
+pub mod utils;
+
+use crate::{
+    error::Error,
+    scan::{self, ScanHandle, ScanStream},
+    scheduler::FairScheduler,
+    session::{self, auth::Credentials, HttpClient, RequestTemplate, SessionConfig, SessionPool},
+    vhostbuster::{
+        tls::{certificate_key, CertificateInfo, SniConnector},
+        utils::extract_title,
+    },
+};
+use result_processor::SingleProbeScanResult;
+use utils::probe_url;
+
+#[derive(Debug, Clone)]
+pub struct ProbeConfig {
+    pub n_threads: usize,
+    pub user_agent: String,
+    pub http_headers: Vec<(String, String)>,
+    pub timeout: Duration,
+    pub max_per_host: usize,
+    pub auth: Option<Credentials>,
+    pub session: Option<SessionConfig>,
+}
+
+type Response = (StatusCode, HeaderMap, Chunk);
+type Certificates = Arc<Mutex<HashMap<String, CertificateInfo>>>;
+
+fn fetch<C: Connect + Clone + Sync + 'static>(
+    client: &HttpClient<C>,
+    sessions: Option<Arc<SessionPool>>,
+    url: &str,
+    config: &ProbeConfig,
+) -> Box<dyn Future<Item = Response, Error = String> + Send> {
+    let mut headers = config.http_headers.clone();
+    headers.push(("User-Agent".to_owned(), config.user_agent.clone()));
+    let request = match url.parse::<Uri>() {
+        Ok(uri) => RequestTemplate {
+            method: "GET".to_owned(),
+            uri,
+            headers,
+            body: String::new(),
+        },
+        Err(e) => return Box::new(future::err(e.to_string())),
+    };
+
+    let exchange_client = client.clone();
+    let response = session::send(client.clone(), sessions, move |session| {
+        session.exchange(&exchange_client, &request)
+    })
+    .map(|response| (response.status, response.headers, response.body));
+
+    Box::new(Timeout::new(response, config.timeout).map_err(|e| {
+        if e.is_elapsed() {
+            "timed out".to_owned()
+        } else {
+            match e.into_inner() {
+                Some(e) => e.to_string(),
+                None => "timer error".to_owned(),
+            }
+        }
+    }))
+}
+
+fn make_probe_future<C: Connect + Clone + Sync + 'static>(
+    tx: UnboundedSender<SingleProbeScanResult>,
+    client: &HttpClient<C>,
+    sessions: Option<Arc<SessionPool>>,
+    host: String,
+    port: u16,
+    config: &ProbeConfig,
+    certificates: Certificates,
+) -> impl Future<Item = (), Error = Error> {
+    let https = probe_url("https", &host, port);
+    let http = probe_url("http", &host, port);
+    let https_err = https.clone();
+    let http_client = client.clone();
+    let http_config = config.clone();
+    let http_sessions = sessions.clone();
+
+    fetch(client, sessions, &https, config)
+        .map(move |response| (https, response))
+        .or_else(move |e| {
+            debug!("{} - {}", https_err, e);
+            fetch(&http_client, http_sessions, &http, &http_config)
+                .map(move |response| (http, response))
+        })
+        .then(move |response| {
+            let mut result = SingleProbeScanResult {
+                target: host.clone(),
+                port,
+                url: probe_url("http", &host, port),
+                alive: false,
+                status: None,
+                title: None,
+                server: None,
+                length: None,
+                redirect: None,
+                certificate: None,
+                error: None,
+            };
+
+            match response {
+                Ok((url, (status, headers, body))) => {
+                    let body = String::from_utf8_lossy(&body);
+                    let value = |name: header::HeaderName| {
+                        headers
+                            .get(name)
+                            .and_then(|value| value.to_str().ok())
+                            .map(|value| value.to_owned())
+                    };
+                    result.alive = true;
+                    result.status = Some(status.to_string());
+                    result.title = extract_title(&body);
+                    result.server = value(header::SERVER);
+                    result.length = Some(body.len());
+                    result.redirect = value(header::LOCATION);
+                    if url.starts_with("https://") {
+                        result.certificate = url.parse().ok().and_then(|uri| {
+                            certificates
+                                .lock()
+                                .unwrap()
+                                .get(&certificate_key(&uri))
+                                .cloned()
+                        });
+                    }
+                    result.url = url;
+                }
+                Err(e) => {
+                    debug!("{} - {}", result.url, e);
+                    result.error = Some(e);
+                }
+            }
+
+            let _ = tx.unbounded_send(result);
+            Ok(())
+        })
+}
+
+pub fn stream(
+    targets: Vec<(String, u16)>,
+    config: ProbeConfig,
+) -> Result<(ScanStream<SingleProbeScanResult>, ScanHandle), Error> {
+    let (tx, rx) = mpsc::unbounded();
+    let total = targets.len();
+    let mut tls_connector_builder = native_tls::TlsConnector::builder();
+    tls_connector_builder.danger_accept_invalid_certs(true);
+    tls_connector_builder.danger_accept_invalid_hostnames(true);
+    let tls_connector = tls_connector_builder.build()?;
+    let connector = SniConnector::with_system_resolver(config.n_threads, tls_connector);
+    let certificates = connector.certificates.clone();
+    let client = HttpClient::new(connector).auth(config.auth.clone());
+    let sessions = config
+        .session
+        .clone()
+        .map(|session| Arc::new(SessionPool::new(session)));
+
+    let jobs: Vec<(String, (String, u16))> = targets
+        .into_iter()
+        .map(|(host, port)| (host.clone(), (host, port)))
+        .collect();
+    let n_threads = config.n_threads;
+    let max_per_host = config.max_per_host;
+
+    let scan = FairScheduler::new(
+        futures::stream::iter_ok(jobs),
+        move |(host, port)| {
+            make_probe_future(
+                tx.clone(),
+                &client,
+                sessions.clone(),
+                host,
+                port,
+                &config,
+                certificates.clone(),
+            )
+        },
+        n_threads,
+        max_per_host,
+    )
+    .for_each(Ok);
+
+    Ok(ScanStream::new(scan, rx, Some(total)))
+}
+
+pub fn run(
+    tx: Sender<SingleProbeScanResult>,
+    targets: Vec<(String, u16)>,
+    config: ProbeConfig,
+) -> Result<(), Error> {
+    scan::forward(stream(targets, config)?.0, tx)
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matcher::{Matchable, ResultFilter},
+    vhostbuster::tls::CertificateInfo,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SingleProbeScanResult {
+    pub target: String,
+    pub port: u16,
+    pub url: String,
+    pub alive: bool,
+    pub status: Option<String>,
+    pub title: Option<String>,
+    pub server: Option<String>,
+    pub length: Option<usize>,
+    pub redirect: Option<String>,
+    pub certificate: Option<CertificateInfo>,
+    pub error: Option<String>,
+}
+
+impl Matchable for SingleProbeScanResult {
+    fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    fn length(&self) -> Option<usize> {
+        self.length
+    }
+}
+
+#[derive(Default)]
+pub struct ProbeScanResult {
+    pub results: Vec<SingleProbeScanResult>,
+    filter: ResultFilter<SingleProbeScanResult>,
+}
+
+impl ProbeScanResult {
+    pub fn new(filter: ResultFilter<SingleProbeScanResult>) -> Self {
+        ProbeScanResult {
+            results: Vec::<SingleProbeScanResult>::new(),
+            filter,
+        }
+    }
+
+    pub fn maybe_add_result(&mut self, res: SingleProbeScanResult) -> bool {
+        trace!("{:?}", res);
+        if res.alive && self.filter.is_match(&res) {
+            self.results.push(res);
+            return true;
+        }
+
+        false
+    }
+}
@@ -0,0 +1,58 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name probebuster;
+
+    test parse_ports() {
+        let parse_ports = crate::probebuster::utils::parse_ports;
+        assert_eq!(Ok(vec![80, 443, 8000, 8001, 8002]), parse_ports("80, 443,8000-8002,443"));
+        assert!(parse_ports("80,http").is_err());
+        assert!(parse_ports("9000-8000").is_err());
+        assert!(parse_ports("0").is_err());
+        assert!(parse_ports("").is_err());
+    }
+
+    test build_probe_targets() {
+        let hosts = vec![
+            "Example.com".to_owned(),
+            "https://example.com:8443/login".to_owned(),
+            "10.0.0.0/31".to_owned(),
+            "::1".to_owned(),
+        ];
+        assert_eq!(
+            vec![
+                ("example.com".to_owned(), 80),
+                ("example.com".to_owned(), 443),
+                ("10.0.0.0".to_owned(), 80),
+                ("10.0.0.0".to_owned(), 443),
+                ("10.0.0.1".to_owned(), 80),
+                ("10.0.0.1".to_owned(), 443),
+                ("[::1]".to_owned(), 80),
+                ("[::1]".to_owned(), 443),
+            ],
+            crate::probebuster::utils::build_probe_targets(&hosts, &[80, 443])
+        );
+    }
+
+    test probe_url() {
+        let probe_url = crate::probebuster::utils::probe_url;
+        assert_eq!("http://example.com/", probe_url("http", "example.com", 80));
+        assert_eq!("https://example.com/", probe_url("https", "example.com", 443));
+        assert_eq!("https://10.0.0.1:80/", probe_url("https", "10.0.0.1", 80));
+        assert_eq!("http://[::1]:8080/", probe_url("http", "[::1]", 8080));
+    }
+
+    test build_scan() {
+        use crate::{error::Error, probebuster::builder::ProbeScan};
+
+        let scan = ProbeScan::builder()
+            .host("10.0.0.0/31")
+            .ports_spec("80,443")
+            .unwrap()
+            .build();
+        assert_eq!(4, scan.unwrap().total());
+
+        let builder = ProbeScan::builder().host("example.com").ports_spec("80-");
+        assert!(matches!(builder, Err(Error::InvalidOption { name: "ports", .. })));
+    }
+}
@@ -0,0 +1,133 @@
+use std::{collections::HashSet, fs::File, io::Write, net::IpAddr, path::Path};
+
+use super::result_processor::SingleProbeScanResult;
+use crate::dnsbuster::utils::parse_cidr;
+
+pub fn parse_ports(spec: &str) -> Result<Vec<u16>, String> {
+    let mut ports: Vec<u16> = Vec::new();
+    for part in spec
+        .split(',')
+        .map(|part| part.trim())
+        .filter(|p| !p.is_empty())
+    {
+        let (start, end) = match part.find('-') {
+            Some(index) => (&part[..index], &part[index + 1..]),
+            None => (part, part),
+        };
+        let start = start
+            .parse::<u16>()
+            .map_err(|_| format!("invalid port {}", part))?;
+        let end = end
+            .parse::<u16>()
+            .map_err(|_| format!("invalid port {}", part))?;
+        if start == 0 || start > end {
+            return Err(format!("invalid port range {}", part));
+        }
+
+        for port in start..=end {
+            if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+    }
+
+    if ports.is_empty() {
+        return Err("no ports specified".to_owned());
+    }
+
+    Ok(ports)
+}
+
+pub fn build_probe_targets(hosts: &[String], ports: &[u16]) -> Vec<(String, u16)> {
+    debug!("building probe targets");
+    let mut names: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    for host in hosts {
+        let is_range = host
+            .split('/')
+            .next()
+            .and_then(|address| address.parse::<IpAddr>().ok())
+            .is_some();
+        let candidates = if is_range {
+            match parse_cidr(host) {
+                Ok(addresses) => addresses
+                    .into_iter()
+                    .map(|address| match address {
+                        IpAddr::V4(ip) => ip.to_string(),
+                        IpAddr::V6(ip) => format!("[{}]", ip),
+                    })
+                    .collect(),
+                Err(e) => {
+                    warn!("Skipping range {}: {}", host, e);
+                    continue;
+                }
+            }
+        } else {
+            let name = match host.find("://") {
+                Some(i) => &host[i + 3..],
+                None => host,
+            };
+            let name = name.split('/').next().unwrap_or_default();
+            let name = match name.rfind(':') {
+                Some(i) if !name.ends_with(']') => &name[..i],
+                _ => name,
+            };
+            vec![name.trim_end_matches('.').to_lowercase()]
+        };
+
+        for name in candidates {
+            if !name.is_empty() && seen.insert(name.clone()) {
+                names.push(name);
+            }
+        }
+    }
+
+    names
+        .iter()
+        .flat_map(|name| ports.iter().map(move |port| (name.to_owned(), *port)))
+        .collect()
+}
+
+pub fn probe_url(scheme: &str, host: &str, port: u16) -> String {
+    match (scheme, port) {
+        ("http", 80) | ("https", 443) => format!("{}://{}/", scheme, host),
+        _ => format!("{}://{}:{}/", scheme, host, port),
+    }
+}
+
+pub fn save_probe_results(path: &str, results: &Vec<SingleProbeScanResult>) {
+    let json_string = serde_json::to_string(&results).unwrap();
+
+    let mut file = match File::create(Path::new(path)) {
+        Ok(f) => f,
+        Err(e) => {
+            error!("Error while creating file: {}\n{}", path, e);
+            return;
+        }
+    };
+
+    match file.write_all(json_string.as_bytes()) {
+        Ok(_) => debug!("Results saved to: {}", path),
+        Err(e) => error!("Error while writing results to file: {}\n{}", path, e),
+    };
+}
+
+pub fn save_alive_targets(path: &str, results: &[SingleProbeScanResult]) {
+    let mut file = match File::create(Path::new(path)) {
+        Ok(f) => f,
+        Err(e) => {
+            error!("Error while creating file: {}\n{}", path, e);
+            return;
+        }
+    };
+
+    let lines: String = results
+        .iter()
+        .filter(|result| result.alive)
+        .map(|result| format!("{}\n", result.url))
+        .collect();
+    match file.write_all(lines.as_bytes()) {
+        Ok(_) => debug!("Alive targets saved to: {}", path),
+        Err(e) => error!("Error while writing alive targets to file: {}\n{}", path, e),
+    };
+}
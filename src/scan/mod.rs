@@ -0,0 +1,153 @@
+use futures::{
+    task::{self, Task},
+    Async, Future, Poll, Stream,
+};
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    mpsc::Sender,
+    Arc, Mutex,
+};
+
+use crate::error::Error;
+
+mod spec;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: Option<usize>,
+}
+
+struct ScanState {
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+    done: AtomicUsize,
+    total: Option<usize>,
+    task: Mutex<Option<Task>>,
+}
+
+impl ScanState {
+    fn wake(&self) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.notify();
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ScanHandle {
+    state: Arc<ScanState>,
+}
+
+impl ScanHandle {
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.wake();
+    }
+
+    pub fn pause(&self) {
+        self.state.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.state.paused.store(false, Ordering::SeqCst);
+        self.state.wake();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            done: self.state.done.load(Ordering::SeqCst),
+            total: self.state.total,
+        }
+    }
+}
+
+pub struct ScanStream<T> {
+    scan: Option<Box<dyn Future<Item = (), Error = Error> + Send>>,
+    results: Box<dyn Stream<Item = T, Error = ()> + Send>,
+    state: Arc<ScanState>,
+}
+
+impl<T: 'static> ScanStream<T> {
+    pub fn new<F, S>(scan: F, results: S, total: Option<usize>) -> (Self, ScanHandle)
+    where
+        F: Future<Item = (), Error = Error> + Send + 'static,
+        S: Stream<Item = T, Error = ()> + Send + 'static,
+    {
+        let state = Arc::new(ScanState {
+            cancelled: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            done: AtomicUsize::new(0),
+            total,
+            task: Mutex::new(None),
+        });
+        let stream = ScanStream {
+            scan: Some(Box::new(scan)),
+            results: Box::new(results),
+            state: state.clone(),
+        };
+
+        (stream, ScanHandle { state })
+    }
+}
+
+impl<T> Stream for ScanStream<T> {
+    type Item = T;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<T>, Error> {
+        *self.state.task.lock().unwrap() = Some(task::current());
+
+        if self.state.cancelled.load(Ordering::SeqCst) {
+            self.scan = None;
+            return Ok(Async::Ready(None));
+        }
+
+        if self.state.paused.load(Ordering::SeqCst) {
+            return Ok(Async::NotReady);
+        }
+
+        let finished = match self.scan {
+            Some(ref mut scan) => match scan.poll() {
+                Ok(Async::NotReady) => false,
+                Ok(Async::Ready(())) => true,
+                Err(e) => {
+                    self.scan = None;
+                    return Err(e);
+                }
+            },
+            None => false,
+        };
+        if finished {
+            self.scan = None;
+        }
+
+        match self.results.poll() {
+            Ok(Async::Ready(Some(result))) => {
+                self.state.done.fetch_add(1, Ordering::SeqCst);
+                Ok(Async::Ready(Some(result)))
+            }
+            Ok(other) => Ok(other),
+            Err(()) => Ok(Async::Ready(None)),
+        }
+    }
+}
+
+pub fn forward<T: Send + 'static>(stream: ScanStream<T>, tx: Sender<T>) -> Result<(), Error> {
+    let mut runtime = tokio::runtime::Runtime::new().map_err(Error::Runtime)?;
+    runtime.block_on(
+        stream
+            .map(move |result| tx.send(result).is_ok())
+            .take_while(|sent| Ok(*sent))
+            .for_each(|_| Ok(())),
+    )
+}
@@ -0,0 +1,67 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name scan;
+
+    use futures::{future, stream, sync::mpsc, Future, Stream};
+    use std::{thread, time::Duration};
+    use crate::{error::Error, scan::{Progress, ScanStream}};
+
+    test stream_yields_results() {
+        let (tx, rx) = mpsc::unbounded();
+        let scan = stream::iter_ok::<_, Error>(vec![1, 2, 3])
+            .for_each(move |n| tx.unbounded_send(n).map_err(|_| Error::MissingOption("receiver")));
+        let (stream, handle) = ScanStream::new(scan, rx, Some(3));
+
+        assert_eq!(vec![1, 2, 3], stream.collect().wait().unwrap());
+        assert_eq!(Progress { done: 3, total: Some(3) }, handle.progress());
+    }
+
+    test scan_error_ends_stream() {
+        let (_tx, rx) = mpsc::unbounded::<i32>();
+        let scan = future::err(Error::MissingOption("target"));
+        let (stream, _) = ScanStream::new(scan, rx, None);
+
+        assert!(matches!(stream.collect().wait(), Err(Error::MissingOption("target"))));
+    }
+
+    test forward_reports_scan_error() {
+        let (_tx, rx) = mpsc::unbounded::<i32>();
+        let (stream, _) = ScanStream::new(future::err(Error::MissingOption("target")), rx, None);
+        let (tx, _rx) = std::sync::mpsc::channel();
+
+        assert!(matches!(crate::scan::forward(stream, tx), Err(Error::MissingOption("target"))));
+    }
+
+    test cancel_ends_stream() {
+        let (tx, rx) = mpsc::unbounded();
+        tx.unbounded_send(1).unwrap();
+        let (stream, handle) = ScanStream::new(future::empty(), rx, None);
+        let mut stream = stream.wait();
+
+        assert_eq!(Some(1), stream.next().map(Result::unwrap));
+        handle.cancel();
+        assert!(handle.is_cancelled());
+        assert!(stream.next().is_none());
+        assert_eq!(Progress { done: 1, total: None }, handle.progress());
+    }
+
+    test resume_wakes_paused_stream() {
+        let (tx, rx) = mpsc::unbounded();
+        tx.unbounded_send(1).unwrap();
+        let (stream, handle) = ScanStream::new(future::empty(), rx, None);
+        handle.pause();
+        assert!(handle.is_paused());
+
+        let resumer = handle.clone();
+        let resume = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            assert_eq!(0, resumer.progress().done);
+            resumer.resume();
+        });
+
+        assert_eq!(Some(1), stream.wait().next().map(Result::unwrap));
+        resume.join().unwrap();
+        assert!(!handle.is_paused());
+    }
+}
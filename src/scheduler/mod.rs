@@ -0,0 +1,146 @@
+use futures::{stream::FuturesUnordered, Async, Future, Poll, Stream};
+
+use std::collections::{HashMap, VecDeque};
+
+mod spec;
+
+struct Tagged<F> {
+    host: usize,
+    inner: F,
+}
+
+pub struct FairScheduler<S, T, M, F>
+where
+    S: Stream<Item = (String, T), Error = F::Error>,
+    M: FnMut(T) -> F,
+    F: Future,
+{
+    input: S,
+    input_done: bool,
+    make: M,
+    hosts: HashMap<String, usize>,
+    queues: Vec<VecDeque<T>>,
+    in_flight: Vec<usize>,
+    cursor: usize,
+    running: FuturesUnordered<Tagged<F>>,
+    max_in_flight: usize,
+    max_per_host: usize,
+}
+
+impl<F: Future> Future for Tagged<F> {
+    type Item = (usize, Result<F::Item, F::Error>);
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, ()> {
+        match self.inner.poll() {
+            Ok(Async::Ready(item)) => Ok(Async::Ready((self.host, Ok(item)))),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(e) => Ok(Async::Ready((self.host, Err(e)))),
+        }
+    }
+}
+
+impl<S, T, M, F> FairScheduler<S, T, M, F>
+where
+    S: Stream<Item = (String, T), Error = F::Error>,
+    M: FnMut(T) -> F,
+    F: Future,
+{
+    pub fn new(input: S, make: M, max_in_flight: usize, max_per_host: usize) -> Self {
+        FairScheduler {
+            input,
+            input_done: false,
+            make,
+            hosts: HashMap::new(),
+            queues: Vec::new(),
+            in_flight: Vec::new(),
+            cursor: 0,
+            running: FuturesUnordered::new(),
+            max_in_flight: max_in_flight.max(1),
+            max_per_host: if max_per_host == 0 {
+                usize::MAX
+            } else {
+                max_per_host
+            },
+        }
+    }
+
+    fn enqueue(&mut self, host: String, item: T) {
+        let index = match self.hosts.get(&host) {
+            Some(index) => *index,
+            None => {
+                self.queues.push(VecDeque::new());
+                self.in_flight.push(0);
+                self.hosts.insert(host, self.queues.len() - 1);
+                self.queues.len() - 1
+            }
+        };
+
+        self.queues[index].push_back(item);
+    }
+
+    fn next_host(&self) -> Option<usize> {
+        let n = self.queues.len();
+        (0..n)
+            .map(|step| (self.cursor + step) % n)
+            .find(|&i| !self.queues[i].is_empty() && self.in_flight[i] < self.max_per_host)
+    }
+
+    fn fill(&mut self) {
+        while self.running.len() < self.max_in_flight {
+            let host = match self.next_host() {
+                Some(v) => v,
+                None => break,
+            };
+            let item = self.queues[host].pop_front().unwrap();
+
+            self.in_flight[host] += 1;
+            self.cursor = (host + 1) % self.queues.len();
+            self.running.push(Tagged {
+                host,
+                inner: (self.make)(item),
+            });
+        }
+    }
+}
+
+impl<S, T, M, F> Stream for FairScheduler<S, T, M, F>
+where
+    S: Stream<Item = (String, T), Error = F::Error>,
+    M: FnMut(T) -> F,
+    F: Future,
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Option<F::Item>, F::Error> {
+        while !self.input_done {
+            match self.input.poll()? {
+                Async::Ready(Some((host, item))) => self.enqueue(host, item),
+                Async::Ready(None) => self.input_done = true,
+                Async::NotReady => break,
+            }
+        }
+
+        self.fill();
+
+        match self.running.poll() {
+            Ok(Async::Ready(Some((host, result)))) => {
+                self.in_flight[host] -= 1;
+                result.map(|item| Async::Ready(Some(item)))
+            }
+            Ok(Async::Ready(None)) if self.input_done => Ok(Async::Ready(None)),
+            _ => Ok(Async::NotReady),
+        }
+    }
+}
+
+pub fn host_of(url: &str) -> String {
+    match url.parse::<hyper::Uri>() {
+        Ok(uri) => uri
+            .authority_part()
+            .map(|authority| authority.as_str().to_owned())
+            .unwrap_or_default(),
+        Err(_) => url.to_owned(),
+    }
+}
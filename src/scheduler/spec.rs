@@ -0,0 +1,27 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name scheduler;
+
+    use futures::{future, stream, Future, Stream};
+
+    test fair_scheduler_round_robin() {
+        let jobs = vec![("a", 1), ("a", 2), ("a", 3), ("b", 4), ("b", 5), ("c", 6)]
+            .into_iter()
+            .map(|(host, job)| (host.to_owned(), job));
+        let scheduler = crate::scheduler::FairScheduler::new(
+            stream::iter_ok::<_, ()>(jobs),
+            future::ok::<usize, ()>,
+            1,
+            0,
+        );
+
+        assert_eq!(vec![1, 4, 6, 2, 5, 3], scheduler.collect().wait().unwrap());
+    }
+
+    test host_of() {
+        let host_of = crate::scheduler::host_of;
+        assert_eq!("127.0.0.1:8080", host_of("http://127.0.0.1:8080/admin"));
+        assert_eq!("example.com", host_of("https://example.com"));
+    }
+}
@@ -0,0 +1,515 @@
+use data_encoding::{BASE64, HEXLOWER};
+use hmac::{Hmac, Mac};
+use md4::Md4;
+use md5::{Digest, Md5};
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::Error;
+
+const NTLM_SIGNATURE: &[u8] = b"NTLMSSP\0";
+const NTLM_NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const NTLM_NEGOTIATE_FLAGS: u32 = 0xa008_8207;
+const FILETIME_UNIX_OFFSET: u64 = 116_444_736_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthType {
+    Basic,
+    Digest,
+    Ntlm,
+    Bearer,
+}
+
+impl FromStr for AuthType {
+    type Err = Error;
+
+    fn from_str(auth_type: &str) -> Result<Self, Error> {
+        match auth_type {
+            "basic" => Ok(AuthType::Basic),
+            "digest" => Ok(AuthType::Digest),
+            "ntlm" => Ok(AuthType::Ntlm),
+            "bearer" => Ok(AuthType::Bearer),
+            _ => Err(Error::InvalidOption {
+                name: "auth type",
+                reason: format!("{} is not one of basic, digest, ntlm or bearer", auth_type),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Credentials {
+    Basic {
+        username: String,
+        password: String,
+    },
+    Digest {
+        username: String,
+        password: String,
+    },
+    Ntlm {
+        domain: String,
+        username: String,
+        password: String,
+    },
+    Bearer(String),
+}
+
+impl Credentials {
+    pub fn new(auth_type: AuthType, credentials: &str) -> Result<Self, Error> {
+        if auth_type == AuthType::Bearer {
+            return Ok(Credentials::Bearer(credentials.to_owned()));
+        }
+
+        let mut parts = credentials.splitn(2, ':');
+        let (username, password) = match (parts.next(), parts.next()) {
+            (Some(username), Some(password)) if !username.is_empty() => {
+                (username.to_owned(), password.to_owned())
+            }
+            _ => {
+                return Err(Error::InvalidOption {
+                    name: "credentials",
+                    reason: format!("{} is not in the user:password form", credentials),
+                })
+            }
+        };
+
+        Ok(match auth_type {
+            AuthType::Basic => Credentials::Basic { username, password },
+            AuthType::Digest => Credentials::Digest { username, password },
+            _ => {
+                let (domain, username) = match username.find('\\') {
+                    Some(i) => (username[..i].to_owned(), username[i + 1..].to_owned()),
+                    None => (String::new(), username),
+                };
+                Credentials::Ntlm {
+                    domain,
+                    username,
+                    password,
+                }
+            }
+        })
+    }
+
+    pub fn authorization(&self) -> Option<String> {
+        match self {
+            Credentials::Basic { username, password } => Some(format!(
+                "Basic {}",
+                BASE64.encode(format!("{}:{}", username, password).as_bytes())
+            )),
+            Credentials::Bearer(token) => Some(format!("Bearer {}", token)),
+            _ => None,
+        }
+    }
+}
+
+pub fn auth_params(params: &str) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut chars = params.chars().peekable();
+    loop {
+        while let Some(c) = chars.peek() {
+            if c.is_whitespace() || *c == ',' {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let mut name = String::new();
+        while let Some(c) = chars.peek() {
+            if *c == '=' || *c == ',' {
+                break;
+            }
+            name.push(*c);
+            chars.next();
+        }
+        if name.is_empty() {
+            return result;
+        }
+        let mut value = String::new();
+        if chars.peek() == Some(&'=') {
+            chars.next();
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => value.extend(chars.next()),
+                        c => value.push(c),
+                    }
+                }
+            } else {
+                while let Some(c) = chars.peek() {
+                    if *c == ',' {
+                        break;
+                    }
+                    value.push(*c);
+                    chars.next();
+                }
+            }
+        }
+        result.push((name.trim().to_lowercase(), value.trim().to_owned()));
+    }
+}
+
+fn strip_scheme<'a>(challenge: &'a str, scheme: &str) -> Option<&'a str> {
+    let challenge = challenge.trim();
+    match challenge.get(..scheme.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(scheme) => {
+            let rest = &challenge[scheme.len()..];
+            if rest.is_empty() || rest.starts_with(' ') {
+                Some(rest.trim())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn md5_hex(data: &str) -> String {
+    HEXLOWER.encode(&Md5::digest(data.as_bytes()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestChallenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub algorithm: String,
+    pub qop: Option<String>,
+    pub stale: bool,
+}
+
+impl DigestChallenge {
+    pub fn parse(www_authenticate: &str) -> Option<Self> {
+        let params = auth_params(strip_scheme(www_authenticate, "Digest")?);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_owned())
+        };
+
+        Some(DigestChallenge {
+            realm: param("realm").unwrap_or_default(),
+            nonce: param("nonce")?,
+            opaque: param("opaque"),
+            algorithm: param("algorithm").unwrap_or_else(|| "MD5".to_owned()),
+            qop: param("qop").and_then(|qop| {
+                qop.split(',')
+                    .map(|qop| qop.trim())
+                    .find(|qop| *qop == "auth")
+                    .map(|qop| qop.to_owned())
+            }),
+            stale: param("stale").is_some_and(|stale| stale.eq_ignore_ascii_case("true")),
+        })
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.algorithm.eq_ignore_ascii_case("MD5")
+            || self.algorithm.eq_ignore_ascii_case("MD5-sess")
+    }
+
+    pub fn response(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+        nc: u32,
+    ) -> String {
+        let mut ha1 = md5_hex(&format!("{}:{}:{}", username, self.realm, password));
+        if self.algorithm.eq_ignore_ascii_case("MD5-sess") {
+            ha1 = md5_hex(&format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = md5_hex(&format!("{}:{}", method, uri));
+
+        match &self.qop {
+            Some(qop) => md5_hex(&format!(
+                "{}:{}:{:08x}:{}:{}:{}",
+                ha1, self.nonce, nc, cnonce, qop, ha2
+            )),
+            None => md5_hex(&format!("{}:{}:{}", ha1, self.nonce, ha2)),
+        }
+    }
+
+    pub fn authorization(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+        nc: u32,
+    ) -> String {
+        let response = self.response(username, password, method, uri, cnonce, nc);
+        let mut authorization = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+            username, self.realm, self.nonce, uri, self.algorithm, response
+        );
+        if let Some(qop) = &self.qop {
+            authorization.push_str(&format!(
+                ", qop={}, nc={:08x}, cnonce=\"{}\"",
+                qop, nc, cnonce
+            ));
+        }
+        if let Some(opaque) = &self.opaque {
+            authorization.push_str(&format!(", opaque=\"{}\"", opaque));
+        }
+        authorization
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NtlmChallenge {
+    pub flags: u32,
+    pub challenge: [u8; 8],
+    pub target_info: Vec<u8>,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_field(data: &[u8], offset: usize) -> Option<&[u8]> {
+    let length = read_u16(data, offset)? as usize;
+    let start = read_u32(data, offset + 4)? as usize;
+    data.get(start..start + length)
+}
+
+impl NtlmChallenge {
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        if message.get(..8)? != NTLM_SIGNATURE || read_u32(message, 8)? != 2 {
+            return None;
+        }
+        let mut challenge = [0; 8];
+        challenge.copy_from_slice(message.get(24..32)?);
+
+        Some(NtlmChallenge {
+            flags: read_u32(message, 20)?,
+            challenge,
+            target_info: if message.len() >= 48 {
+                read_field(message, 40)?.to_vec()
+            } else {
+                Vec::new()
+            },
+        })
+    }
+
+    pub fn from_header(www_authenticate: &str) -> Option<Self> {
+        let token = strip_scheme(www_authenticate, "NTLM")?;
+        NtlmChallenge::parse(&BASE64.decode(token.as_bytes()).ok()?)
+    }
+
+    fn timestamp(&self) -> Option<u64> {
+        let mut offset = 0;
+        while let (Some(id), Some(length)) = (
+            read_u16(&self.target_info, offset),
+            read_u16(&self.target_info, offset + 2),
+        ) {
+            let value = self
+                .target_info
+                .get(offset + 4..offset + 4 + length as usize)?;
+            match id {
+                0 => return None,
+                7 if length == 8 => {
+                    let mut timestamp = [0; 8];
+                    timestamp.copy_from_slice(value);
+                    return Some(u64::from_le_bytes(timestamp));
+                }
+                _ => offset += 4 + length as usize,
+            }
+        }
+        None
+    }
+}
+
+fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .flat_map(|c| c.to_le_bytes().to_vec())
+        .collect()
+}
+
+fn hmac_md5(key: &[u8], data: &[&[u8]]) -> Vec<u8> {
+    let mut mac = Hmac::<Md5>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for data in data {
+        mac.update(data);
+    }
+    mac.finalize().into_bytes().to_vec()
+}
+
+pub fn nt_hash(password: &str) -> Vec<u8> {
+    Md4::digest(utf16le(password)).to_vec()
+}
+
+pub fn ntlmv2_hash(nt_hash: &[u8], username: &str, domain: &str) -> Vec<u8> {
+    hmac_md5(
+        nt_hash,
+        &[&utf16le(&format!("{}{}", username.to_uppercase(), domain))],
+    )
+}
+
+pub fn ntlmv2_response(
+    ntlmv2_hash: &[u8],
+    server_challenge: &[u8],
+    client_challenge: &[u8],
+    timestamp: u64,
+    target_info: &[u8],
+) -> (Vec<u8>, Vec<u8>) {
+    let mut blob = vec![1, 1, 0, 0, 0, 0, 0, 0];
+    blob.extend_from_slice(&timestamp.to_le_bytes());
+    blob.extend_from_slice(client_challenge);
+    blob.extend_from_slice(&[0; 4]);
+    blob.extend_from_slice(target_info);
+    blob.extend_from_slice(&[0; 4]);
+
+    let mut nt_response = hmac_md5(ntlmv2_hash, &[server_challenge, &blob]);
+    nt_response.extend(blob);
+    let mut lm_response = hmac_md5(ntlmv2_hash, &[server_challenge, client_challenge]);
+    lm_response.extend_from_slice(client_challenge);
+
+    (lm_response, nt_response)
+}
+
+pub fn ntlm_negotiate() -> Vec<u8> {
+    let mut message = NTLM_SIGNATURE.to_vec();
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&NTLM_NEGOTIATE_FLAGS.to_le_bytes());
+    message.extend_from_slice(&[0, 0, 0, 0, 32, 0, 0, 0]);
+    message.extend_from_slice(&[0, 0, 0, 0, 32, 0, 0, 0]);
+    message
+}
+
+pub fn ntlm_authenticate(
+    domain: &str,
+    username: &str,
+    password: &str,
+    challenge: &NtlmChallenge,
+    client_challenge: &[u8],
+    timestamp: u64,
+) -> Vec<u8> {
+    let hash = ntlmv2_hash(&nt_hash(password), username, domain);
+    let (lm_response, nt_response) = ntlmv2_response(
+        &hash,
+        &challenge.challenge,
+        client_challenge,
+        timestamp,
+        &challenge.target_info,
+    );
+    let encode = |text: &str| match challenge.flags & NTLM_NEGOTIATE_UNICODE {
+        0 => text.as_bytes().to_vec(),
+        _ => utf16le(text),
+    };
+    let fields = [
+        lm_response,
+        nt_response,
+        encode(domain),
+        encode(username),
+        Vec::new(),
+        Vec::new(),
+    ];
+
+    let mut message = NTLM_SIGNATURE.to_vec();
+    message.extend_from_slice(&3u32.to_le_bytes());
+    let mut offset = 64;
+    for field in &fields {
+        message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += field.len();
+    }
+    message.extend_from_slice(&(challenge.flags & NTLM_NEGOTIATE_FLAGS).to_le_bytes());
+    for field in &fields {
+        message.extend_from_slice(field);
+    }
+    message
+}
+
+pub struct Authenticator {
+    credentials: Credentials,
+    digest: Mutex<HashMap<String, (DigestChallenge, u32)>>,
+}
+
+impl Authenticator {
+    pub fn new(credentials: Credentials) -> Self {
+        Authenticator {
+            credentials,
+            digest: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    pub fn store_digest(&self, key: &str, challenge: DigestChallenge) {
+        self.digest
+            .lock()
+            .unwrap()
+            .insert(key.to_owned(), (challenge, 0));
+    }
+
+    pub fn digest(&self, key: &str, method: &str, uri: &str) -> Option<(String, String)> {
+        let (username, password) = match &self.credentials {
+            Credentials::Digest { username, password } => (username, password),
+            _ => return None,
+        };
+        let mut challenges = self.digest.lock().unwrap();
+        let (challenge, nc) = challenges.get_mut(key)?;
+        *nc += 1;
+        let cnonce = HEXLOWER.encode(&rand::thread_rng().gen::<[u8; 8]>());
+
+        Some((
+            challenge.nonce.clone(),
+            challenge.authorization(username, password, method, uri, &cnonce, *nc),
+        ))
+    }
+
+    pub fn ntlm_negotiate(&self) -> String {
+        format!("NTLM {}", BASE64.encode(&ntlm_negotiate()))
+    }
+
+    pub fn ntlm_authenticate(&self, challenge: &NtlmChallenge) -> Option<String> {
+        let (domain, username, password) = match &self.credentials {
+            Credentials::Ntlm {
+                domain,
+                username,
+                password,
+            } => (domain, username, password),
+            _ => return None,
+        };
+        let timestamp = challenge.timestamp().unwrap_or_else(|| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            FILETIME_UNIX_OFFSET + now.as_secs() * 10_000_000 + u64::from(now.subsec_nanos() / 100)
+        });
+        let client_challenge = rand::thread_rng().gen::<[u8; 8]>();
+
+        Some(format!(
+            "NTLM {}",
+            BASE64.encode(&ntlm_authenticate(
+                domain,
+                username,
+                password,
+                challenge,
+                &client_challenge,
+                timestamp,
+            ))
+        ))
+    }
+}
@@ -0,0 +1,378 @@
+use futures::{future, try_ready, Async, Future, Stream};
+use hyper::{
+    client::{
+        conn::{self, SendRequest},
+        connect::{Connect, Destination},
+        HttpConnector,
+    },
+    header, Body, Chunk, Client, Request, StatusCode, Uri,
+};
+use hyper_tls::HttpsConnector;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use super::{
+    auth::{Authenticator, Credentials, DigestChallenge, NtlmChallenge},
+    cookie::CookieJar,
+    RequestTemplate, Response,
+};
+use crate::error::Error;
+
+type Connections = Arc<Mutex<HashMap<String, Vec<SendRequest<Body>>>>>;
+
+#[derive(Clone)]
+pub struct HttpClient<C = HttpsConnector<HttpConnector>> {
+    client: Client<C>,
+    connector: C,
+    auth: Option<Arc<Authenticator>>,
+    connections: Connections,
+    read_body: bool,
+}
+
+impl HttpClient {
+    pub fn https(n_threads: usize, ignore_certificate: bool) -> Result<Self, Error> {
+        let mut tls_connector_builder = native_tls::TlsConnector::builder();
+        tls_connector_builder.danger_accept_invalid_certs(ignore_certificate);
+        let tls_connector = tls_connector_builder.build()?;
+        let mut http_connector = HttpConnector::new(n_threads);
+        http_connector.enforce_http(false);
+
+        Ok(HttpClient::new(HttpsConnector::from((
+            http_connector,
+            tls_connector,
+        ))))
+    }
+}
+
+fn origin(uri: &Uri) -> String {
+    format!(
+        "{}://{}",
+        uri.scheme_str().unwrap_or("http"),
+        uri.authority_part().map(|a| a.as_str()).unwrap_or_default()
+    )
+}
+
+fn request_target(uri: &Uri) -> String {
+    uri.path_and_query()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| "/".to_owned())
+}
+
+fn challenges(response: &Response) -> impl Iterator<Item = &str> {
+    response
+        .headers
+        .get_all(header::WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+}
+
+fn with_authorization(template: &RequestTemplate, authorization: String) -> RequestTemplate {
+    let mut template = template.clone();
+    template
+        .headers
+        .push((header::AUTHORIZATION.to_string(), authorization));
+    template
+}
+
+fn read_response(
+    response: hyper::Response<Body>,
+    uri: Uri,
+    jar: Option<Arc<CookieJar>>,
+    start: Instant,
+    read_body: bool,
+) -> impl Future<Item = Response, Error = Error> {
+    let (parts, body) = response.into_parts();
+    if let Some(jar) = jar {
+        jar.store(&uri, &parts.headers);
+    }
+
+    let body = if read_body {
+        future::Either::A(body.concat2().map(|body| {
+            let length = body.len();
+            (body, length)
+        }))
+    } else {
+        future::Either::B(
+            body.fold(0, |length, chunk| {
+                Ok::<_, hyper::Error>(length + chunk.len())
+            })
+            .map(|length| (Chunk::default(), length)),
+        )
+    };
+
+    body.map_err(Error::from)
+        .map(move |(body, length)| Response {
+            status: parts.status,
+            headers: parts.headers,
+            body,
+            length,
+            time: start.elapsed(),
+        })
+}
+
+fn send_on(
+    sender: SendRequest<Body>,
+    template: &RequestTemplate,
+    jar: Option<Arc<CookieJar>>,
+    start: Instant,
+    read_body: bool,
+) -> impl Future<Item = (SendRequest<Body>, Response), Error = Error> {
+    let uri = template.uri.clone();
+    let mut request: Request<Body> = template.build(jar.as_deref());
+    *request.uri_mut() = request_target(&uri).parse().expect("origin-form URI");
+    if !request.headers().contains_key(header::HOST) {
+        if let Some(Ok(host)) = uri
+            .authority_part()
+            .map(|authority| header::HeaderValue::from_str(authority.as_str()))
+        {
+            request.headers_mut().insert(header::HOST, host);
+        }
+    }
+
+    let mut sender = Some(sender);
+    future::poll_fn(move || {
+        try_ready!(sender.as_mut().expect("polled after ready").poll_ready());
+        Ok::<_, hyper::Error>(Async::Ready(sender.take().expect("polled after ready")))
+    })
+    .map_err(Error::from)
+    .and_then(move |mut sender| {
+        sender
+            .send_request(request)
+            .map_err(Error::from)
+            .and_then(move |response| read_response(response, uri, jar, start, read_body))
+            .map(move |response| (sender, response))
+    })
+}
+
+impl<C> HttpClient<C>
+where
+    C: Connect + Clone + Sync + 'static,
+{
+    pub fn new(connector: C) -> Self {
+        HttpClient {
+            client: Client::builder().build(connector.clone()),
+            connector,
+            auth: None,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            read_body: true,
+        }
+    }
+
+    pub fn read_body(mut self, read_body: bool) -> Self {
+        self.read_body = read_body;
+        self
+    }
+
+    pub fn auth(mut self, credentials: Option<Credentials>) -> Self {
+        self.auth = credentials.map(|credentials| Arc::new(Authenticator::new(credentials)));
+        self.connections = Arc::new(Mutex::new(HashMap::new()));
+        self
+    }
+
+    pub fn send(
+        &self,
+        template: &RequestTemplate,
+        jar: Option<&Arc<CookieJar>>,
+    ) -> Box<dyn Future<Item = Response, Error = Error> + Send> {
+        let start = Instant::now();
+        let jar = jar.cloned();
+        let auth = match &self.auth {
+            Some(auth)
+                if !template
+                    .headers
+                    .iter()
+                    .any(|(name, _)| name.eq_ignore_ascii_case("authorization")) =>
+            {
+                auth.clone()
+            }
+            _ => return Box::new(self.fetch(template.clone(), jar, start)),
+        };
+
+        match auth.credentials() {
+            Credentials::Digest { .. } => self.digest(auth, template.clone(), jar, start),
+            Credentials::Ntlm { .. } => self.ntlm(auth, template.clone(), jar, start),
+            credentials => {
+                let authorization = credentials.authorization().unwrap_or_default();
+                Box::new(self.fetch(with_authorization(template, authorization), jar, start))
+            }
+        }
+    }
+
+    fn fetch(
+        &self,
+        template: RequestTemplate,
+        jar: Option<Arc<CookieJar>>,
+        start: Instant,
+    ) -> impl Future<Item = Response, Error = Error> {
+        let read_body = self.read_body;
+        self.client
+            .request(template.build(jar.as_deref()))
+            .map_err(Error::from)
+            .and_then(move |response| read_response(response, template.uri, jar, start, read_body))
+    }
+
+    fn digest(
+        &self,
+        auth: Arc<Authenticator>,
+        template: RequestTemplate,
+        jar: Option<Arc<CookieJar>>,
+        start: Instant,
+    ) -> Box<dyn Future<Item = Response, Error = Error> + Send> {
+        let key = origin(&template.uri);
+        let target = request_target(&template.uri);
+        let (nonce, first) = match auth.digest(&key, &template.method, &target) {
+            Some((nonce, authorization)) => (
+                Some(nonce),
+                self.fetch(
+                    with_authorization(&template, authorization),
+                    jar.clone(),
+                    start,
+                ),
+            ),
+            None => (None, self.fetch(template.clone(), jar.clone(), start)),
+        };
+        let client = self.clone();
+
+        Box::new(first.and_then(move |response| {
+            if response.status != StatusCode::UNAUTHORIZED {
+                return future::Either::A(future::ok(response));
+            }
+            let challenge = challenges(&response).find_map(DigestChallenge::parse);
+            let challenge = match challenge {
+                Some(challenge) if challenge.is_supported() => challenge,
+                Some(challenge) => {
+                    warn!("unsupported digest algorithm {}", challenge.algorithm);
+                    return future::Either::A(future::ok(response));
+                }
+                None => return future::Either::A(future::ok(response)),
+            };
+            if nonce.as_ref() == Some(&challenge.nonce) && !challenge.stale {
+                return future::Either::A(future::ok(response));
+            }
+
+            debug!("digest challenge from {}: {}", key, challenge.nonce);
+            auth.store_digest(&key, challenge);
+            match auth.digest(&key, &template.method, &target) {
+                Some((_, authorization)) => future::Either::B(client.fetch(
+                    with_authorization(&template, authorization),
+                    jar,
+                    start,
+                )),
+                None => future::Either::A(future::ok(response)),
+            }
+        }))
+    }
+
+    fn ntlm(
+        &self,
+        auth: Arc<Authenticator>,
+        template: RequestTemplate,
+        jar: Option<Arc<CookieJar>>,
+        start: Instant,
+    ) -> Box<dyn Future<Item = Response, Error = Error> + Send> {
+        let key = origin(&template.uri);
+        let idle = self
+            .connections
+            .lock()
+            .unwrap()
+            .get_mut(&key)
+            .and_then(|connections| connections.pop());
+        let sender = match idle {
+            Some(sender) => sender,
+            None => return self.ntlm_handshake(auth, template, jar, start),
+        };
+        let client = self.clone();
+
+        Box::new(
+            send_on(sender, &template, jar.clone(), start, self.read_body).then(move |result| {
+                match result {
+                    Ok((sender, response)) => {
+                        if response.status != StatusCode::UNAUTHORIZED {
+                            client.release(&key, sender);
+                            return future::Either::A(future::ok(response));
+                        }
+                        debug!("ntlm connection to {} no longer authenticated", key);
+                        future::Either::B(client.ntlm_handshake(auth, template, jar, start))
+                    }
+                    Err(e) => {
+                        debug!("ntlm connection to {} closed: {}", key, e);
+                        future::Either::B(client.ntlm_handshake(auth, template, jar, start))
+                    }
+                }
+            }),
+        )
+    }
+
+    fn ntlm_handshake(
+        &self,
+        auth: Arc<Authenticator>,
+        template: RequestTemplate,
+        jar: Option<Arc<CookieJar>>,
+        start: Instant,
+    ) -> Box<dyn Future<Item = Response, Error = Error> + Send> {
+        let destination = match Destination::try_from_uri(template.uri.clone()) {
+            Ok(v) => v,
+            Err(e) => return Box::new(future::err(Error::from(e))),
+        };
+        let url = template.uri.to_string();
+        let negotiate = with_authorization(&template, auth.ntlm_negotiate());
+        let negotiate_jar = jar.clone();
+        let read_body = self.read_body;
+        let client = self.clone();
+
+        Box::new(
+            self.connector
+                .connect(destination)
+                .map_err(move |e| Error::Connect {
+                    url,
+                    reason: e.into().to_string(),
+                })
+                .and_then(|(io, _)| conn::handshake(io).map_err(Error::from))
+                .and_then(move |(sender, connection)| {
+                    hyper::rt::spawn(connection.map_err(|e| debug!("ntlm connection: {}", e)));
+                    send_on(sender, &negotiate, negotiate_jar, start, read_body)
+                })
+                .and_then(move |(sender, response)| {
+                    let authenticate = challenges(&response)
+                        .find_map(NtlmChallenge::from_header)
+                        .and_then(|challenge| auth.ntlm_authenticate(&challenge));
+                    let authenticate = match authenticate {
+                        Some(v) => v,
+                        None => {
+                            debug!("no ntlm challenge from {}", template.uri);
+                            return future::Either::A(future::ok(response));
+                        }
+                    };
+                    let key = origin(&template.uri);
+
+                    future::Either::B(
+                        send_on(
+                            sender,
+                            &with_authorization(&template, authenticate),
+                            jar,
+                            start,
+                            read_body,
+                        )
+                        .map(move |(sender, response)| {
+                            if response.status != StatusCode::UNAUTHORIZED {
+                                client.release(&key, sender);
+                            }
+                            response
+                        }),
+                    )
+                }),
+        )
+    }
+
+    fn release(&self, key: &str, sender: SendRequest<Body>) {
+        self.connections
+            .lock()
+            .unwrap()
+            .entry(key.to_owned())
+            .or_default()
+            .push(sender);
+    }
+}
@@ -0,0 +1,232 @@
+use hyper::{header, HeaderMap, Uri};
+use std::{
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::{read_file, Error};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    pub host_only: bool,
+    pub secure: bool,
+    pub expires: Option<SystemTime>,
+}
+
+impl Cookie {
+    pub fn new(name: &str, value: &str) -> Self {
+        Cookie {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            domain: String::new(),
+            path: "/".to_owned(),
+            host_only: false,
+            secure: false,
+            expires: None,
+        }
+    }
+
+    pub fn parse(set_cookie: &str, uri: &Uri) -> Option<Self> {
+        let host = uri.host()?.trim_end_matches('.').to_lowercase();
+        let mut attributes = set_cookie.split(';');
+        let mut pair = attributes.next()?.splitn(2, '=');
+        let name = pair.next()?.trim();
+        let value = pair.next()?.trim().trim_matches('"');
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie {
+            domain: host.clone(),
+            path: default_path(uri),
+            host_only: true,
+            ..Cookie::new(name, value)
+        };
+        let mut max_age = None;
+        for attribute in attributes {
+            let mut attribute = attribute.splitn(2, '=');
+            let key = attribute.next().unwrap_or_default().trim().to_lowercase();
+            let value = attribute.next().unwrap_or_default().trim();
+            match key.as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_lowercase();
+                    if !domain_matches(&host, &domain) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_owned(),
+                "secure" => cookie.secure = true,
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "expires" => {
+                    cookie.expires = chrono::DateTime::parse_from_rfc2822(value)
+                        .ok()
+                        .map(|date| {
+                            UNIX_EPOCH + Duration::from_secs(date.timestamp().max(0) as u64)
+                        })
+                }
+                _ => (),
+            }
+        }
+        if let Some(max_age) = max_age {
+            cookie.expires = Some(SystemTime::now() + Duration::from_secs(max_age.max(0) as u64));
+        }
+
+        Some(cookie)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        match self.expires {
+            Some(expires) => expires <= SystemTime::now(),
+            None => false,
+        }
+    }
+
+    pub fn matches(&self, uri: &Uri) -> bool {
+        let host = uri
+            .host()
+            .unwrap_or_default()
+            .trim_end_matches('.')
+            .to_lowercase();
+        let domain = self.domain.is_empty()
+            || if self.host_only {
+                host == self.domain
+            } else {
+                domain_matches(&host, &self.domain)
+            };
+        let path = uri.path();
+        let path = path.starts_with(&self.path)
+            && (path.len() == self.path.len()
+                || self.path.ends_with('/')
+                || path[self.path.len()..].starts_with('/'));
+
+        domain && path && (!self.secure || uri.scheme_str() == Some("https"))
+    }
+}
+
+fn default_path(uri: &Uri) -> String {
+    let path = uri.path();
+    match path.rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(i) => path[..i].to_owned(),
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: Mutex<Vec<Cookie>>,
+}
+
+impl CookieJar {
+    pub fn new(cookies: Vec<Cookie>) -> Self {
+        CookieJar {
+            cookies: Mutex::new(cookies),
+        }
+    }
+
+    pub fn insert(&self, cookie: Cookie) {
+        let mut cookies = self.cookies.lock().unwrap();
+        cookies.retain(|c| {
+            c.name != cookie.name || c.domain != cookie.domain || c.path != cookie.path
+        });
+        if !cookie.is_expired() {
+            cookies.push(cookie);
+        }
+    }
+
+    pub fn store(&self, uri: &Uri, headers: &HeaderMap) {
+        for set_cookie in headers.get_all(header::SET_COOKIE) {
+            let set_cookie = String::from_utf8_lossy(set_cookie.as_bytes());
+            match Cookie::parse(&set_cookie, uri) {
+                Some(cookie) => {
+                    trace!("storing cookie {}={}", cookie.name, cookie.value);
+                    self.insert(cookie);
+                }
+                None => debug!("ignoring cookie {} from {}", set_cookie, uri),
+            }
+        }
+    }
+
+    pub fn header(&self, uri: &Uri) -> Option<String> {
+        let mut cookies: Vec<Cookie> = self
+            .cookies
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|cookie| !cookie.is_expired() && cookie.matches(uri))
+            .cloned()
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+
+        Some(
+            cookies
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<String>>()
+                .join("; "),
+        )
+    }
+
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.cookies.lock().unwrap().clone()
+    }
+}
+
+pub fn parse_cookies(contents: &str) -> Vec<Cookie> {
+    let mut cookies = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim_end_matches('\r');
+        let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || (line.starts_with('#') && !http_only) {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() == 7 {
+            let expires = fields[4].parse::<u64>().unwrap_or_default();
+            cookies.push(Cookie {
+                name: fields[5].to_owned(),
+                value: fields[6].to_owned(),
+                domain: fields[0].trim_start_matches('.').to_lowercase(),
+                path: fields[2].to_owned(),
+                host_only: fields[1] != "TRUE",
+                secure: fields[3] == "TRUE",
+                expires: match expires {
+                    0 => None,
+                    _ => Some(UNIX_EPOCH + Duration::from_secs(expires)),
+                },
+            });
+            continue;
+        }
+
+        for pair in line.split(';') {
+            let mut pair = pair.splitn(2, '=');
+            if let (Some(name), Some(value)) = (pair.next(), pair.next()) {
+                if !name.trim().is_empty() {
+                    cookies.push(Cookie::new(name.trim(), value.trim()));
+                }
+            }
+        }
+    }
+
+    cookies
+}
+
+pub fn read_cookies(path: &str) -> Result<Vec<Cookie>, Error> {
+    Ok(parse_cookies(&read_file(path)?))
+}
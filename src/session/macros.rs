@@ -0,0 +1,302 @@
+use futures::{future, stream, Future, Stream};
+use hyper::{header, Uri};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use super::{cookie::Cookie, HttpClient, RequestTemplate, Response, Session};
+use crate::{
+    error::{check_regex, read_file, Error},
+    fuzzbuster::utils::split_http_headers,
+};
+
+pub type Variables = HashMap<String, String>;
+
+#[derive(Debug, Clone)]
+pub enum Extractor {
+    Regex(Regex),
+    Json(String),
+    Header(String),
+    Cookie(String),
+}
+
+impl Extractor {
+    pub fn regex(regex: &str) -> Result<Self, Error> {
+        Ok(Extractor::Regex(check_regex(regex)?))
+    }
+
+    pub fn extract(&self, response: &Response, session: &Session, uri: &Uri) -> Option<String> {
+        match self {
+            Extractor::Regex(regex) => {
+                let body = String::from_utf8_lossy(&response.body);
+                let captures = regex.captures(&body)?;
+                captures
+                    .get(1)
+                    .or_else(|| captures.get(0))
+                    .map(|capture| capture.as_str().to_owned())
+            }
+            Extractor::Json(path) => {
+                let document: Value = serde_json::from_slice(&response.body).ok()?;
+                match json_path(&document, path)? {
+                    Value::String(value) => Some(value.to_owned()),
+                    Value::Null => None,
+                    value => Some(value.to_string()),
+                }
+            }
+            Extractor::Header(name) => response
+                .headers
+                .get(name.as_str())
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned()),
+            Extractor::Cookie(name) => response
+                .headers
+                .get_all(header::SET_COOKIE)
+                .iter()
+                .filter_map(|value| Cookie::parse(&String::from_utf8_lossy(value.as_bytes()), uri))
+                .chain(session.jar().map(|jar| jar.cookies()).unwrap_or_default())
+                .find(|cookie| &cookie.name == name)
+                .map(|cookie| cookie.value),
+        }
+    }
+}
+
+pub fn json_path<'a>(document: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim_start_matches('$').trim_start_matches('.');
+    let mut value = document;
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let mut parts = segment.split('[');
+        let key = parts.next().unwrap_or_default();
+        if !key.is_empty() {
+            value = value.get(key)?;
+        }
+        for index in parts {
+            value = value.get(index.trim_end_matches(']').parse::<usize>().ok()?)?;
+        }
+    }
+
+    Some(value)
+}
+
+pub fn substitute(text: &str, variables: &Variables) -> String {
+    let mut text = text.to_owned();
+    for (name, value) in variables {
+        text = text.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    text
+}
+
+pub fn url_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+pub fn substitute_uri(uri: &Uri, variables: &Variables) -> Uri {
+    let decoded = decode_placeholders(&uri.to_string());
+    let encoded: Variables = variables
+        .iter()
+        .map(|(name, value)| (name.to_owned(), url_encode(value)))
+        .collect();
+    let substituted = substitute(&decoded, &encoded);
+    if substituted == decoded {
+        return uri.clone();
+    }
+
+    match substituted.parse::<Uri>() {
+        Ok(uri) => uri,
+        Err(e) => {
+            warn!(
+                "invalid URL after substituting the variables {}: {}",
+                substituted, e
+            );
+            uri.clone()
+        }
+    }
+}
+
+pub fn encode_placeholders(url: &str) -> String {
+    url.replace("{{", "%7B%7B").replace("}}", "%7D%7D")
+}
+
+pub fn decode_placeholders(url: &str) -> String {
+    url.replace("%7B%7B", "{{").replace("%7D%7D", "}}")
+}
+
+#[derive(Debug, Clone)]
+pub struct MacroStep {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub extract: Vec<(String, Extractor)>,
+}
+
+impl MacroStep {
+    pub fn new(method: &str, url: &str) -> Self {
+        MacroStep {
+            method: method.to_owned(),
+            url: url.to_owned(),
+            headers: Vec::new(),
+            body: String::new(),
+            extract: Vec::new(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = body.to_owned();
+        self
+    }
+
+    pub fn extract(mut self, name: &str, extractor: Extractor) -> Self {
+        self.extract.push((name.to_owned(), extractor));
+        self
+    }
+
+    fn request(&self, variables: &Variables, user_agent: &str) -> Result<RequestTemplate, Error> {
+        let url = substitute(&self.url, variables);
+        let uri = url.parse::<Uri>().map_err(|e| Error::InvalidUrl {
+            url: url.clone(),
+            reason: e.to_string(),
+        })?;
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (substitute(name, variables), substitute(value, variables)))
+            .collect();
+        if !headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+        {
+            headers.push(("User-Agent".to_owned(), user_agent.to_owned()));
+        }
+
+        Ok(RequestTemplate {
+            method: self.method.clone(),
+            uri,
+            headers,
+            body: substitute(&self.body, variables),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ExtractorFile {
+    Regex(String),
+    Json(String),
+    Header(String),
+    Cookie(String),
+}
+
+#[derive(Deserialize)]
+struct MacroStepFile {
+    url: String,
+    method: Option<String>,
+    #[serde(default)]
+    headers: Vec<String>,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    extract: BTreeMap<String, ExtractorFile>,
+}
+
+#[derive(Deserialize)]
+struct MacroFile {
+    steps: Vec<MacroStepFile>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Macro {
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    pub fn new(steps: Vec<MacroStep>) -> Self {
+        Macro { steps }
+    }
+
+    pub fn parse(contents: &str, path: &str) -> Result<Self, Error> {
+        let invalid = |reason: String| Error::InvalidOption {
+            name: "macro",
+            reason,
+        };
+        let file: MacroFile = if path.ends_with(".yaml") || path.ends_with(".yml") {
+            serde_yaml::from_str(contents).map_err(|e| invalid(e.to_string()))?
+        } else {
+            toml::from_str(contents).map_err(|e| invalid(e.to_string()))?
+        };
+
+        let mut steps = Vec::new();
+        for step in file.steps {
+            let mut macro_step =
+                MacroStep::new(step.method.as_deref().unwrap_or("GET"), &step.url).body(&step.body);
+            for header in &step.headers {
+                let (name, value) = split_http_headers(header);
+                macro_step = macro_step.header(&name, &value);
+            }
+            for (name, extractor) in step.extract {
+                let extractor = match extractor {
+                    ExtractorFile::Regex(regex) => Extractor::regex(&regex)?,
+                    ExtractorFile::Json(path) => Extractor::Json(path),
+                    ExtractorFile::Header(name) => Extractor::Header(name),
+                    ExtractorFile::Cookie(name) => Extractor::Cookie(name),
+                };
+                macro_step = macro_step.extract(&name, extractor);
+            }
+            steps.push(macro_step);
+        }
+
+        Ok(Macro { steps })
+    }
+
+    pub fn load(path: &str) -> Result<Self, Error> {
+        Macro::parse(&read_file(path)?, path)
+    }
+
+    pub fn run(
+        &self,
+        session: &Session,
+        client: &HttpClient,
+        user_agent: &str,
+    ) -> impl Future<Item = Variables, Error = Error> {
+        let session = session.clone();
+        let client = client.clone();
+        let user_agent = user_agent.to_owned();
+
+        stream::iter_ok(self.steps.clone()).fold(Variables::new(), move |variables, step| {
+            let request = match step.request(&variables, &user_agent) {
+                Ok(v) => v,
+                Err(e) => return future::Either::A(future::err(e)),
+            };
+            let session = session.clone();
+            let uri = request.uri.clone();
+
+            future::Either::B(session.exchange(&client, &request).map(move |response| {
+                let mut variables = variables;
+                for (name, extractor) in &step.extract {
+                    match extractor.extract(&response, &session, &uri) {
+                        Some(value) => {
+                            trace!("{} = {}", name, value);
+                            variables.insert(name.to_owned(), value);
+                        }
+                        None => warn!("no value for {} in the response of {}", name, uri),
+                    }
+                }
+                variables
+            }))
+        })
+    }
+}
@@ -0,0 +1,345 @@
+use futures::{future, future::Shared, Future};
+use hyper::{client::connect::Connect, Body, Chunk, HeaderMap, Request, StatusCode, Uri};
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+pub mod auth;
+pub mod client;
+pub mod cookie;
+pub mod macros;
+mod spec;
+
+pub use client::HttpClient;
+
+use crate::{
+    error::Error,
+    matcher::{collect_headers, expr::Expression, find_header, Matchable, Matcher},
+};
+use cookie::{Cookie, CookieJar};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JarScope {
+    Global,
+    Worker,
+}
+
+impl FromStr for JarScope {
+    type Err = Error;
+
+    fn from_str(scope: &str) -> Result<Self, Error> {
+        match scope {
+            "global" => Ok(JarScope::Global),
+            "worker" => Ok(JarScope::Worker),
+            _ => Err(Error::InvalidOption {
+                name: "cookie jar scope",
+                reason: format!("{} is neither global nor worker", scope),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestTemplate {
+    pub method: String,
+    pub uri: Uri,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RequestTemplate {
+    pub fn build(&self, jar: Option<&CookieJar>) -> Request<Body> {
+        let mut request_builder = Request::builder();
+        let cookies = jar.and_then(|jar| jar.header(&self.uri));
+        let mut cookies_placed = false;
+
+        for (header, value) in &self.headers {
+            match &cookies {
+                Some(cookies) if header.eq_ignore_ascii_case("cookie") && !cookies_placed => {
+                    cookies_placed = true;
+                    request_builder
+                        .header(header.as_str(), format!("{}; {}", value, cookies).as_str())
+                }
+                _ => request_builder.header(header.as_str(), value.as_str()),
+            };
+        }
+        if let (Some(cookies), false) = (&cookies, cookies_placed) {
+            request_builder.header("Cookie", cookies.as_str());
+        }
+
+        request_builder
+            .method(&self.method[..])
+            .uri(&self.uri)
+            .body(Body::from(self.body.clone()))
+            .expect("Request builder")
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Chunk,
+    pub length: usize,
+    pub time: Duration,
+}
+
+struct Snapshot {
+    status: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    length: usize,
+}
+
+impl Matchable for Snapshot {
+    fn status(&self) -> Option<&str> {
+        Some(&self.status)
+    }
+
+    fn length(&self) -> Option<usize> {
+        Some(self.length)
+    }
+
+    fn body(&self) -> Option<&str> {
+        Some(&self.body)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+impl Response {
+    pub fn matches(&self, expression: &Expression) -> bool {
+        expression.matches(&Snapshot {
+            status: self.status.to_string(),
+            headers: collect_headers(&self.headers),
+            body: String::from_utf8_lossy(&self.body).into_owned(),
+            length: self.length,
+        })
+    }
+}
+
+type SharedLogin = Shared<Box<dyn Future<Item = (), Error = Error> + Send>>;
+
+#[derive(Default)]
+struct LoginState {
+    generation: usize,
+    pending: Option<SharedLogin>,
+}
+
+impl fmt::Debug for LoginState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoginState")
+            .field("generation", &self.generation)
+            .field("pending", &self.pending.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    jar: Option<Arc<CookieJar>>,
+    login: Arc<Mutex<LoginState>>,
+    token: Arc<Mutex<Option<(String, usize)>>>,
+}
+
+impl Session {
+    pub fn new(cookies: Vec<Cookie>) -> Self {
+        Session {
+            jar: Some(Arc::new(CookieJar::new(cookies))),
+            ..Session::default()
+        }
+    }
+
+    pub fn jar(&self) -> Option<&Arc<CookieJar>> {
+        self.jar.as_ref()
+    }
+
+    pub fn logins(&self) -> usize {
+        self.login.lock().unwrap().generation
+    }
+
+    pub fn reuse_token(&self, max_uses: usize) -> Option<String> {
+        match &mut *self.token.lock().unwrap() {
+            Some((token, uses)) if max_uses == 0 || *uses < max_uses => {
+                *uses += 1;
+                Some(token.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn store_token(&self, token: &str) {
+        *self.token.lock().unwrap() = Some((token.to_owned(), 1));
+    }
+
+    pub fn invalidate_token(&self) {
+        *self.token.lock().unwrap() = None;
+    }
+
+    pub fn exchange<C: Connect + Clone + Sync + 'static>(
+        &self,
+        client: &HttpClient<C>,
+        template: &RequestTemplate,
+    ) -> impl Future<Item = Response, Error = Error> {
+        client.send(template, self.jar.as_ref())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub scope: JarScope,
+    pub cookies: Vec<Cookie>,
+    pub login: Option<RequestTemplate>,
+    pub expired: Option<Expression>,
+}
+
+impl SessionConfig {
+    pub fn new(scope: JarScope) -> Self {
+        SessionConfig {
+            scope,
+            cookies: Vec::new(),
+            login: None,
+            expired: None,
+        }
+    }
+}
+
+pub struct SessionPool {
+    config: SessionConfig,
+    global: Session,
+    idle: Mutex<Vec<Session>>,
+}
+
+impl SessionPool {
+    pub fn new(config: SessionConfig) -> Self {
+        SessionPool {
+            global: Session::new(config.cookies.clone()),
+            config,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn acquire(&self) -> Session {
+        match self.config.scope {
+            JarScope::Global => self.global.clone(),
+            JarScope::Worker => self
+                .idle
+                .lock()
+                .unwrap()
+                .pop()
+                .unwrap_or_else(|| Session::new(self.config.cookies.clone())),
+        }
+    }
+
+    pub fn release(&self, session: Session) {
+        if self.config.scope == JarScope::Worker {
+            self.idle.lock().unwrap().push(session);
+        }
+    }
+
+    pub fn is_expired(&self, response: &Response) -> bool {
+        match &self.config.expired {
+            Some(expired) => response.matches(expired),
+            None => false,
+        }
+    }
+
+    pub fn login<C: Connect + Clone + Sync + 'static>(
+        &self,
+        client: &HttpClient<C>,
+        session: &Session,
+        seen: usize,
+    ) -> Box<dyn Future<Item = (), Error = Error> + Send> {
+        let login = match &self.config.login {
+            Some(login) => login,
+            None => return Box::new(future::ok(())),
+        };
+        let mut state = session.login.lock().unwrap();
+        if state.generation > seen {
+            return Box::new(future::ok(()));
+        }
+
+        let uri = login.uri.to_string();
+        let shared = match &state.pending {
+            Some(pending) => pending.clone(),
+            None => {
+                let login_state = session.login.clone();
+                let login_uri = uri.clone();
+                let login: Box<dyn Future<Item = (), Error = Error> + Send> =
+                    Box::new(session.exchange(client, login).then(move |result| {
+                        let mut state = login_state.lock().unwrap();
+                        state.pending = None;
+                        let response = result?;
+                        debug!("logged in via {}: {}", login_uri, response.status);
+                        state.generation += 1;
+                        Ok(())
+                    }));
+                let shared = login.shared();
+                state.pending = Some(shared.clone());
+                shared
+            }
+        };
+
+        Box::new(shared.map(|_| ()).map_err(move |e| Error::Login {
+            url: uri,
+            reason: e.to_string(),
+        }))
+    }
+}
+
+pub fn send<C, F, R>(
+    client: HttpClient<C>,
+    sessions: Option<Arc<SessionPool>>,
+    attempt: F,
+) -> Box<dyn Future<Item = Response, Error = Error> + Send>
+where
+    C: Connect + Clone + Sync + 'static,
+    F: Fn(&Session) -> R + Send + Sync + 'static,
+    R: Future<Item = Response, Error = Error> + Send + 'static,
+{
+    let pool = match sessions {
+        Some(pool) => pool,
+        None => return Box::new(attempt(&Session::default())),
+    };
+    let session = pool.acquire();
+    let attempt = Arc::new(attempt);
+    let retry = (pool.clone(), session.clone(), attempt.clone());
+    let release = (pool.clone(), session.clone());
+
+    Box::new(
+        pool.login(&client, &session, 0)
+            .and_then(move |_| {
+                let seen = session.logins();
+                attempt(&session).map(move |response| (seen, response))
+            })
+            .and_then(move |(seen, response)| {
+                let (pool, session, attempt) = retry;
+                if pool.config.login.is_none() || !pool.is_expired(&response) {
+                    return future::Either::A(future::ok(response));
+                }
+
+                debug!("session expired, logging in again");
+                session.invalidate_token();
+                future::Either::B(
+                    pool.login(&client, &session, seen)
+                        .and_then(move |_| attempt(&session))
+                        .map(move |response| {
+                            if pool.is_expired(&response) {
+                                warn!("session still expired after logging in again");
+                            }
+                            response
+                        }),
+                )
+            })
+            .then(move |result| {
+                let (pool, session) = release;
+                pool.release(session);
+                result
+            }),
+    )
+}
@@ -0,0 +1,238 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name session;
+
+    use hyper::{header, HeaderMap, Uri};
+    use crate::session::{auth::*, cookie::*, macros::*, *};
+    use data_encoding::HEXLOWER;
+
+    fn uri(uri: &str) -> Uri {
+        uri.parse().unwrap()
+    }
+
+    test parse_set_cookie() {
+        let cookie = Cookie::parse("SID=abc; Path=/app; Domain=.Example.com; Secure; HttpOnly", &uri("https://www.example.com/app/login")).unwrap();
+        assert_eq!("SID", cookie.name);
+        assert_eq!("abc", cookie.value);
+        assert_eq!("example.com", cookie.domain);
+        assert_eq!("/app", cookie.path);
+        assert!(!cookie.host_only);
+        assert!(cookie.secure);
+        assert!(cookie.matches(&uri("https://api.example.com/app/users")));
+        assert!(!cookie.matches(&uri("http://www.example.com/app")));
+        assert!(!cookie.matches(&uri("https://www.example.com/application")));
+
+        let cookie = Cookie::parse("token=1", &uri("http://example.com/a/b")).unwrap();
+        assert_eq!("/a", cookie.path);
+        assert!(cookie.host_only);
+        assert!(!cookie.matches(&uri("http://www.example.com/a")));
+
+        assert!(Cookie::parse("SID=abc; Domain=evil.com", &uri("http://example.com/")).is_none());
+        assert!(Cookie::parse("SID=abc; Max-Age=0", &uri("http://example.com/")).unwrap().is_expired());
+        assert!(Cookie::parse("SID=abc; Expires=Wed, 21 Oct 2015 07:28:00 GMT", &uri("http://example.com/")).unwrap().is_expired());
+    }
+
+    test cookie_jar() {
+        let jar = CookieJar::new(vec![Cookie::new("seed", "1")]);
+        let mut headers = HeaderMap::new();
+        headers.append(header::SET_COOKIE, "SID=abc; Path=/".parse().unwrap());
+        headers.append(header::SET_COOKIE, "pref=dark; Path=/admin".parse().unwrap());
+        jar.store(&uri("http://127.0.0.1/admin/login"), &headers);
+
+        assert_eq!(Some("pref=dark; seed=1; SID=abc".to_owned()), jar.header(&uri("http://127.0.0.1/admin/")));
+        assert_eq!(Some("seed=1; SID=abc".to_owned()), jar.header(&uri("http://127.0.0.1/")));
+        assert_eq!(Some("seed=1".to_owned()), jar.header(&uri("http://10.0.0.1/")));
+
+        let mut headers = HeaderMap::new();
+        headers.append(header::SET_COOKIE, "SID=; Max-Age=0; Path=/".parse().unwrap());
+        jar.store(&uri("http://127.0.0.1/logout"), &headers);
+        assert_eq!(Some("seed=1".to_owned()), jar.header(&uri("http://127.0.0.1/")));
+    }
+
+    test parse_cookie_file() {
+        let cookies = parse_cookies("# Netscape HTTP Cookie File\n\
+            .example.com\tTRUE\t/\tFALSE\t0\tSID\tabc\n\
+            #HttpOnly_example.com\tFALSE\t/admin\tTRUE\t4102444800\tadmin\t1\n\
+            \n\
+            lang=en; theme=dark\n");
+
+        assert_eq!(4, cookies.len());
+        assert_eq!("example.com", cookies[0].domain);
+        assert!(!cookies[0].host_only);
+        assert!(cookies[1].host_only && cookies[1].secure && cookies[1].expires.is_some());
+        assert_eq!("/admin", cookies[1].path);
+        assert_eq!(Cookie::new("theme", "dark"), cookies[3]);
+    }
+
+    test request_template_cookies() {
+        let template = RequestTemplate {
+            method: "POST".to_owned(),
+            uri: uri("http://127.0.0.1/login"),
+            headers: vec![("Cookie".to_owned(), "a=1".to_owned())],
+            body: "user=admin".to_owned(),
+        };
+        let jar = CookieJar::new(vec![Cookie::new("SID", "abc")]);
+
+        assert_eq!("a=1", template.build(None).headers()["Cookie"]);
+        assert_eq!("a=1; SID=abc", template.build(Some(&jar)).headers()["Cookie"]);
+        let template = RequestTemplate { headers: Vec::new(), ..template };
+        assert_eq!("SID=abc", template.build(Some(&jar)).headers()["Cookie"]);
+    }
+
+    test session_pool_scopes() {
+        let mut config = SessionConfig::new(JarScope::Worker);
+        config.cookies.push(Cookie::new("seed", "1"));
+        let pool = SessionPool::new(config.clone());
+        let first = pool.acquire();
+        let second = pool.acquire();
+        first.jar().unwrap().insert(Cookie::new("SID", "abc"));
+        assert_eq!(Some("seed=1".to_owned()), second.jar().unwrap().header(&uri("http://127.0.0.1/")));
+        pool.release(first);
+        assert_eq!(Some("seed=1; SID=abc".to_owned()), pool.acquire().jar().unwrap().header(&uri("http://127.0.0.1/")));
+
+        config.scope = JarScope::Global;
+        let pool = SessionPool::new(config);
+        pool.acquire().jar().unwrap().insert(Cookie::new("SID", "abc"));
+        assert_eq!(Some("seed=1; SID=abc".to_owned()), pool.acquire().jar().unwrap().header(&uri("http://127.0.0.1/")));
+        assert!("process".parse::<JarScope>().is_err());
+    }
+
+    test session_tokens() {
+        let pool = SessionPool::new(SessionConfig::new(JarScope::Worker));
+        let first = pool.acquire();
+        let second = pool.acquire();
+        assert_eq!(None, first.reuse_token(3));
+        first.store_token("abc");
+        assert_eq!(Some("abc".to_owned()), first.reuse_token(3));
+        assert_eq!(Some("abc".to_owned()), first.reuse_token(3));
+        assert_eq!(None, first.reuse_token(3));
+        assert_eq!(None, second.reuse_token(3));
+
+        first.store_token("def");
+        pool.release(first);
+        let first = pool.acquire();
+        assert_eq!(Some("def".to_owned()), first.reuse_token(3));
+        first.invalidate_token();
+        assert_eq!(None, first.reuse_token(3));
+
+        first.store_token("ghi");
+        for _ in 0..10 {
+            assert_eq!(Some("ghi".to_owned()), first.reuse_token(0));
+        }
+        first.invalidate_token();
+        assert_eq!(None, first.reuse_token(0));
+    }
+
+    test macro_variables() {
+        let document: serde_json::Value = serde_json::from_str(r#"{"data":{"items":[{"token":"abc","id":7}]}}"#).unwrap();
+        assert_eq!(Some(&serde_json::json!("abc")), json_path(&document, "$.data.items[0].token"));
+        assert_eq!(Some(&serde_json::json!(7)), json_path(&document, "data.items[0].id"));
+        assert_eq!(None, json_path(&document, "data.items[1].id"));
+
+        let mut variables = Variables::new();
+        variables.insert("token".to_owned(), "abc".to_owned());
+        assert_eq!("csrf=abc&user={{user}}", substitute("csrf={{token}}&user={{user}}", &variables));
+
+        let uri = encode_placeholders("http://127.0.0.1/submit/{{token}}?user={{user}}").parse::<Uri>().unwrap();
+        assert_eq!("http://127.0.0.1/submit/abc?user={{user}}", decode_placeholders(&substitute_uri(&uri, &variables).to_string()));
+
+        variables.insert("user".to_owned(), "jo hn&admin=1".to_owned());
+        assert_eq!("http://127.0.0.1/submit/abc?user=jo%20hn%26admin%3D1", substitute_uri(&uri, &variables).to_string());
+    }
+
+    test macro_invalid_url() {
+        use futures::Future;
+
+        let client = crate::session::HttpClient::https(1, false).unwrap();
+        let session_macro = Macro::new(vec![MacroStep::new("GET", "http://127.0.0.1/{{missing}}")]);
+
+        let result = session_macro.run(&Session::default(), &client, "rustbuster").wait();
+        assert!(matches!(result, Err(crate::error::Error::InvalidUrl { .. })));
+    }
+
+    test macro_file() {
+        let toml = r#"
+            [[steps]]
+            method = "POST"
+            url = "http://127.0.0.1/login"
+            body = "user=admin"
+            headers = ["Content-Type: application/x-www-form-urlencoded"]
+            extract = { session = { cookie = "SID" } }
+
+            [[steps]]
+            url = "http://127.0.0.1/form"
+            [steps.extract]
+            token = { regex = 'name="token" value="(\w+)"' }
+            nonce = { header = "X-Nonce" }
+        "#;
+        let pre_request = Macro::parse(toml, "macro.toml").unwrap();
+        assert_eq!(2, pre_request.steps.len());
+        assert_eq!("POST", pre_request.steps[0].method);
+        assert_eq!(vec![("Content-Type".to_owned(), "application/x-www-form-urlencoded".to_owned())], pre_request.steps[0].headers);
+        assert_eq!("GET", pre_request.steps[1].method);
+        assert_eq!(vec!["nonce", "token"], pre_request.steps[1].extract.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>());
+
+        let yaml = "steps:\n  - url: http://127.0.0.1/api\n    extract:\n      id: {json: \"data.id\"}\n";
+        assert_eq!(1, Macro::parse(yaml, "macro.yml").unwrap().steps.len());
+        assert!(Macro::parse("[[steps]]\nurl = \"http://127.0.0.1/\"\nextract = { a = { regex = \"(\" } }", "macro.toml").is_err());
+        assert!(Macro::parse("[[steps]]\nmethod = \"GET\"", "macro.toml").is_err());
+    }
+
+    test auth_credentials() {
+        assert_eq!(Credentials::Basic { username: "admin".to_owned(), password: "p:ss".to_owned() }, Credentials::new(AuthType::Basic, "admin:p:ss").unwrap());
+        assert_eq!(Some("Basic YWRtaW46cDpzcw==".to_owned()), Credentials::new(AuthType::Basic, "admin:p:ss").unwrap().authorization());
+        assert_eq!(Some("Bearer eyJ0.x".to_owned()), Credentials::new(AuthType::Bearer, "eyJ0.x").unwrap().authorization());
+        assert_eq!(Credentials::Ntlm { domain: "CORP".to_owned(), username: "alice".to_owned(), password: "secret".to_owned() }, Credentials::new(AuthType::Ntlm, "CORP\\alice:secret").unwrap());
+        assert!(Credentials::new(AuthType::Digest, "admin").is_err());
+        assert!(Credentials::new(AuthType::Basic, ":secret").is_err());
+        assert!("kerberos".parse::<AuthType>().is_err());
+    }
+
+    test digest_response() {
+        let challenge = DigestChallenge::parse(r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#).unwrap();
+        assert_eq!("testrealm@host.com", challenge.realm);
+        assert_eq!(Some("auth".to_owned()), challenge.qop);
+        assert!(challenge.is_supported());
+        assert!(!challenge.stale);
+        assert_eq!("6629fae49393a05397450978507c4ef1", challenge.response("Mufasa", "Circle Of Life", "GET", "/dir/index.html", "0a4f113b", 1));
+        assert!(challenge.authorization("Mufasa", "Circle Of Life", "GET", "/dir/index.html", "0a4f113b", 1).contains("nc=00000001, cnonce=\"0a4f113b\", opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""));
+
+        assert!(DigestChallenge::parse("Basic realm=\"x\"").is_none());
+        assert!(!DigestChallenge::parse("Digest nonce=\"n\", algorithm=SHA-256, stale=TRUE").unwrap().is_supported());
+        assert!(DigestChallenge::parse("Digest nonce=\"n\", stale=TRUE").unwrap().stale);
+    }
+
+    test ntlmv2_vectors() {
+        let target_info = HEXLOWER.decode(b"02000c0044006f006d00610069006e0001000c0053006500720076006500720000000000").unwrap();
+        let hash = ntlmv2_hash(&nt_hash("Password"), "User", "Domain");
+        assert_eq!("a4f49c406510bdcab6824ee7c30fd852", HEXLOWER.encode(&nt_hash("Password")));
+        assert_eq!("0c868a403bfd7a93a3001ef22ef02e3f", HEXLOWER.encode(&hash));
+
+        let server_challenge = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+        let (lm, nt) = ntlmv2_response(&hash, &server_challenge, &[0xaa; 8], 0, &target_info);
+        assert_eq!("86c35097ac9cec102554764a57cccc19aaaaaaaaaaaaaaaa", HEXLOWER.encode(&lm));
+        assert_eq!("68cd0ab851e51c96aabc927bebef6a1c", HEXLOWER.encode(&nt[..16]));
+    }
+
+    test ntlm_messages() {
+        let mut message = b"NTLMSSP\0".to_vec();
+        message.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0, 48, 0, 0, 0]);
+        message.extend_from_slice(&0xa208_8205u32.to_le_bytes());
+        message.extend_from_slice(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&[4, 0, 4, 0, 48, 0, 0, 0]);
+        message.extend_from_slice(&[0, 0, 0, 0]);
+        let challenge = NtlmChallenge::parse(&message).unwrap();
+        assert_eq!([0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef], challenge.challenge);
+        assert_eq!(vec![0, 0, 0, 0], challenge.target_info);
+        assert!(NtlmChallenge::parse(&ntlm_negotiate()).is_none());
+
+        let authenticate = ntlm_authenticate("Domain", "User", "Password", &challenge, &[0xaa; 8], 0);
+        assert_eq!(b"NTLMSSP\0", &authenticate[..8]);
+        assert_eq!(3, authenticate[8]);
+        let offset = u32::from_le_bytes([authenticate[40], authenticate[41], authenticate[42], authenticate[43]]) as usize;
+        let user = &authenticate[offset..offset + authenticate[36] as usize];
+        assert_eq!("User".encode_utf16().flat_map(|c| c.to_le_bytes().to_vec()).collect::<Vec<u8>>(), user);
+    }
+}
@@ -0,0 +1,158 @@
+use std::sync::{mpsc::Sender, Arc};
+
+use super::{result_processor::SingleTildeScanResult, TildeBuster};
+use crate::{
+    error::{check_url, Error},
+    scan::{self, ScanHandle, ScanStream},
+    session::{auth::Credentials, HttpClient, SessionConfig, SessionPool},
+};
+
+#[derive(Debug, Clone)]
+pub struct TildeScanBuilder {
+    tildebuster: TildeBuster,
+    targets: Vec<String>,
+    max_per_host: usize,
+    auth: Option<Credentials>,
+    session: Option<SessionConfig>,
+}
+
+pub struct TildeScan {
+    tildebuster: TildeBuster,
+    client: HttpClient,
+    sessions: Option<Arc<SessionPool>>,
+    targets: Vec<String>,
+    max_per_host: usize,
+}
+
+impl TildeScanBuilder {
+    pub fn new() -> Self {
+        TildeScanBuilder {
+            tildebuster: TildeBuster {
+                n_threads: 10,
+                ignore_certificate: false,
+                http_method: "GET".to_owned(),
+                http_body: "".to_owned(),
+                user_agent: "rustbuster".to_owned(),
+                http_headers: Vec::new(),
+                extension: None,
+            },
+            targets: Vec::new(),
+            max_per_host: 0,
+            auth: None,
+            session: None,
+        }
+    }
+
+    pub fn target(mut self, url: &str) -> Self {
+        self.targets.push(url.to_owned());
+        self
+    }
+
+    pub fn targets(mut self, urls: Vec<String>) -> Self {
+        self.targets.extend(urls);
+        self
+    }
+
+    pub fn threads(mut self, n_threads: usize) -> Self {
+        self.tildebuster.n_threads = n_threads;
+        self
+    }
+
+    pub fn max_per_host(mut self, max_per_host: usize) -> Self {
+        self.max_per_host = max_per_host;
+        self
+    }
+
+    pub fn ignore_certificate(mut self, ignore_certificate: bool) -> Self {
+        self.tildebuster.ignore_certificate = ignore_certificate;
+        self
+    }
+
+    pub fn method(mut self, http_method: &str) -> Self {
+        self.tildebuster.http_method = http_method.to_owned();
+        self
+    }
+
+    pub fn body(mut self, http_body: &str) -> Self {
+        self.tildebuster.http_body = http_body.to_owned();
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.tildebuster.user_agent = user_agent.to_owned();
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.tildebuster
+            .http_headers
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn extension(mut self, extension: &str) -> Self {
+        self.tildebuster.extension = Some(extension.to_owned());
+        self
+    }
+
+    pub fn auth(mut self, credentials: Credentials) -> Self {
+        self.auth = Some(credentials);
+        self
+    }
+
+    pub fn session(mut self, session: SessionConfig) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    pub fn build(self) -> Result<TildeScan, Error> {
+        if self.targets.is_empty() {
+            return Err(Error::MissingOption("target"));
+        }
+        for target in &self.targets {
+            check_url(target)?;
+        }
+
+        let client = HttpClient::https(
+            self.tildebuster.n_threads,
+            self.tildebuster.ignore_certificate,
+        )?
+        .auth(self.auth);
+
+        Ok(TildeScan {
+            tildebuster: self.tildebuster,
+            client,
+            sessions: self
+                .session
+                .map(|session| Arc::new(SessionPool::new(session))),
+            targets: self.targets,
+            max_per_host: self.max_per_host,
+        })
+    }
+}
+
+impl Default for TildeScanBuilder {
+    fn default() -> Self {
+        TildeScanBuilder::new()
+    }
+}
+
+impl TildeScan {
+    pub fn builder() -> TildeScanBuilder {
+        TildeScanBuilder::new()
+    }
+
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    pub fn stream(self) -> Result<(ScanStream<SingleTildeScanResult>, ScanHandle), Error> {
+        Ok(self
+            .tildebuster
+            .stream(self.client, self.sessions, self.targets, self.max_per_host))
+    }
+
+    pub fn run(self, tx: Sender<SingleTildeScanResult>) -> Result<(), Error> {
+        scan::forward(self.stream()?.0, tx)
+    }
+}
@@ -1,24 +1,21 @@
 use futures::Stream;
-use hyper::{
-    client::HttpConnector,
-    rt::{self, Future},
-    Body, Client, Request,
-};
-use hyper_tls::{self, HttpsConnector};
-use native_tls;
-use std::boxed::Box;
-use std::sync::mpsc::channel;
-use std::sync::mpsc::Sender;
+use hyper::rt::Future;
+use std::{boxed::Box, sync::Arc};
 
-use futures::sync::mpsc;
+use futures::sync::mpsc::{self, UnboundedSender};
 
+pub mod builder;
 pub mod result_processor;
-
-use result_processor::{FSObject, SingleTildeScanResult, TildeRequest, TildeScanProcessor};
-
-use std::time::SystemTime;
-
-use indicatif::{ProgressBar, ProgressStyle};
+mod spec;
+
+use crate::{
+    error::Error,
+    fingerprint::{detect, load_signatures},
+    scan::{ScanHandle, ScanStream},
+    scheduler::{host_of, FairScheduler},
+    session::{self, HttpClient, RequestTemplate, SessionPool},
+};
+use result_processor::{FSObject, SingleTildeScanResult, TildeRequest};
 
 #[derive(Debug, Clone)]
 pub struct TildeBuster {
@@ -28,13 +25,29 @@ pub struct TildeBuster {
     pub http_body: String,
     pub user_agent: String,
     pub http_headers: Vec<(String, String)>,
-    pub url: String,
-    pub no_progress_bar: bool,
-    pub exit_on_connection_errors: bool,
-    pub output: String,
     pub extension: Option<String>,
 }
 
+fn build_request(url: &str, request: &TildeRequest) -> RequestTemplate {
+    RequestTemplate {
+        method: request.http_method.clone(),
+        uri: url.parse().unwrap(),
+        headers: vec![("User-Agent".to_owned(), request.user_agent.clone())],
+        body: request.http_body.clone(),
+    }
+}
+
+fn send(
+    client: &HttpClient,
+    sessions: &Option<Arc<SessionPool>>,
+    request: RequestTemplate,
+) -> impl Future<Item = session::Response, Error = Error> {
+    let exchange_client = client.clone();
+    session::send(client.clone(), sessions.clone(), move |session| {
+        session.exchange(&exchange_client, &request)
+    })
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum IISVersion {
     Unknown,
@@ -52,18 +65,14 @@ pub enum IISVersion {
 }
 
 impl TildeBuster {
-    pub fn run(mut self) {
-        let (tx, rx) = channel::<SingleTildeScanResult>();
-        let mut tls_connector_builder = native_tls::TlsConnector::builder();
-        tls_connector_builder.danger_accept_invalid_certs(self.ignore_certificate);
-        let tls_connector = tls_connector_builder
-            .build()
-            .expect("TLS initialization failed");
-        let mut http_connector = HttpConnector::new(self.n_threads);
-        http_connector.enforce_http(false);
-        let https_connector = HttpsConnector::from((http_connector, tls_connector));
-        let client = Client::builder().build(https_connector);
-        let mut current_numbers_of_request = 0;
+    fn stream(
+        self,
+        client: HttpClient,
+        sessions: Option<Arc<SessionPool>>,
+        targets: Vec<String>,
+        max_per_host: usize,
+    ) -> (ScanStream<SingleTildeScanResult>, ScanHandle) {
+        let (tx1, rx) = mpsc::unbounded::<Option<SingleTildeScanResult>>();
         let chars = "abcdefghijklmnopqrstuvwxyz1234567890-_"
             .split("")
             .filter(|c| !c.is_empty())
@@ -74,102 +83,83 @@ impl TildeBuster {
             .filter(|c| !c.is_empty())
             .map(|c| c.to_owned())
             .collect::<Vec<String>>();
-        let start_time = SystemTime::now();
-        let mut result_processor = TildeScanProcessor::new();
-        let output = self.output.clone();
-        let no_progress_bar = self.no_progress_bar;
-        let exit_on_connection_errors = self.exit_on_connection_errors;
-        let bar = if self.no_progress_bar {
-            ProgressBar::hidden()
-        } else {
-            ProgressBar::new_spinner()
-        };
-        let tx1 = tx.clone();
-        let client1 = client.clone();
-        let chars1 = chars.clone();
-        bar.set_style(
-            ProgressStyle::default_spinner().template("{spinner} [{elapsed_precise}] {msg}"),
-        );
+        let (tx_futures, rx_futures) = mpsc::unbounded::<(
+            String,
+            Box<dyn Future<Item = (), Error = Error> + Send + 'static>,
+        )>();
+        let tx_done = tx1.clone();
+        let scan = FairScheduler::new(
+            rx_futures,
+            move |f| {
+                let tx_done = tx_done.clone();
+                f.then(move |_| {
+                    let _ = tx_done.unbounded_send(None);
+                    Ok(())
+                })
+            },
+            self.n_threads,
+            max_per_host,
+        )
+        .for_each(Ok)
+        .or_else(|()| Ok(()));
+
+        for target in targets.iter() {
+            let url = if target.ends_with("/") {
+                target.to_owned()
+            } else {
+                format!("{}/", target)
+            };
+            let base_request = TildeRequest {
+                url,
+                http_method: self.http_method.clone(),
+                http_headers: self.http_headers.clone(),
+                http_body: self.http_body.clone(),
+                user_agent: self.user_agent.clone(),
+                filename: "".to_owned(),
+                extension: "".to_owned(),
+                redirect_extension: self.extension.clone(),
+                duplicate_index: "1".to_owned(),
+            };
 
-        if !self.url.ends_with("/") {
-            self.url = format!("{}/", self.url);
+            tx_futures
+                .unbounded_send((
+                    host_of(&base_request.url),
+                    Box::new(TildeBuster::_run_checks(
+                        tx1.clone(),
+                        client.clone(),
+                        sessions.clone(),
+                        base_request,
+                    )),
+                ))
+                .unwrap();
         }
 
-        let base_request = TildeRequest {
-            url: self.url.clone(),
-            http_method: self.http_method.clone(),
-            http_headers: self.http_headers.clone(),
-            http_body: self.http_body.clone(),
-            user_agent: self.user_agent.clone(),
-            filename: "".to_owned(),
-            extension: "".to_owned(),
-            redirect_extension: self.extension.clone(),
-            duplicate_index: "1".to_owned(),
+        let mut spawned_futures = targets.len();
+        let mut spawner = if spawned_futures > 0 {
+            Some((tx_futures, tx1))
+        } else {
+            None
         };
 
-        let (tx_futures, rx_futures) =
-            mpsc::unbounded::<Box<dyn Future<Item = (), Error = ()> + Send + 'static>>();
-        let stream_of_futures = rx_futures
-            .buffer_unordered(self.n_threads)
-            .for_each(Ok)
-            .map_err(|err| eprintln!("Err {:?}", err));
-
-        std::thread::spawn(|| rt::run(stream_of_futures));
-
-        tx_futures
-            .unbounded_send(Box::new(TildeBuster::_run_checks(
-                tx1.clone(),
-                client1.clone(),
-                base_request,
-            )))
-            .unwrap();
-
-        let mut spawned_futures = 1;
-
-        while spawned_futures > 0 {
+        let results = rx.filter_map(move |msg| {
             debug!("spawned_futures: {}", spawned_futures);
-            current_numbers_of_request = current_numbers_of_request + 1;
-            bar.inc(1);
-            spawned_futures = spawned_futures - 1;
-            let seconds_from_start = start_time.elapsed().unwrap().as_millis() / 1000;
-            if seconds_from_start != 0 {
-                bar.set_message(&format!(
-                    "{} requests done | req/s: {} | queued reqs: {}",
-                    current_numbers_of_request,
-                    current_numbers_of_request as u64 / seconds_from_start as u64,
-                    spawned_futures,
-                ));
-            } else {
-                bar.set_message("warming up...")
-            }
-
-            let msg = match rx.recv() {
-                Ok(msg) => msg,
-                Err(_err) => {
-                    error!("{:?}", _err);
-                    break;
+            let msg = match msg {
+                Some(msg) => msg,
+                None => {
+                    spawned_futures = spawned_futures - 1;
+                    if spawned_futures == 0 {
+                        spawner = None;
+                    }
+                    return None;
                 }
             };
 
-            match &msg.error {
-                Some(e) => {
-                    error!("{} - {:?}", msg.request.url, e);
-                    if current_numbers_of_request == 1 || exit_on_connection_errors {
-                        warn!("Check connectivity to the target");
-                        break;
-                    }
-
-                    continue;
-                }
-                None => match msg.kind {
-                    FSObject::NotVulnerable => {
-                        error!("The target doesn't seem to be vulnerable");
-                        warn!("Try setting HTTP method to OPTIONS or add an extension like aspx");
-                    }
+            if let (Some((tx_futures, tx1)), None) = (&spawner, &msg.error) {
+                match msg.kind {
                     FSObject::Vulnerable => {
                         for c in chars.iter() {
                             let request = TildeRequest {
-                                url: self.url.clone(),
+                                url: msg.request.url.clone(),
                                 http_method: self.http_method.clone(),
                                 http_headers: self.http_headers.clone(),
                                 http_body: self.http_body.clone(),
@@ -181,167 +171,103 @@ impl TildeBuster {
                             };
 
                             tx_futures
-                                .unbounded_send(Box::new(TildeBuster::_brute_filename(
-                                    tx1.clone(),
-                                    client1.clone(),
-                                    request,
-                                )))
-                                .unwrap();
-                            spawned_futures = spawned_futures + 1;
-                        }
-                    }
-                    FSObject::NotExisting => {
-                        trace!("{:?}", msg);
-                    }
-                    FSObject::DuplicateFile => {
-                        if no_progress_bar {
-                            println!(
-                                "File\t\t{}~{}.{}",
-                                msg.request.filename,
-                                msg.request.duplicate_index,
-                                msg.request.extension,
-                            );
-                        } else {
-                            bar.println(format!(
-                                "File\t\t{}~{}.{}",
-                                msg.request.filename,
-                                msg.request.duplicate_index,
-                                msg.request.extension,
-                            ));
-                        }
-
-                        result_processor.maybe_add_result(msg);
-                    }
-                    FSObject::DuplicateDirectory => {
-                        if no_progress_bar {
-                            println!(
-                                "Directory\t{}~{}",
-                                msg.request.filename, msg.request.duplicate_index,
-                            );
-                        } else {
-                            bar.println(format!(
-                                "Directory\t{}~{}",
-                                msg.request.filename, msg.request.duplicate_index,
-                            ));
-                        }
-
-                        result_processor.maybe_add_result(msg);
-                    }
-                    FSObject::File => {
-                        if no_progress_bar {
-                            println!(
-                                "File\t\t{}~{}.{}",
-                                msg.request.filename,
-                                msg.request.duplicate_index,
-                                msg.request.extension,
-                            );
-                        } else {
-                            bar.println(format!(
-                                "File\t\t{}~{}.{}",
-                                msg.request.filename,
-                                msg.request.duplicate_index,
-                                msg.request.extension,
-                            ));
-                        }
-
-                        for c in chars_duplicate.iter() {
-                            let mut request = msg.request.clone();
-                            request.duplicate_index = c.clone();
-                            tx_futures
-                                .unbounded_send(Box::new(TildeBuster::_brute_duplicate(
-                                    tx1.clone(),
-                                    client1.clone(),
-                                    request,
-                                )))
+                                .unbounded_send((
+                                    host_of(&request.url),
+                                    Box::new(TildeBuster::_brute_filename(
+                                        tx1.clone(),
+                                        client.clone(),
+                                        sessions.clone(),
+                                        request,
+                                    )),
+                                ))
                                 .unwrap();
                             spawned_futures = spawned_futures + 1;
                         }
-
-                        result_processor.maybe_add_result(msg);
                     }
-                    FSObject::Directory => {
-                        if no_progress_bar {
-                            println!(
-                                "Directory\t{}~{}",
-                                msg.request.filename, msg.request.duplicate_index
-                            );
-                        } else {
-                            bar.println(format!(
-                                "Directory\t{}~{}",
-                                msg.request.filename, msg.request.duplicate_index
-                            ));
-                        }
-
+                    FSObject::File | FSObject::Directory => {
                         for c in chars_duplicate.iter() {
                             let mut request = msg.request.clone();
                             request.duplicate_index = c.clone();
                             tx_futures
-                                .unbounded_send(Box::new(TildeBuster::_brute_duplicate(
-                                    tx1.clone(),
-                                    client1.clone(),
-                                    request,
-                                )))
+                                .unbounded_send((
+                                    host_of(&request.url),
+                                    Box::new(TildeBuster::_brute_duplicate(
+                                        tx1.clone(),
+                                        client.clone(),
+                                        sessions.clone(),
+                                        request,
+                                    )),
+                                ))
                                 .unwrap();
                             spawned_futures = spawned_futures + 1;
                         }
-
-                        result_processor.maybe_add_result(msg);
                     }
                     FSObject::BruteExtension => {
-                        for c in chars1.iter() {
+                        for c in chars.iter() {
                             let mut request = msg.request.clone();
                             request.extension = format!("{}{}", request.extension, c);
                             tx_futures
-                                .unbounded_send(Box::new(TildeBuster::_brute_extension(
-                                    tx1.clone(),
-                                    client1.clone(),
-                                    request,
-                                )))
+                                .unbounded_send((
+                                    host_of(&request.url),
+                                    Box::new(TildeBuster::_brute_extension(
+                                        tx1.clone(),
+                                        client.clone(),
+                                        sessions.clone(),
+                                        request,
+                                    )),
+                                ))
                                 .unwrap();
                             spawned_futures = spawned_futures + 1;
                         }
                     }
                     FSObject::BruteFilename => {
-                        for c in chars1.iter() {
+                        for c in chars.iter() {
                             let mut request = msg.request.clone();
                             request.filename = format!("{}{}", request.filename, c);
                             tx_futures
-                                .unbounded_send(Box::new(TildeBuster::_brute_filename(
-                                    tx1.clone(),
-                                    client1.clone(),
-                                    request,
-                                )))
+                                .unbounded_send((
+                                    host_of(&request.url),
+                                    Box::new(TildeBuster::_brute_filename(
+                                        tx1.clone(),
+                                        client.clone(),
+                                        sessions.clone(),
+                                        request,
+                                    )),
+                                ))
                                 .unwrap();
                             spawned_futures = spawned_futures + 1;
                         }
                     }
                     FSObject::CheckIfDirectory => {
                         tx_futures
-                            .unbounded_send(Box::new(TildeBuster::_check_if_directory(
-                                tx1.clone(),
-                                client1.clone(),
-                                msg.request,
-                            )))
+                            .unbounded_send((
+                                host_of(&msg.request.url),
+                                Box::new(TildeBuster::_check_if_directory(
+                                    tx1.clone(),
+                                    client.clone(),
+                                    sessions.clone(),
+                                    msg.request.clone(),
+                                )),
+                            ))
                             .unwrap();
                         spawned_futures = spawned_futures + 1;
                     }
-                },
+                    _ => (),
+                }
             }
-        }
 
-        bar.finish();
-        println!("{}", crate::banner::ending_time());
+            Some(msg)
+        });
 
-        if !output.is_empty() {
-            result_processor.save_tilde_results(&output);
-        }
+        ScanStream::new(scan, results, None)
     }
 
     fn _brute_extension(
-        tx: Sender<SingleTildeScanResult>,
-        client: Client<HttpsConnector<HttpConnector>>,
+        tx: UnboundedSender<Option<SingleTildeScanResult>>,
+        client: HttpClient,
+        sessions: Option<Arc<SessionPool>>,
         request: TildeRequest,
-    ) -> impl Future<Item = (), Error = ()> {
+    ) -> impl Future<Item = (), Error = Error> {
         let magic_suffix = match &request.redirect_extension {
             Some(v) => format!("/.{}", v),
             None => "".to_owned(),
@@ -355,44 +281,29 @@ impl TildeBuster {
             "%3f".repeat(3 - request.extension.len()),
             magic_suffix,
         );
-        let hyper_request = Request::builder()
-            .header("User-Agent", &request.user_agent[..])
-            .method(&request.http_method[..])
-            .uri(&vuln_url.parse::<hyper::Uri>().unwrap())
-            .body(Body::from(request.http_body.clone()))
-            .expect("Request builder");
-
-        client
-            .request(hyper_request)
+        let hyper_request = build_request(&vuln_url, &request);
+
+        send(&client, &sessions, hyper_request)
             .and_then(move |res| {
-                match (res.status(), request.extension.len()) {
+                match (res.status, request.extension.len()) {
                     (hyper::StatusCode::NOT_FOUND, 3) => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::File,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result =
+                            SingleTildeScanResult::new(FSObject::File, request).response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     }
                     (hyper::StatusCode::NOT_FOUND, _) => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::BruteExtension,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result = SingleTildeScanResult::new(FSObject::BruteExtension, request)
+                            .response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     }
                     (hyper::StatusCode::BAD_REQUEST, _) | _ => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::NotExisting,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result = SingleTildeScanResult::new(FSObject::NotExisting, request)
+                            .response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     } // _ => {
                       //     warn!(
                       //         "Got invalid HTTP status code when bruteforcing the extension: {}",
-                      //         res.status()
+                      //         res.status
                       //     );
                       // }
                 }
@@ -406,10 +317,11 @@ impl TildeBuster {
     }
 
     fn _brute_filename(
-        tx: Sender<SingleTildeScanResult>,
-        client: Client<HttpsConnector<HttpConnector>>,
+        tx: UnboundedSender<Option<SingleTildeScanResult>>,
+        client: HttpClient,
+        sessions: Option<Arc<SessionPool>>,
         request: TildeRequest,
-    ) -> impl Future<Item = (), Error = ()> {
+    ) -> impl Future<Item = (), Error = Error> {
         let magic_suffix = match &request.redirect_extension {
             Some(v) => format!("*~1*/.{}", v),
             None => "*~1*".to_owned(),
@@ -423,59 +335,38 @@ impl TildeBuster {
         let vuln_url = format!("{}{}{}", request.url, request.filename, magic_suffix);
         let vuln_url_short = format!("{}{}{}", request.url, request.filename, magic_suffix_short);
 
-        let hyper_request = Request::builder()
-            .header("User-Agent", &request.user_agent[..])
-            .method(&request.http_method[..])
-            .uri(&vuln_url.parse::<hyper::Uri>().unwrap())
-            .body(Body::from(request.http_body.clone()))
-            .expect("Request builder");
+        let hyper_request = build_request(&vuln_url, &request);
 
-        let hyper_request_short = Request::builder()
-            .header("User-Agent", &request.user_agent[..])
-            .method(&request.http_method[..])
-            .uri(&vuln_url_short.parse::<hyper::Uri>().unwrap())
-            .body(Body::from(request.http_body.clone()))
-            .expect("Request builder");
+        let hyper_request_short = build_request(&vuln_url_short, &request);
 
-        let req = client.request(hyper_request);
-        let req_short = client.request(hyper_request_short);
+        let req = send(&client, &sessions, hyper_request);
+        let req_short = send(&client, &sessions, hyper_request_short);
 
         req.join(req_short)
             .and_then(move |(res, res_short)| {
-                match (res.status(), res_short.status()) {
+                match (res.status, res_short.status) {
                     (_, hyper::StatusCode::NOT_FOUND) => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::CheckIfDirectory,
-                            error: None,
-                            request: request.clone(),
-                        };
-                        tx.send(res).unwrap();
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::BruteFilename,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result =
+                            SingleTildeScanResult::new(FSObject::CheckIfDirectory, request.clone())
+                                .response(&res_short);
+                        let _ = tx.unbounded_send(Some(result));
+                        let result = SingleTildeScanResult::new(FSObject::BruteFilename, request)
+                            .response(&res_short);
+                        let _ = tx.unbounded_send(Some(result));
                     }
                     (hyper::StatusCode::NOT_FOUND, _) => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::BruteFilename,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result = SingleTildeScanResult::new(FSObject::BruteFilename, request)
+                            .response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     }
                     (hyper::StatusCode::BAD_REQUEST, _) | _ => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::NotExisting,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result = SingleTildeScanResult::new(FSObject::NotExisting, request)
+                            .response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     } // _ => {
                       //     warn!(
                       //         "Got invalid HTTP status code when bruteforcing the filename: {}",
-                      //         res.status()
+                      //         res.status
                       //     );
                       // }
                 }
@@ -489,45 +380,34 @@ impl TildeBuster {
     }
 
     fn _check_if_directory(
-        tx: Sender<SingleTildeScanResult>,
-        client: Client<HttpsConnector<HttpConnector>>,
+        tx: UnboundedSender<Option<SingleTildeScanResult>>,
+        client: HttpClient,
+        sessions: Option<Arc<SessionPool>>,
         request: TildeRequest,
-    ) -> impl Future<Item = (), Error = ()> {
+    ) -> impl Future<Item = (), Error = Error> {
         let magic_suffix = match &request.redirect_extension {
             Some(v) => format!("*~1/.{}", v),
             None => "*~1".to_owned(),
         };
         let vuln_url = format!("{}{}{}", request.url, request.filename, magic_suffix);
-        let hyper_request = Request::builder()
-            .header("User-Agent", &request.user_agent[..])
-            .method(&request.http_method[..])
-            .uri(vuln_url.parse::<hyper::Uri>().unwrap())
-            .body(Body::from(request.http_body.clone()))
-            .expect("Request builder");
-
-        client
-            .request(hyper_request)
+        let hyper_request = build_request(&vuln_url, &request);
+
+        send(&client, &sessions, hyper_request)
             .and_then(move |res| {
-                match res.status() {
+                match res.status {
                     hyper::StatusCode::NOT_FOUND => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::Directory,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result =
+                            SingleTildeScanResult::new(FSObject::Directory, request).response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     }
                     hyper::StatusCode::BAD_REQUEST | _ => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::BruteExtension,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result = SingleTildeScanResult::new(FSObject::BruteExtension, request)
+                            .response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     } // _ => {
                       //     warn!(
                       //         "Got invalid HTTP status code when checking if directory: {}",
-                      //         res.status()
+                      //         res.status
                       //     );
                       // }
                 }
@@ -541,46 +421,45 @@ impl TildeBuster {
     }
 
     pub fn check_iis_version(
-        client: &Client<HttpsConnector<HttpConnector>>,
+        client: &HttpClient,
+        sessions: &Option<Arc<SessionPool>>,
         request: TildeRequest,
-    ) -> impl Future<Item = IISVersion, Error = hyper::Error> {
-        let hyper_request = Request::builder()
-            .header("User-Agent", &request.user_agent[..])
-            .method(&request.http_method[..])
-            .uri(request.url.parse::<hyper::Uri>().unwrap())
-            .body(Body::from(request.http_body.clone()))
-            .expect("Request builder");
-
-        client
-            .request(hyper_request)
-            .and_then(move |res| Ok(TildeBuster::map_iis_version(res.headers())))
+    ) -> impl Future<Item = IISVersion, Error = Error> {
+        let hyper_request = build_request(&request.url, &request);
+
+        send(client, sessions, hyper_request)
+            .and_then(move |res| Ok(TildeBuster::map_iis_version(&res.headers)))
     }
 
     pub fn map_iis_version(headers: &hyper::HeaderMap) -> IISVersion {
-        match headers.get("Server") {
-            None => IISVersion::Unknown,
-            Some(v) => match v.to_str().unwrap() {
-                "Microsoft-IIS/1" => IISVersion::IIS1,
-                "Microsoft-IIS/2" => IISVersion::IIS2,
-                "Microsoft-IIS/3" => IISVersion::IIS3,
-                "Microsoft-IIS/4" => IISVersion::IIS4,
-                "Microsoft-IIS/5" => IISVersion::IIS5,
-                "Microsoft-IIS/6" => IISVersion::IIS6,
-                "Microsoft-IIS/7" => IISVersion::IIS7,
-                "Microsoft-IIS/7.5" => IISVersion::IIS75,
-                "Microsoft-IIS/8" => IISVersion::IIS8,
-                "Microsoft-IIS/8.5" => IISVersion::IIS85,
-                "Microsoft-IIS/10" => IISVersion::IIS10,
-                _ => IISVersion::Unknown,
-            },
+        let signatures = load_signatures(None).unwrap_or_default();
+        let version = detect(&signatures, headers, "", None)
+            .into_iter()
+            .find(|technology| technology.name == "IIS")
+            .and_then(|technology| technology.version);
+
+        match version.as_ref().map(|v| v.trim_end_matches(".0")) {
+            Some("1") => IISVersion::IIS1,
+            Some("2") => IISVersion::IIS2,
+            Some("3") => IISVersion::IIS3,
+            Some("4") => IISVersion::IIS4,
+            Some("5") => IISVersion::IIS5,
+            Some("6") => IISVersion::IIS6,
+            Some("7") => IISVersion::IIS7,
+            Some("7.5") => IISVersion::IIS75,
+            Some("8") => IISVersion::IIS8,
+            Some("8.5") => IISVersion::IIS85,
+            Some("10") => IISVersion::IIS10,
+            _ => IISVersion::Unknown,
         }
     }
 
     pub fn check_if_vulnerable(
-        client: &Client<HttpsConnector<HttpConnector>>,
+        client: &HttpClient,
+        sessions: &Option<Arc<SessionPool>>,
         request: TildeRequest,
         _version: IISVersion,
-    ) -> impl Future<Item = bool, Error = hyper::Error> {
+    ) -> impl Future<Item = bool, Error = Error> {
         let magic_suffix = match &request.redirect_extension {
             Some(v) => format!("*~1*/.{}", v),
             None => "*~1*".to_owned(),
@@ -591,43 +470,30 @@ impl TildeBuster {
         };
         let vuln_url = format!("{}{}", request.url, magic_suffix);
         let not_existing_url = format!("{}{}", request.url, not_existing_suffix);
-        let hyper_request = Request::builder()
-            .header("User-Agent", &request.user_agent[..])
-            .method(&request.http_method[..])
-            .uri(vuln_url.parse::<hyper::Uri>().unwrap())
-            .body(Body::from(request.http_body.clone()))
-            .expect("Request builder");
-
-        let not_existing_hyper_request = Request::builder()
-            .header("User-Agent", &request.user_agent[..])
-            .method(&request.http_method[..])
-            .uri(not_existing_url.parse::<hyper::Uri>().unwrap())
-            .body(Body::from(request.http_body.clone()))
-            .expect("Request builder");
-
-        let fut1 = client
-            .request(hyper_request)
-            .and_then(|res| match res.status() {
-                hyper::StatusCode::NOT_FOUND => Ok(true),
-                hyper::StatusCode::BAD_REQUEST => Ok(false),
-                _ => {
-                    warn!(
-                        "Got invalid HTTP status code when checking if vulnerable: {}",
-                        res.status()
-                    );
-                    Ok(false)
-                }
-            });
+        let hyper_request = build_request(&vuln_url, &request);
+
+        let not_existing_hyper_request = build_request(&not_existing_url, &request);
+
+        let fut1 = send(client, sessions, hyper_request).and_then(|res| match res.status {
+            hyper::StatusCode::NOT_FOUND => Ok(true),
+            hyper::StatusCode::BAD_REQUEST => Ok(false),
+            _ => {
+                warn!(
+                    "Got invalid HTTP status code when checking if vulnerable: {}",
+                    res.status
+                );
+                Ok(false)
+            }
+        });
 
-        let fut2 = client
-            .request(not_existing_hyper_request)
-            .and_then(|res| match res.status() {
+        let fut2 =
+            send(client, sessions, not_existing_hyper_request).and_then(|res| match res.status {
                 hyper::StatusCode::NOT_FOUND => Ok(true),
                 hyper::StatusCode::BAD_REQUEST => Ok(false),
                 _ => {
                     warn!(
                         "Got invalid HTTP status code when checking if vulnerable: {}",
-                        res.status()
+                        res.status
                     );
                     Ok(false)
                 }
@@ -640,15 +506,17 @@ impl TildeBuster {
     }
 
     pub fn _run_checks(
-        tx: Sender<SingleTildeScanResult>,
-        client: Client<HttpsConnector<HttpConnector>>,
+        tx: UnboundedSender<Option<SingleTildeScanResult>>,
+        client: HttpClient,
+        sessions: Option<Arc<SessionPool>>,
         request: TildeRequest,
-    ) -> impl Future<Item = (), Error = ()> {
-        TildeBuster::check_iis_version(&client, request.clone())
+    ) -> impl Future<Item = (), Error = Error> {
+        TildeBuster::check_iis_version(&client, &sessions, request.clone())
             .and_then(move |version| {
                 futures::future::ok(version.clone())
                     .join(TildeBuster::check_if_vulnerable(
                         &client.clone(),
+                        &sessions,
                         request.clone(),
                         version,
                     ))
@@ -656,19 +524,11 @@ impl TildeBuster {
                         info!("iis version: {:?}", version);
                         info!("is vulnerable: {:?}", is_vulnerable);
                         let res = match is_vulnerable {
-                            true => SingleTildeScanResult {
-                                kind: FSObject::Vulnerable,
-                                error: None,
-                                request: request,
-                            },
-                            false => SingleTildeScanResult {
-                                kind: FSObject::NotVulnerable,
-                                error: None,
-                                request: request,
-                            },
+                            true => SingleTildeScanResult::new(FSObject::Vulnerable, request),
+                            false => SingleTildeScanResult::new(FSObject::NotVulnerable, request),
                         };
 
-                        tx.send(res).unwrap();
+                        let _ = tx.unbounded_send(Some(res));
                         Ok(())
                     })
             })
@@ -679,10 +539,11 @@ impl TildeBuster {
     }
 
     pub fn _brute_duplicate(
-        tx: Sender<SingleTildeScanResult>,
-        client: Client<HttpsConnector<HttpConnector>>,
+        tx: UnboundedSender<Option<SingleTildeScanResult>>,
+        client: HttpClient,
+        sessions: Option<Arc<SessionPool>>,
         request: TildeRequest,
-    ) -> impl Future<Item = (), Error = ()> {
+    ) -> impl Future<Item = (), Error = Error> {
         let vuln_url = match (&request.extension.len(), &request.redirect_extension) {
             (0, Some(v)) => format!(
                 "{}{}~{}/.{}",
@@ -702,44 +563,30 @@ impl TildeBuster {
             ),
         };
 
-        let hyper_request = Request::builder()
-            .header("User-Agent", &request.user_agent[..])
-            .method(&request.http_method[..])
-            .uri(&vuln_url.parse::<hyper::Uri>().unwrap())
-            .body(Body::from(request.http_body.clone()))
-            .expect("Request builder");
+        let hyper_request = build_request(&vuln_url, &request);
 
-        client
-            .request(hyper_request)
+        send(&client, &sessions, hyper_request)
             .and_then(move |res| {
-                match (res.status(), request.extension.len()) {
+                match (res.status, request.extension.len()) {
                     (hyper::StatusCode::NOT_FOUND, 3) => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::DuplicateFile,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result = SingleTildeScanResult::new(FSObject::DuplicateFile, request)
+                            .response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     }
                     (hyper::StatusCode::NOT_FOUND, _) => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::DuplicateDirectory,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result =
+                            SingleTildeScanResult::new(FSObject::DuplicateDirectory, request)
+                                .response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     }
                     (hyper::StatusCode::BAD_REQUEST, _) | _ => {
-                        let res = SingleTildeScanResult {
-                            kind: FSObject::NotExisting,
-                            error: None,
-                            request: request,
-                        };
-                        tx.send(res).unwrap();
+                        let result = SingleTildeScanResult::new(FSObject::NotExisting, request)
+                            .response(&res);
+                        let _ = tx.unbounded_send(Some(result));
                     } // _ => {
                       //     warn!(
                       //         "Got invalid HTTP status code when bruteforcing duplicates: {}",
-                      //         res.status()
+                      //         res.status
                       //     );
                       // }
                 }
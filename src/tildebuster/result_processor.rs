@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::{fs::File, io::Write, path::Path, str};
 
+use crate::{
+    matcher::{collect_headers, find_header, Matchable, ResultFilter},
+    session::Response,
+};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum FSObject {
     File,
@@ -33,24 +38,67 @@ pub struct SingleTildeScanResult {
     pub kind: FSObject,
     pub error: Option<String>,
     pub request: TildeRequest,
+    pub status: String,
+    pub length: Option<usize>,
+    pub headers: Vec<(String, String)>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl SingleTildeScanResult {
+    pub fn new(kind: FSObject, request: TildeRequest) -> Self {
+        SingleTildeScanResult {
+            kind,
+            error: None,
+            request,
+            status: String::new(),
+            length: None,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn response(mut self, response: &Response) -> Self {
+        self.status = response.status.to_string();
+        self.length = Some(response.length);
+        self.headers = collect_headers(&response.headers);
+        self
+    }
+}
+
+impl Matchable for SingleTildeScanResult {
+    fn status(&self) -> Option<&str> {
+        Some(self.status.as_str()).filter(|status| !status.is_empty())
+    }
+
+    fn length(&self) -> Option<usize> {
+        self.length
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+#[derive(Default)]
 pub struct TildeScanProcessor {
     pub results: Vec<SingleTildeScanResult>,
+    filter: ResultFilter<SingleTildeScanResult>,
 }
 
 impl TildeScanProcessor {
-    pub fn new() -> Self {
+    pub fn new(filter: ResultFilter<SingleTildeScanResult>) -> Self {
         TildeScanProcessor {
             results: Vec::<SingleTildeScanResult>::new(),
+            filter,
         }
     }
 
     pub fn maybe_add_result(&mut self, res: SingleTildeScanResult) -> bool {
         trace!("{:?}", res);
-        self.results.push(res);
-        return true;
+        if self.filter.is_match(&res) {
+            self.results.push(res);
+            return true;
+        }
+
+        false
     }
 
     pub fn save_tilde_results(&self, path: &str) {
@@ -0,0 +1,50 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name tildebuster;
+
+    use crate::{
+        matcher::{expr::Expression, Matcher, ResultFilter, SizeMatcher, StatusMatcher},
+        session::Response,
+        tildebuster::result_processor::{FSObject, SingleTildeScanResult, TildeRequest},
+    };
+    use hyper::{header, Chunk, HeaderMap, StatusCode};
+    use std::time::Duration;
+
+    fn request() -> TildeRequest {
+        TildeRequest {
+            url: "http://localhost/".to_owned(),
+            http_method: "OPTIONS".to_owned(),
+            http_headers: Vec::new(),
+            http_body: String::new(),
+            user_agent: "rustbuster".to_owned(),
+            filename: "WEBCON".to_owned(),
+            redirect_extension: None,
+            extension: "CON".to_owned(),
+            duplicate_index: "1".to_owned(),
+        }
+    }
+
+    test result_matchers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::SERVER, "Microsoft-IIS/8.5".parse().unwrap());
+        let response = Response {
+            status: StatusCode::NOT_FOUND,
+            headers,
+            body: Chunk::from("Not Found"),
+            length: 9,
+            time: Duration::from_millis(20),
+        };
+        let result = SingleTildeScanResult::new(FSObject::File, request()).response(&response);
+        let vulnerable = SingleTildeScanResult::new(FSObject::Vulnerable, request());
+
+        assert!("404".parse::<StatusMatcher>().unwrap().matches(&result));
+        assert!(!"404".parse::<StatusMatcher>().unwrap().matches(&vulnerable));
+        assert!(SizeMatcher::exact(9).matches(&result));
+        assert!(Expression::parse("status == 404 && header.server ~ /IIS/").unwrap().matches(&result));
+
+        let filter = ResultFilter::new().expressions(&[], &[Expression::parse("size == 9").unwrap()]);
+        assert!(!filter.is_match(&result));
+        assert!(filter.is_match(&vulnerable));
+    }
+}
@@ -0,0 +1,190 @@
+use std::{collections::HashMap, sync::mpsc::Sender};
+
+use super::{
+    harvest::seed_vhosts, result_processor::SingleVhostScanResult, utils::build_vhosts, VhostConfig,
+};
+use crate::{
+    error::{check_url, Error},
+    scan::{ScanHandle, ScanStream},
+    session::{auth::Credentials, SessionConfig},
+};
+
+#[derive(Debug, Clone)]
+pub struct VhostScanBuilder {
+    config: VhostConfig,
+    targets: Vec<String>,
+    wordlist_paths: Vec<String>,
+    harvest: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct VhostScan {
+    targets: Vec<(VhostConfig, Vec<hyper::Uri>)>,
+}
+
+impl VhostScanBuilder {
+    pub fn new() -> Self {
+        VhostScanBuilder {
+            config: VhostConfig {
+                n_threads: 10,
+                ignore_certificate: false,
+                user_agent: "rustbuster".to_owned(),
+                http_method: "GET".to_owned(),
+                http_body: "".to_owned(),
+                http_headers: Vec::new(),
+                original_url: "".to_owned(),
+                domain: "".to_owned(),
+                similarity_threshold: 0.9,
+                sni: false,
+                sources: HashMap::new(),
+                max_per_host: 0,
+                auth: None,
+                session: None,
+            },
+            targets: Vec::new(),
+            wordlist_paths: Vec::new(),
+            harvest: false,
+        }
+    }
+
+    pub fn target(mut self, url: &str) -> Self {
+        self.targets.push(url.to_owned());
+        self
+    }
+
+    pub fn targets(mut self, urls: Vec<String>) -> Self {
+        self.targets.extend(urls);
+        self
+    }
+
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.config.domain = domain.to_owned();
+        self
+    }
+
+    pub fn wordlist(mut self, path: &str) -> Self {
+        self.wordlist_paths.push(path.to_owned());
+        self
+    }
+
+    pub fn similarity_threshold(mut self, similarity_threshold: f64) -> Self {
+        self.config.similarity_threshold = similarity_threshold;
+        self
+    }
+
+    pub fn sni(mut self, sni: bool) -> Self {
+        self.config.sni = sni;
+        self
+    }
+
+    pub fn auth(mut self, credentials: Credentials) -> Self {
+        self.config.auth = Some(credentials);
+        self
+    }
+
+    pub fn session(mut self, session: SessionConfig) -> Self {
+        self.config.session = Some(session);
+        self
+    }
+
+    pub fn harvest(mut self, harvest: bool) -> Self {
+        self.harvest = harvest;
+        self
+    }
+
+    pub fn threads(mut self, n_threads: usize) -> Self {
+        self.config.n_threads = n_threads;
+        self
+    }
+
+    pub fn max_per_host(mut self, max_per_host: usize) -> Self {
+        self.config.max_per_host = max_per_host;
+        self
+    }
+
+    pub fn ignore_certificate(mut self, ignore_certificate: bool) -> Self {
+        self.config.ignore_certificate = ignore_certificate;
+        self
+    }
+
+    pub fn method(mut self, http_method: &str) -> Self {
+        self.config.http_method = http_method.to_owned();
+        self
+    }
+
+    pub fn body(mut self, http_body: &str) -> Self {
+        self.config.http_body = http_body.to_owned();
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.config.user_agent = user_agent.to_owned();
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.config
+            .http_headers
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn build(self) -> Result<VhostScan, Error> {
+        if self.targets.is_empty() {
+            return Err(Error::MissingOption("target"));
+        }
+        if self.wordlist_paths.is_empty() {
+            return Err(Error::MissingOption("wordlist"));
+        }
+
+        let mut targets = Vec::new();
+        for target in &self.targets {
+            let uri = check_url(target)?;
+            let mut config = self.config.clone();
+            config.original_url = target.to_owned();
+            if config.domain.is_empty() {
+                config.domain = uri
+                    .host()
+                    .unwrap_or_default()
+                    .trim_end_matches('.')
+                    .to_lowercase();
+            }
+
+            let mut vhosts = Vec::new();
+            for wordlist in &self.wordlist_paths {
+                vhosts.extend(build_vhosts(wordlist, &config.domain)?);
+            }
+            if self.harvest {
+                vhosts = seed_vhosts(&mut config, vhosts)?;
+            }
+
+            targets.push((config, vhosts));
+        }
+
+        Ok(VhostScan { targets })
+    }
+}
+
+impl Default for VhostScanBuilder {
+    fn default() -> Self {
+        VhostScanBuilder::new()
+    }
+}
+
+impl VhostScan {
+    pub fn builder() -> VhostScanBuilder {
+        VhostScanBuilder::new()
+    }
+
+    pub fn total(&self) -> usize {
+        self.targets.iter().map(|(_, vhosts)| vhosts.len()).sum()
+    }
+
+    pub fn stream(self) -> Result<(ScanStream<SingleVhostScanResult>, ScanHandle), Error> {
+        super::stream(self.targets)
+    }
+
+    pub fn run(self, tx: Sender<SingleVhostScanResult>) -> Result<(), Error> {
+        super::run(tx, self.targets)
+    }
+}
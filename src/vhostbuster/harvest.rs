@@ -0,0 +1,189 @@
+use hyper::{header, Uri};
+use regex::Regex;
+
+use std::{collections::HashSet, net::IpAddr};
+
+use super::{resolve_target, tls::SniConnector, VhostConfig};
+use crate::{
+    error::Error,
+    session::{HttpClient, RequestTemplate},
+};
+
+pub const SOURCE_CERTIFICATE: &str = "certificate";
+pub const SOURCE_REDIRECT: &str = "redirect";
+pub const SOURCE_CSP: &str = "csp";
+pub const SOURCE_LINK: &str = "link";
+
+lazy_static! {
+    static ref HOST: Regex = Regex::new(r"^[a-z0-9_-]+(\.[a-z0-9_-]+)+$").unwrap();
+    static ref LINK: Regex =
+        Regex::new(r#"(?i)(?:href|src|action)\s*=\s*["']?(?:https?:)?//([^/"'\s:>?#]+)"#).unwrap();
+}
+
+fn normalize_host(host: &str) -> Option<String> {
+    let host = host
+        .trim()
+        .trim_start_matches("*.")
+        .trim_end_matches('.')
+        .to_lowercase();
+
+    if !HOST.is_match(&host) || host.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+
+    Some(host)
+}
+
+pub fn in_scope(host: &str, domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+pub fn csp_hosts(policy: &str) -> Vec<String> {
+    policy
+        .split(|c: char| c == ';' || c.is_whitespace())
+        .filter(|token| !token.starts_with('\'') && token.contains('.'))
+        .map(|token| match token.find("://") {
+            Some(i) => &token[i + 3..],
+            None => token,
+        })
+        .filter_map(|token| {
+            let host = token.split('/').next().unwrap_or_default();
+            normalize_host(host.split(':').next().unwrap_or_default())
+        })
+        .collect()
+}
+
+pub fn link_hosts(body: &str) -> Vec<String> {
+    LINK.captures_iter(body)
+        .filter_map(|captures| normalize_host(&captures[1]))
+        .collect()
+}
+
+fn redirect_host(location: &str) -> Option<String> {
+    location
+        .parse::<Uri>()
+        .ok()
+        .and_then(|uri| uri.host().and_then(normalize_host))
+}
+
+pub fn harvest(config: &VhostConfig) -> Result<Vec<(String, String)>, Error> {
+    let addresses = match resolve_target(&config.original_url) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Unable to resolve {}: {}", config.original_url, e);
+            return Ok(Vec::new());
+        }
+    };
+
+    let mut tls_connector_builder = native_tls::TlsConnector::builder();
+    tls_connector_builder.danger_accept_invalid_certs(true);
+    tls_connector_builder.danger_accept_invalid_hostnames(true);
+    let tls_connector = tls_connector_builder.build()?;
+    let connector = SniConnector::new(addresses, tls_connector);
+    let certificates = connector.certificates.clone();
+    let client = HttpClient::new(connector).auth(config.auth.clone());
+
+    let mut headers: Vec<(String, String)> = config
+        .http_headers
+        .iter()
+        .filter(|(header, value)| !header.contains("FUZZ") && !value.contains("FUZZ"))
+        .cloned()
+        .collect();
+    headers.push(("User-Agent".to_owned(), config.user_agent.clone()));
+    let request = match config.original_url.parse::<Uri>() {
+        Ok(uri) => RequestTemplate {
+            method: "GET".to_owned(),
+            uri,
+            headers,
+            body: String::new(),
+        },
+        Err(e) => {
+            warn!("Unable to build the harvesting request: {}", e);
+            return Ok(Vec::new());
+        }
+    };
+
+    let mut runtime = tokio::runtime::Runtime::new().map_err(Error::Runtime)?;
+    let response = runtime.block_on(client.send(&request, None));
+
+    let mut candidates: Vec<(String, String)> = Vec::new();
+    for certificate in certificates.lock().unwrap().values() {
+        let common_name = certificate
+            .subject
+            .split(", ")
+            .filter_map(|attribute| attribute.strip_prefix("CN="))
+            .filter_map(normalize_host);
+        for host in common_name.chain(
+            certificate
+                .sans
+                .iter()
+                .filter_map(|san| normalize_host(san)),
+        ) {
+            candidates.push((host, SOURCE_CERTIFICATE.to_owned()));
+        }
+    }
+
+    match response {
+        Ok(response) => {
+            let headers = response.headers;
+            let body = response.body;
+            let redirects = headers
+                .get_all(header::LOCATION)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .filter_map(redirect_host)
+                .map(|host| (host, SOURCE_REDIRECT));
+            let policies = headers
+                .get_all(header::CONTENT_SECURITY_POLICY)
+                .iter()
+                .chain(
+                    headers
+                        .get_all(header::CONTENT_SECURITY_POLICY_REPORT_ONLY)
+                        .iter(),
+                )
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(csp_hosts)
+                .map(|host| (host, SOURCE_CSP));
+            let links = link_hosts(&String::from_utf8_lossy(&body))
+                .into_iter()
+                .map(|host| (host, SOURCE_LINK));
+
+            candidates.extend(
+                redirects
+                    .chain(policies)
+                    .chain(links)
+                    .filter(|(host, _)| in_scope(host, &config.domain))
+                    .map(|(host, source)| (host, source.to_owned())),
+            );
+        }
+        Err(e) => warn!("Unable to request {}: {}", config.original_url, e),
+    }
+
+    let mut seen = HashSet::new();
+    candidates.retain(|(host, _)| seen.insert(host.clone()));
+    debug!("harvested {:?}", candidates);
+
+    Ok(candidates)
+}
+
+pub fn seed_vhosts(config: &mut VhostConfig, vhosts: Vec<Uri>) -> Result<Vec<Uri>, Error> {
+    let seeds = harvest(config)?;
+    info!(
+        "Harvested {} vhosts from {}",
+        seeds.len(),
+        config.original_url
+    );
+    let mut seeded: Vec<Uri> = seeds
+        .iter()
+        .filter_map(|(host, _)| host.parse::<Uri>().ok())
+        .collect();
+    seeded.extend(
+        vhosts
+            .into_iter()
+            .filter(|url| !seeds.iter().any(|(host, _)| url == host.as_str())),
+    );
+    config.sources = seeds.into_iter().collect();
+
+    Ok(seeded)
+}
@@ -1,101 +1,412 @@
-use futures::Stream;
+use futures::{
+    future,
+    sync::mpsc::{self, UnboundedSender},
+    Stream,
+};
 use hyper::{
-    client::HttpConnector,
-    rt::{self, Future},
-    Body, Client, Request, StatusCode, Uri,
+    client::{connect::Connect, HttpConnector},
+    rt::Future,
+    StatusCode, Uri,
 };
 use hyper_tls::{self, HttpsConnector};
 use native_tls;
 
-use std::sync::{mpsc::Sender, Arc, Mutex};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, ToSocketAddrs},
+    sync::{mpsc::Sender, Arc, Mutex},
+};
 
+pub mod builder;
+pub mod harvest;
 pub mod result_processor;
+mod spec;
+pub mod tls;
 pub mod utils;
 
+use crate::{
+    dnsbuster::resolver::random_label,
+    error::Error,
+    matcher::collect_headers,
+    scan::{self, ScanHandle, ScanStream},
+    scheduler::{host_of, FairScheduler},
+    session::{self, auth::Credentials, HttpClient, RequestTemplate, SessionConfig, SessionPool},
+};
 use result_processor::SingleVhostScanResult;
+use tls::{certificate_key, CertificateInfo, SniConnector};
+use utils::{extract_title, similarity};
+
+type Certificates = Arc<Mutex<HashMap<String, CertificateInfo>>>;
 
 #[derive(Debug, Clone)]
 pub struct VhostConfig {
     pub n_threads: usize,
     pub ignore_certificate: bool,
-    pub ignore_strings: Vec<String>,
     pub user_agent: String,
     pub http_method: String,
+    pub http_body: String,
+    pub http_headers: Vec<(String, String)>,
     pub original_url: String,
+    pub domain: String,
+    pub similarity_threshold: f64,
+    pub sni: bool,
+    pub sources: HashMap<String, String>,
+    pub max_per_host: usize,
+    pub auth: Option<Credentials>,
+    pub session: Option<SessionConfig>,
 }
 
-fn make_request_future(
-    tx: Sender<SingleVhostScanResult>,
-    client: &Client<HttpsConnector<HttpConnector>>,
+#[derive(Debug, Clone)]
+pub struct VhostBaseline {
+    pub status: String,
+    pub length: usize,
+    pub title: Option<String>,
+    pub body: String,
+    pub certificate: Option<CertificateInfo>,
+}
+
+fn request_uri(host: &str, config: &VhostConfig) -> String {
+    let original = match config.original_url.parse::<Uri>() {
+        Ok(v) => v,
+        Err(_) => return config.original_url.clone(),
+    };
+    if !config.sni {
+        return config.original_url.clone();
+    }
+
+    let port = match original.port_part() {
+        Some(port) => format!(":{}", port),
+        None => String::new(),
+    };
+    let path = original.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+
+    format!(
+        "{}://{}{}{}",
+        original.scheme_str().unwrap_or("https"),
+        host,
+        port,
+        path
+    )
+}
+
+fn certificate_of(certificates: &Option<Certificates>, key: &str) -> Option<CertificateInfo> {
+    certificates
+        .as_ref()
+        .and_then(|certificates| certificates.lock().unwrap().get(key).cloned())
+}
+
+fn build_request(host: &str, config: &VhostConfig) -> Option<RequestTemplate> {
+    let mut headers = Vec::new();
+    let mut host_placed = false;
+
+    for (header, value) in &config.http_headers {
+        host_placed = host_placed || header.contains("FUZZ") || value.contains("FUZZ");
+        headers.push((header.replace("FUZZ", host), value.replace("FUZZ", host)));
+    }
+
+    if !host_placed {
+        headers.push(("Host".to_owned(), host.to_owned()));
+    }
+    headers.push(("User-Agent".to_owned(), config.user_agent.clone()));
+
+    let uri = request_uri(host, config);
+    match uri.parse::<Uri>() {
+        Ok(uri) => Some(RequestTemplate {
+            method: config.http_method.clone(),
+            uri,
+            headers,
+            body: config.http_body.replace("FUZZ", host),
+        }),
+        Err(e) => {
+            warn!("Unable to build the request to {}: {}", uri, e);
+            None
+        }
+    }
+}
+
+fn make_baseline_future<C: Connect + Clone + Sync + 'static>(
+    client: &HttpClient<C>,
+    sessions: Option<Arc<SessionPool>>,
+    config: &VhostConfig,
+    certificates: Option<Certificates>,
+) -> impl Future<Item = Option<VhostBaseline>, Error = Error> {
+    let host = format!("{}.{}", random_label(), config.domain);
+    let request = match build_request(&host, config) {
+        Some(v) => v,
+        None => return future::Either::A(future::ok(None)),
+    };
+    let key = certificate_key(&request.uri);
+    let exchange_client = client.clone();
+
+    future::Either::B(
+        session::send(client.clone(), sessions, move |session| {
+            session.exchange(&exchange_client, &request)
+        })
+        .map(move |response| {
+            let status = response.status.to_string();
+            let body = String::from_utf8_lossy(&response.body).replace(&host, "");
+            debug!("baseline {} - {} - {} bytes", host, status, body.len());
+            Some(VhostBaseline {
+                title: extract_title(&body),
+                length: body.len(),
+                body,
+                status,
+                certificate: certificate_of(&certificates, &key),
+            })
+        })
+        .or_else(|e| {
+            warn!("Unable to request the baseline vhost: {}", e);
+            Ok(None)
+        }),
+    )
+}
+
+fn make_request_future<C: Connect + Clone + Sync + 'static>(
+    tx: UnboundedSender<SingleVhostScanResult>,
+    client: &HttpClient<C>,
+    sessions: Option<Arc<SessionPool>>,
     url: Uri,
     config: &VhostConfig,
-) -> impl Future<Item = (), Error = ()> {
+    baseline: Option<Arc<VhostBaseline>>,
+    certificates: Option<Certificates>,
+) -> impl Future<Item = (), Error = Error> {
     let tx_err = tx.clone();
-    let target = Arc::new(Mutex::new(SingleVhostScanResult {
+    let host = url.host().unwrap_or_default().to_owned();
+    let target = SingleVhostScanResult {
+        target: config.original_url.clone(),
         vhost: url.to_string(),
         status: StatusCode::default().to_string(),
         error: None,
         method: config.http_method.clone(),
         ignored: false,
-    }));
-    let target_res = target.clone();
-    let mut target_err = (*target.lock().unwrap()).clone();
-    let mut request_builder = Request::builder();
-    let ignore_strings = config.ignore_strings.clone();
-    let request = request_builder
-        .header("User-Agent", &config.user_agent[..])
-        .method(&config.http_method[..])
-        .uri(&config.original_url)
-        .header("Host", url.host().unwrap())
-        .body(Body::from(""))
-        .expect("Request builder");
-
-    client
-        .request(request)
-        .and_then(move |res| {
-            target.lock().unwrap().status = res.status().to_string();
-            res.into_body().concat2()
+        length: None,
+        title: None,
+        length_delta: None,
+        similarity: None,
+        certificate: None,
+        source: config.sources.get(&host).cloned(),
+        time: None,
+        headers: Vec::new(),
+        body: String::new(),
+    };
+    let mut target_err = target.clone();
+    let similarity_threshold = config.similarity_threshold;
+    if host.is_empty() {
+        target_err.error = Some(
+            Error::InvalidUrl {
+                url: url.to_string(),
+                reason: "missing host".to_owned(),
+            }
+            .to_string(),
+        );
+        let _ = tx_err.unbounded_send(target_err);
+        return future::Either::A(future::ok(()));
+    }
+    let request = match build_request(&host, config) {
+        Some(v) => v,
+        None => {
+            target_err.error = Some("invalid request URL".to_owned());
+            let _ = tx_err.unbounded_send(target_err);
+            return future::Either::A(future::ok(()));
+        }
+    };
+    let key = certificate_key(&request.uri);
+    let exchange_client = client.clone();
+
+    future::Either::B(
+        session::send(client.clone(), sessions, move |session| {
+            session.exchange(&exchange_client, &request)
         })
-        .and_then(move |body| {
-            let vec = body.iter().cloned().collect();
-            let body = String::from_utf8(vec).unwrap();
-            target_res.lock().unwrap().ignored = false;
-            for s in ignore_strings {
-                if body.contains(&s) {
-                    target_res.lock().unwrap().ignored = true;
-                    break;
-                }
+        .and_then(move |response| {
+            let body = String::from_utf8_lossy(&response.body).into_owned();
+            let mut target = target;
+            target.status = response.status.to_string();
+            target.headers = collect_headers(&response.headers);
+            target.length = Some(body.len());
+            target.time = Some(response.time.as_millis() as u64);
+            target.title = extract_title(&body);
+            target.certificate = certificate_of(&certificates, &key);
+
+            if let Some(baseline) = baseline {
+                let normalized = body.replace(&host, "");
+                let similarity = similarity(&baseline.body, &normalized);
+                let length_delta = normalized.len() as i64 - baseline.length as i64;
+                let length_ratio = length_delta.abs() as f64 / baseline.length.max(1) as f64;
+                let differs = target.status != baseline.status
+                    || extract_title(&normalized) != baseline.title
+                    || length_ratio > 1.0 - similarity_threshold
+                    || similarity < similarity_threshold
+                    || target.certificate.as_ref().map(|c| &c.fingerprint)
+                        != baseline.certificate.as_ref().map(|c| &c.fingerprint);
+
+                target.ignored = !differs;
+                target.length_delta = Some(length_delta);
+                target.similarity = Some(similarity);
             }
 
-            let target = Arc::try_unwrap(target_res).unwrap().into_inner().unwrap();
-            tx.send(target).unwrap();
+            target.body = body;
+            let _ = tx.unbounded_send(target);
             Ok(())
         })
         .or_else(move |e| {
             target_err.error = Some(e.to_string());
-            tx_err.send(target_err).unwrap_or_else(|_| ());
+            let _ = tx_err.unbounded_send(target_err);
             Ok(())
+        }),
+    )
+}
+
+struct VhostTarget<C> {
+    config: VhostConfig,
+    client: HttpClient<C>,
+    certificates: Option<Certificates>,
+}
+
+fn make_scan_future<C: Connect + Clone + Sync + 'static>(
+    tx: UnboundedSender<SingleVhostScanResult>,
+    targets: Vec<(VhostTarget<C>, Vec<hyper::Uri>)>,
+    sessions: Option<Arc<SessionPool>>,
+    n_threads: usize,
+    max_per_host: usize,
+) -> impl Future<Item = (), Error = Error> {
+    let (targets, urls): (Vec<VhostTarget<C>>, Vec<Vec<hyper::Uri>>) = targets.into_iter().unzip();
+    let baselines = targets
+        .iter()
+        .map(|target| {
+            make_baseline_future(
+                &target.client,
+                sessions.clone(),
+                &target.config,
+                target.certificates.clone(),
+            )
         })
+        .collect::<Vec<_>>();
+
+    futures::future::join_all(baselines).and_then(move |baselines| {
+        let baselines: Vec<Option<Arc<VhostBaseline>>> =
+            baselines.into_iter().map(|b| b.map(Arc::new)).collect();
+        let jobs: Vec<(String, (usize, hyper::Uri))> = urls
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, urls)| {
+                let host = host_of(&targets[i].config.original_url);
+                urls.into_iter().map(move |url| (host.clone(), (i, url)))
+            })
+            .collect();
+
+        FairScheduler::new(
+            futures::stream::iter_ok(jobs),
+            move |(i, url)| {
+                let target = &targets[i];
+                make_request_future(
+                    tx.clone(),
+                    &target.client,
+                    sessions.clone(),
+                    url,
+                    &target.config,
+                    baselines[i].clone(),
+                    target.certificates.clone(),
+                )
+            },
+            n_threads,
+            max_per_host,
+        )
+        .for_each(Ok)
+    })
+}
+
+fn resolve_target(url: &str) -> Result<Vec<IpAddr>, String> {
+    let uri = url.parse::<Uri>().map_err(|e| e.to_string())?;
+    let host = uri.host().ok_or("missing host")?;
+    let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+        Some("http") => 80,
+        _ => 443,
+    });
+
+    (host.trim_matches(|c| c == '[' || c == ']'), port)
+        .to_socket_addrs()
+        .map(|addrs| addrs.map(|addr| addr.ip()).collect())
+        .map_err(|e| e.to_string())
 }
 
-pub fn run(tx: Sender<SingleVhostScanResult>, urls: Vec<hyper::Uri>, config: VhostConfig) {
+pub fn stream(
+    targets: Vec<(VhostConfig, Vec<hyper::Uri>)>,
+) -> Result<(ScanStream<SingleVhostScanResult>, ScanHandle), Error> {
+    let (tx, rx) = mpsc::unbounded();
+    let total = targets.iter().map(|(_, urls)| urls.len()).sum();
+    let (n_threads, max_per_host, ignore_certificate, sni, sessions) = match targets.first() {
+        Some((config, _)) => (
+            config.n_threads,
+            config.max_per_host,
+            config.ignore_certificate,
+            config.sni,
+            config
+                .session
+                .clone()
+                .map(|session| Arc::new(SessionPool::new(session))),
+        ),
+        None => return Ok(ScanStream::new(future::ok(()), rx, Some(total))),
+    };
     let mut tls_connector_builder = native_tls::TlsConnector::builder();
-    tls_connector_builder.danger_accept_invalid_certs(config.ignore_certificate);
-    let tls_connector = tls_connector_builder
-        .build()
-        .expect("TLS initialization failed");
-    let mut http_connector = HttpConnector::new(config.n_threads);
-    http_connector.enforce_http(false);
-    let https_connector = HttpsConnector::from((http_connector, tls_connector));
-    let client = Client::builder().build(https_connector);
-    let n_threads = config.n_threads;
-    debug!("{:?}", urls);
-
-    let stream = futures::stream::iter_ok(urls)
-        .map(move |url| make_request_future(tx.clone(), &client, url, &config))
-        .buffer_unordered(n_threads)
-        .for_each(Ok)
-        .map_err(|err| eprintln!("Err {:?}", err));
+    tls_connector_builder.danger_accept_invalid_certs(ignore_certificate);
+    if sni {
+        debug!("--sni disables TLS hostname verification to compare the certificates");
+        tls_connector_builder.danger_accept_invalid_hostnames(true);
+    }
+    let tls_connector = tls_connector_builder.build()?;
 
-    rt::run(stream);
+    if sni {
+        let targets = targets
+            .into_iter()
+            .filter_map(|(config, urls)| {
+                let addresses = match resolve_target(&config.original_url) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("Unable to resolve {}: {}", config.original_url, e);
+                        return None;
+                    }
+                };
+                let connector = SniConnector::new(addresses, tls_connector.clone());
+                let certificates = Some(connector.certificates.clone());
+                let client = HttpClient::new(connector).auth(config.auth.clone());
+                Some((
+                    VhostTarget {
+                        config,
+                        client,
+                        certificates,
+                    },
+                    urls,
+                ))
+            })
+            .collect();
+        let scan = make_scan_future(tx, targets, sessions, n_threads, max_per_host);
+        Ok(ScanStream::new(scan, rx, Some(total)))
+    } else {
+        let mut http_connector = HttpConnector::new(n_threads);
+        http_connector.enforce_http(false);
+        let https_connector = HttpsConnector::from((http_connector, tls_connector));
+        let client = HttpClient::new(https_connector);
+        let targets = targets
+            .into_iter()
+            .map(|(config, urls)| {
+                (
+                    VhostTarget {
+                        client: client.clone().auth(config.auth.clone()),
+                        config,
+                        certificates: None,
+                    },
+                    urls,
+                )
+            })
+            .collect();
+        let scan = make_scan_future(tx, targets, sessions, n_threads, max_per_host);
+        Ok(ScanStream::new(scan, rx, Some(total)))
+    }
+}
+
+pub fn run(
+    tx: Sender<SingleVhostScanResult>,
+    targets: Vec<(VhostConfig, Vec<hyper::Uri>)>,
+) -> Result<(), Error> {
+    scan::forward(stream(targets)?.0, tx)
 }
@@ -1,29 +1,73 @@
 use serde::{Deserialize, Serialize};
 
+use std::time::Duration;
+
+use super::tls::CertificateInfo;
+use crate::matcher::{find_header, Matchable, ResultFilter};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SingleVhostScanResult {
+    pub target: String,
     pub vhost: String,
     pub status: String,
     pub method: String,
     pub error: Option<String>,
     pub ignored: bool,
+    pub length: Option<usize>,
+    pub title: Option<String>,
+    pub length_delta: Option<i64>,
+    pub similarity: Option<f64>,
+    pub certificate: Option<CertificateInfo>,
+    pub source: Option<String>,
+    pub time: Option<u64>,
+    pub headers: Vec<(String, String)>,
+    #[serde(skip)]
+    pub body: String,
+}
+
+impl Matchable for SingleVhostScanResult {
+    fn status(&self) -> Option<&str> {
+        Some(&self.status)
+    }
+
+    fn length(&self) -> Option<usize> {
+        self.length
+    }
+
+    fn body(&self) -> Option<&str> {
+        Some(&self.body)
+    }
+
+    fn time(&self) -> Option<Duration> {
+        self.time.map(Duration::from_millis)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VhostScanResult {
     pub results: Vec<SingleVhostScanResult>,
+    filter: ResultFilter<SingleVhostScanResult>,
 }
 
 impl VhostScanResult {
-    pub fn new() -> Self {
+    pub fn new(filter: ResultFilter<SingleVhostScanResult>) -> Self {
         VhostScanResult {
             results: Vec::<SingleVhostScanResult>::new(),
+            filter,
         }
     }
 
-    pub fn maybe_add_result(&mut self, res: SingleVhostScanResult) -> bool {
+    pub fn maybe_add_result(&mut self, mut res: SingleVhostScanResult) -> bool {
         trace!("{:?}", res);
-        self.results.push(res);
-        true
+        if self.filter.is_match(&res) {
+            res.body = String::new();
+            self.results.push(res);
+            return true;
+        }
+
+        false
     }
 }
@@ -0,0 +1,122 @@
+use galvanic_test::test_suite;
+
+test_suite! {
+    name vhostbuster;
+
+    test extract_title() {
+        let extract_title = crate::vhostbuster::utils::extract_title;
+        assert_eq!(Some("Welcome to nginx!".to_owned()), extract_title("<html><TITLE>\n  Welcome to\n nginx!</TITLE>"));
+        assert_eq!(Some("".to_owned()), extract_title("<title lang=\"en\"></title>"));
+        assert_eq!(None, extract_title("<html><body>It works!</body></html>"));
+    }
+
+    test similarity() {
+        let similarity = crate::vhostbuster::utils::similarity;
+        assert_eq!(1.0, similarity("", ""));
+        assert_eq!(1.0, similarity("<p>It works!</p>", "<P>it WORKS</P>"));
+        assert_eq!(0.0, similarity("It works", "Admin panel"));
+        assert_eq!(0.5, similarity("a b c", "a b d"));
+    }
+
+    test build_request_fuzz_placement() {
+        let mut config = crate::vhostbuster::VhostConfig {
+            n_threads: 1,
+            ignore_certificate: false,
+            user_agent: "rustbuster".to_owned(),
+            http_method: "POST".to_owned(),
+            http_body: "host=FUZZ".to_owned(),
+            http_headers: vec![("Cookie".to_owned(), "a=1".to_owned())],
+            original_url: "http://127.0.0.1/".to_owned(),
+            domain: "test.local".to_owned(),
+            similarity_threshold: 0.9,
+            sni: false,
+            sources: std::collections::HashMap::new(),
+            max_per_host: 0,
+            auth: None,
+            session: None,
+        };
+
+        let request = crate::vhostbuster::build_request("admin.test.local", &config).unwrap().build(None);
+        assert_eq!("admin.test.local", request.headers()["Host"]);
+        assert_eq!("a=1", request.headers()["Cookie"]);
+        assert_eq!("POST", request.method());
+
+        config.http_headers.push(("X-Forwarded-Host".to_owned(), "FUZZ".to_owned()));
+        let request = crate::vhostbuster::build_request("admin.test.local", &config).unwrap().build(None);
+        assert!(request.headers().get("Host").is_none());
+        assert_eq!("admin.test.local", request.headers()["X-Forwarded-Host"]);
+    }
+
+    test request_uri_with_sni() {
+        let mut config = crate::vhostbuster::VhostConfig {
+            n_threads: 1,
+            ignore_certificate: false,
+            user_agent: "rustbuster".to_owned(),
+            http_method: "GET".to_owned(),
+            http_body: "".to_owned(),
+            http_headers: Vec::new(),
+            original_url: "https://10.0.0.1:8443/login?next=/".to_owned(),
+            domain: "test.local".to_owned(),
+            similarity_threshold: 0.9,
+            sni: false,
+            sources: std::collections::HashMap::new(),
+            max_per_host: 0,
+            auth: None,
+            session: None,
+        };
+
+        let request = crate::vhostbuster::build_request("admin.test.local", &config).unwrap().build(None);
+        assert_eq!("https://10.0.0.1:8443/login?next=/", request.uri().to_string());
+
+        config.sni = true;
+        let request = crate::vhostbuster::build_request("admin.test.local", &config).unwrap().build(None);
+        assert_eq!("https://admin.test.local:8443/login?next=/", request.uri().to_string());
+        assert_eq!("admin.test.local", request.headers()["Host"]);
+
+        config.original_url = "https://10.0.0.1".to_owned();
+        let request = crate::vhostbuster::build_request("admin.test.local", &config).unwrap().build(None);
+        assert_eq!("https://admin.test.local/", request.uri().to_string());
+    }
+
+    test parse_certificate() {
+        let der = data_encoding::BASE64.decode(b"MIIB1TCCAXygAwIBAgIUcxqJwOl2iWQPKz5ik7w2u5Ui5q4wCgYIKoZIzj0EAwIwJzENMAsGA1UECgwEQWNtZTEWMBQGA1UEAwwNd3d3LmFjbWUudGVzdDAeFw0yNjEwMTgxNjExMDBaFw0zNjEwMTUxNjExMDBaMCcxDTALBgNVBAoMBEFjbWUxFjAUBgNVBAMMDXd3dy5hY21lLnRlc3QwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAARVHd7XFiT9IHItug9XeUXGUhzLHEmlbAXCwnwK2B5RwBdLuJCIgfix09nJId2q4Ha46oHM4CTUKuwb6mJbnpsjo4GFMIGCMB0GA1UdDgQWBBSbo5L17lKZhgt4NqUL2zybzvn7NTAfBgNVHSMEGDAWgBSbo5L17lKZhgt4NqUL2zybzvn7NTAPBgNVHRMBAf8EBTADAQH/MC8GA1UdEQQoMCaCDXd3dy5hY21lLnRlc3SCD2FkbWluLmFjbWUudGVzdIcECgAAATAKBggqhkjOPQQDAgNHADBEAiBJ/fHfezSXAaxcoU/CE8dlBgLqSNQj2QivFJ0Jw0c0DwIgDmltefHevBGGCd5BQ7Ai/NEZy+mesy5UP2Of6Cp3W88=").unwrap();
+        let certificate = crate::vhostbuster::tls::parse_certificate(&der).unwrap();
+        assert_eq!("O=Acme, CN=www.acme.test", certificate.subject);
+        assert_eq!(vec!["www.acme.test", "admin.acme.test"], certificate.sans);
+        assert_eq!("A6:C2:5F:9D:C1:CC:73:A3:9F:33:34:D9:F4:FB:26:7B:32:A3:36:CC", certificate.fingerprint);
+        assert_eq!(None, crate::vhostbuster::tls::parse_certificate(&der[..64]));
+    }
+
+    test harvest_hosts() {
+        let harvest = crate::vhostbuster::harvest::csp_hosts;
+        assert_eq!(
+            vec!["cdn.acme.test", "static.acme.test", "evil.com"],
+            harvest("default-src 'self' https://cdn.acme.test:443/js/ *.static.acme.test; img-src data: evil.com")
+        );
+
+        let harvest = crate::vhostbuster::harvest::link_hosts;
+        assert_eq!(
+            vec!["blog.acme.test", "shop.acme.test"],
+            harvest("<a href=\"//blog.acme.test/x\"><form ACTION='https://shop.acme.test:8443/'><img src=\"/local.png\"><a href=\"http://10.0.0.1/\">")
+        );
+
+        let in_scope = crate::vhostbuster::harvest::in_scope;
+        assert!(in_scope("acme.test", "acme.test"));
+        assert!(in_scope("blog.acme.test", "acme.test."));
+        assert!(!in_scope("notacme.test", "acme.test"));
+    }
+
+    test build_scan() {
+        use crate::{error::Error, vhostbuster::builder::VhostScan};
+
+        let scan = VhostScan::builder()
+            .target("http://10.0.0.1/")
+            .domain("acme.test")
+            .wordlist("./examples/wordlist_short")
+            .build();
+        assert_eq!(2, scan.unwrap().total());
+
+        let scan = VhostScan::builder().target("http://10.0.0.1/").build();
+        assert!(matches!(scan, Err(Error::MissingOption("wordlist"))));
+    }
+}
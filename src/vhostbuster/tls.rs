@@ -0,0 +1,273 @@
+use futures::{future, Future};
+use hyper::client::{
+    connect::{
+        dns::{GaiResolver, Name, Resolve},
+        Connect, Connected, Destination,
+    },
+    HttpConnector,
+};
+use hyper::Uri;
+use hyper_tls::{HttpsConnector, MaybeHttpsStream};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+
+use std::{
+    collections::HashMap,
+    io,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    vec,
+};
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_EXTENSIONS: u8 = 0xa3;
+const TAG_DNS_NAME: u8 = 0x82;
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub sans: Vec<String>,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FixedResolver {
+    addresses: Vec<IpAddr>,
+}
+
+#[derive(Clone)]
+pub struct SniConnector<R = FixedResolver> {
+    inner: HttpsConnector<HttpConnector<R>>,
+    pub certificates: Arc<Mutex<HashMap<String, CertificateInfo>>>,
+}
+
+impl FixedResolver {
+    pub fn new(addresses: Vec<IpAddr>) -> Self {
+        FixedResolver { addresses }
+    }
+}
+
+impl Resolve for FixedResolver {
+    type Addrs = vec::IntoIter<IpAddr>;
+    type Future = future::FutureResult<Self::Addrs, io::Error>;
+
+    fn resolve(&self, name: Name) -> Self::Future {
+        trace!("resolving {} to {:?}", name.as_str(), self.addresses);
+        future::ok(self.addresses.clone().into_iter())
+    }
+}
+
+fn port_or_default(port: Option<u16>, scheme: Option<&str>) -> u16 {
+    port.unwrap_or(match scheme {
+        Some("http") => 80,
+        _ => 443,
+    })
+}
+
+pub fn certificate_key(uri: &Uri) -> String {
+    format!(
+        "{}:{}",
+        uri.host().unwrap_or_default(),
+        port_or_default(uri.port_u16(), uri.scheme_str())
+    )
+}
+
+impl SniConnector<FixedResolver> {
+    pub fn new(addresses: Vec<IpAddr>, tls_connector: native_tls::TlsConnector) -> Self {
+        let http_connector = HttpConnector::new_with_resolver(FixedResolver::new(addresses));
+        SniConnector::from_http(http_connector, tls_connector)
+    }
+}
+
+impl SniConnector<GaiResolver> {
+    pub fn with_system_resolver(n_threads: usize, tls_connector: native_tls::TlsConnector) -> Self {
+        SniConnector::from_http(HttpConnector::new(n_threads), tls_connector)
+    }
+}
+
+impl<R> SniConnector<R> {
+    fn from_http(
+        mut http_connector: HttpConnector<R>,
+        tls_connector: native_tls::TlsConnector,
+    ) -> Self {
+        http_connector.enforce_http(false);
+
+        SniConnector {
+            inner: HttpsConnector::from((http_connector, tls_connector)),
+            certificates: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<R> Connect for SniConnector<R>
+where
+    R: Resolve + Clone + Send + Sync + 'static,
+    R::Future: Send,
+{
+    type Transport = MaybeHttpsStream<TcpStream>;
+    type Error = io::Error;
+    type Future = Box<dyn Future<Item = (Self::Transport, Connected), Error = io::Error> + Send>;
+
+    fn connect(&self, dst: Destination) -> Self::Future {
+        let key = format!(
+            "{}:{}",
+            dst.host(),
+            port_or_default(dst.port(), Some(dst.scheme()))
+        );
+        let certificates = self.certificates.clone();
+
+        Box::new(self.inner.connect(dst).map(move |(stream, connected)| {
+            if let MaybeHttpsStream::Https(tls) = &stream {
+                let certificate = tls
+                    .get_ref()
+                    .peer_certificate()
+                    .ok()
+                    .and_then(|certificate| certificate)
+                    .and_then(|certificate| certificate.to_der().ok())
+                    .and_then(|der| parse_certificate(&der));
+                if let Some(certificate) = certificate {
+                    certificates.lock().unwrap().insert(key, certificate);
+                }
+            }
+
+            (stream, connected)
+        }))
+    }
+}
+
+fn read_tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first = *data.get(1)? as usize;
+    let (length, offset) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let n = first & 0x7f;
+        if n == 0 || n > 4 {
+            return None;
+        }
+        let length = data
+            .get(2..2 + n)?
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (length, 2 + n)
+    };
+    let value = data.get(offset..offset + length)?;
+
+    Some((tag, value, &data[offset + length..]))
+}
+
+fn read_all(mut data: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut items = Vec::new();
+    while let Some((tag, value, rest)) = read_tlv(data) {
+        items.push((tag, value));
+        data = rest;
+    }
+
+    items
+}
+
+fn attribute_name(oid: &[u8]) -> String {
+    match oid {
+        [0x55, 0x04, 0x03] => "CN".to_owned(),
+        [0x55, 0x04, 0x06] => "C".to_owned(),
+        [0x55, 0x04, 0x07] => "L".to_owned(),
+        [0x55, 0x04, 0x08] => "ST".to_owned(),
+        [0x55, 0x04, 0x0a] => "O".to_owned(),
+        [0x55, 0x04, 0x0b] => "OU".to_owned(),
+        _ => oid
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<String>>()
+            .join("."),
+    }
+}
+
+fn parse_name(name: &[u8]) -> String {
+    read_all(name)
+        .into_iter()
+        .filter(|(tag, _)| *tag == TAG_SET)
+        .flat_map(|(_, set)| read_all(set))
+        .filter_map(|(_, attribute)| match read_all(attribute).as_slice() {
+            [(TAG_OID, oid), (_, value)] => Some(format!(
+                "{}={}",
+                attribute_name(oid),
+                String::from_utf8_lossy(value)
+            )),
+            _ => None,
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn parse_sans(extensions: &[u8]) -> Vec<String> {
+    let extensions = match read_tlv(extensions) {
+        Some((TAG_SEQUENCE, v, _)) => v,
+        _ => return Vec::new(),
+    };
+
+    read_all(extensions)
+        .into_iter()
+        .filter_map(|(_, extension)| {
+            let fields = read_all(extension);
+            match (fields.first(), fields.last()) {
+                (Some((TAG_OID, OID_SUBJECT_ALT_NAME)), Some((TAG_OCTET_STRING, value))) => {
+                    Some(value.to_vec())
+                }
+                _ => None,
+            }
+        })
+        .flat_map(|value| match read_tlv(&value) {
+            Some((TAG_SEQUENCE, names, _)) => read_all(names)
+                .into_iter()
+                .filter(|(tag, _)| *tag == TAG_DNS_NAME)
+                .map(|(_, name)| String::from_utf8_lossy(name).into_owned())
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+pub fn parse_certificate(der: &[u8]) -> Option<CertificateInfo> {
+    let certificate = match read_tlv(der)? {
+        (TAG_SEQUENCE, v, _) => v,
+        _ => return None,
+    };
+    let tbs = match read_tlv(certificate)? {
+        (TAG_SEQUENCE, v, _) => v,
+        _ => return None,
+    };
+
+    let mut fields = read_all(tbs);
+    if let Some((0xa0, _)) = fields.first() {
+        fields.remove(0);
+    }
+    let subject = match fields.get(4) {
+        Some((TAG_SEQUENCE, name)) => parse_name(name),
+        _ => return None,
+    };
+    let sans = fields
+        .iter()
+        .find(|(tag, _)| *tag == TAG_EXTENSIONS)
+        .map(|(_, extensions)| parse_sans(extensions))
+        .unwrap_or_default();
+
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(der);
+    let fingerprint = hasher
+        .digest()
+        .bytes()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<String>>()
+        .join(":");
+
+    Some(CertificateInfo {
+        subject,
+        sans,
+        fingerprint,
+    })
+}
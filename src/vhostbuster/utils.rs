@@ -1,12 +1,14 @@
-use std::{fs, fs::File, io::Write, path::Path, str};
+use regex::Regex;
+
+use std::{collections::HashSet, fs::File, io::Write, path::Path, str};
 
 use super::result_processor::SingleVhostScanResult;
+use crate::error::{read_file, Error};
 
-pub fn build_vhosts(wordlist_path: &str, url: &str) -> Vec<hyper::Uri> {
+pub fn build_vhosts(wordlist_path: &str, url: &str) -> Result<Vec<hyper::Uri>, Error> {
     debug!("building urls");
     let mut urls: Vec<hyper::Uri> = Vec::new();
-    let wordlist =
-        fs::read_to_string(wordlist_path).expect("Something went wrong reading the wordlist file");
+    let wordlist = read_file(wordlist_path)?;
     let urls_iter = wordlist
         .lines()
         .filter(|word| !word.starts_with('#') && !word.starts_with(' '))
@@ -23,7 +25,35 @@ pub fn build_vhosts(wordlist_path: &str, url: &str) -> Vec<hyper::Uri> {
         }
     }
 
-    urls
+    Ok(urls)
+}
+
+lazy_static! {
+    static ref TITLE: Regex = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+}
+
+pub fn extract_title(body: &str) -> Option<String> {
+    TITLE.captures(body).map(|captures| {
+        captures[1]
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+    })
+}
+
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let tokens = |s: &str| -> HashSet<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(|token| token.to_lowercase())
+            .collect()
+    };
+    let (a, b) = (tokens(a), tokens(b));
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
 }
 
 pub fn save_vhost_results(path: &str, results: &Vec<SingleVhostScanResult>) {
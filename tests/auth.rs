@@ -0,0 +1,162 @@
+use galvanic_test::test_suite;
+
+mod common;
+
+test_suite! {
+    name auth;
+
+    use data_encoding::BASE64;
+    use crate::common::{self, header, respond};
+    use hyper::{client::HttpConnector, header::{AUTHORIZATION, WWW_AUTHENTICATE}, Body, Request, Response, StatusCode};
+    use librustbuster::session::{auth::*, HttpClient, RequestTemplate};
+    use std::{net::SocketAddr, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Arc}};
+    use tokio::runtime::Runtime;
+
+    const SERVER_CHALLENGE: [u8; 8] = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+    const NONCE: &str = "dcd98b7102dd2f0e8b11d0f600bfb0c093";
+
+    #[derive(Default)]
+    struct Counters {
+        digest_challenges: AtomicUsize,
+        ntlm_handshakes: AtomicUsize,
+    }
+
+    fn challenge(status: StatusCode, challenge: Option<String>) -> Response<Body> {
+        match challenge {
+            Some(challenge) => respond(status, &[(WWW_AUTHENTICATE, &challenge)], status.as_str()),
+            None => respond(status, &[], status.as_str()),
+        }
+    }
+
+    fn check_digest(request: &Request<String>) -> bool {
+        let authorization = header(request, AUTHORIZATION);
+        let params = match authorization.strip_prefix("Digest ") {
+            Some(params) => auth_params(params),
+            None => return false,
+        };
+        let param = |name: &str| params.iter().find(|(key, _)| key == name).map(|(_, value)| value.to_owned()).unwrap_or_default();
+        let challenge = DigestChallenge::parse(&format!("Digest realm=\"intranet\", qop=\"auth\", nonce=\"{}\"", NONCE)).unwrap();
+        let nc = u32::from_str_radix(&param("nc"), 16).unwrap_or_default();
+
+        param("nonce") == NONCE && param("uri") == request.uri().to_string()
+            && param("response") == challenge.response(&param("username"), "secret", request.method().as_str(), &param("uri"), &param("cnonce"), nc)
+    }
+
+    fn ntlm_challenge() -> Vec<u8> {
+        let target_info = [2, 0, 8, 0, 67, 0, 79, 0, 82, 0, 80, 0, 0, 0, 0, 0];
+        let mut message = b"NTLMSSP\0".to_vec();
+        message.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0, 48, 0, 0, 0]);
+        message.extend_from_slice(&0xa288_8205u32.to_le_bytes());
+        message.extend_from_slice(&SERVER_CHALLENGE);
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&[16, 0, 16, 0, 48, 0, 0, 0]);
+        message.extend_from_slice(&target_info);
+        message
+    }
+
+    fn check_ntlm(message: &[u8]) -> bool {
+        let field = |offset: usize| {
+            let length = u16::from_le_bytes([message[offset], message[offset + 1]]) as usize;
+            let start = u32::from_le_bytes([message[offset + 4], message[offset + 5], message[offset + 6], message[offset + 7]]) as usize;
+            &message[start..start + length]
+        };
+        let nt_response = field(20);
+        let blob = &nt_response[16..];
+        let mut timestamp = [0; 8];
+        timestamp.copy_from_slice(&blob[8..16]);
+        let hash = ntlmv2_hash(&nt_hash("secret"), "alice", "CORP");
+        let (_, expected) = ntlmv2_response(&hash, &SERVER_CHALLENGE, &blob[16..24], u64::from_le_bytes(timestamp), &blob[28..blob.len() - 4]);
+
+        expected == nt_response
+    }
+
+    fn serve(runtime: &mut Runtime, counters: Arc<Counters>) -> SocketAddr {
+        common::serve(runtime, move || {
+            let counters = counters.clone();
+            let authenticated = AtomicBool::new(false);
+            move |request: Request<String>| {
+                let authorization = header(&request, AUTHORIZATION);
+                match request.uri().path() {
+                    "/basic" if authorization == "Basic YWRtaW46c2VjcmV0" => challenge(StatusCode::OK, None),
+                    "/basic" => challenge(StatusCode::UNAUTHORIZED, Some("Basic realm=\"intranet\"".to_owned())),
+                    "/bearer" if authorization == "Bearer t0ken" => challenge(StatusCode::OK, None),
+                    "/bearer" => challenge(StatusCode::UNAUTHORIZED, Some("Bearer".to_owned())),
+                    "/digest" if check_digest(&request) => challenge(StatusCode::OK, None),
+                    "/digest" => {
+                        counters.digest_challenges.fetch_add(1, Ordering::SeqCst);
+                        challenge(StatusCode::UNAUTHORIZED, Some(format!("Digest realm=\"intranet\", qop=\"auth\", nonce=\"{}\", opaque=\"abc\"", NONCE)))
+                    }
+                    _ if authenticated.load(Ordering::SeqCst) => challenge(StatusCode::OK, None),
+                    _ => {
+                        let message = authorization.strip_prefix("NTLM ").and_then(|token| BASE64.decode(token.as_bytes()).ok()).unwrap_or_default();
+                        match message.get(8) {
+                            Some(1) => {
+                                counters.ntlm_handshakes.fetch_add(1, Ordering::SeqCst);
+                                challenge(StatusCode::UNAUTHORIZED, Some(format!("NTLM {}", BASE64.encode(&ntlm_challenge()))))
+                            }
+                            Some(3) if check_ntlm(&message) => {
+                                authenticated.store(true, Ordering::SeqCst);
+                                challenge(StatusCode::OK, None)
+                            }
+                            _ => challenge(StatusCode::UNAUTHORIZED, Some("NTLM".to_owned())),
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn get(runtime: &mut Runtime, client: &HttpClient<HttpConnector>, address: SocketAddr, path: &str) -> StatusCode {
+        let request = RequestTemplate {
+            method: "GET".to_owned(),
+            uri: format!("http://{}{}", address, path).parse().unwrap(),
+            headers: Vec::new(),
+            body: String::new(),
+        };
+        runtime.block_on(client.send(&request, None)).unwrap().status
+    }
+
+    fn client(auth_type: AuthType, credentials: &str) -> HttpClient<HttpConnector> {
+        HttpClient::new(HttpConnector::new(1)).auth(Some(Credentials::new(auth_type, credentials).unwrap()))
+    }
+
+    test basic_and_bearer() {
+        let mut runtime = Runtime::new().unwrap();
+        let address = serve(&mut runtime, Arc::new(Counters::default()));
+
+        assert_eq!(StatusCode::UNAUTHORIZED, get(&mut runtime, &HttpClient::new(HttpConnector::new(1)), address, "/basic"));
+        assert_eq!(StatusCode::OK, get(&mut runtime, &client(AuthType::Basic, "admin:secret"), address, "/basic"));
+        assert_eq!(StatusCode::UNAUTHORIZED, get(&mut runtime, &client(AuthType::Basic, "admin:wrong"), address, "/basic"));
+        assert_eq!(StatusCode::OK, get(&mut runtime, &client(AuthType::Bearer, "t0ken"), address, "/bearer"));
+    }
+
+    test digest_challenge() {
+        let mut runtime = Runtime::new().unwrap();
+        let counters = Arc::new(Counters::default());
+        let address = serve(&mut runtime, counters.clone());
+        let client = client(AuthType::Digest, "admin:secret");
+
+        assert_eq!(StatusCode::OK, get(&mut runtime, &client, address, "/digest"));
+        assert_eq!(StatusCode::OK, get(&mut runtime, &client, address, "/digest?page=2"));
+        assert_eq!(1, counters.digest_challenges.load(Ordering::SeqCst));
+
+        let wrong = self::client(AuthType::Digest, "admin:wrong");
+        assert_eq!(StatusCode::UNAUTHORIZED, get(&mut runtime, &wrong, address, "/digest"));
+    }
+
+    test ntlm_handshake() {
+        let mut runtime = Runtime::new().unwrap();
+        let counters = Arc::new(Counters::default());
+        let address = serve(&mut runtime, counters.clone());
+        let client = client(AuthType::Ntlm, "CORP\\alice:secret");
+
+        assert_eq!(StatusCode::UNAUTHORIZED, get(&mut runtime, &HttpClient::new(HttpConnector::new(1)), address, "/ntlm"));
+        assert_eq!(StatusCode::OK, get(&mut runtime, &client, address, "/ntlm"));
+        assert_eq!(StatusCode::OK, get(&mut runtime, &client, address, "/ntlm/other"));
+        assert_eq!(1, counters.ntlm_handshakes.load(Ordering::SeqCst));
+
+        let wrong = self::client(AuthType::Ntlm, "CORP\\alice:wrong");
+        assert_eq!(StatusCode::UNAUTHORIZED, get(&mut runtime, &wrong, address, "/ntlm"));
+        assert_eq!(2, counters.ntlm_handshakes.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,50 @@
+use futures::{future, Future, Stream};
+use hyper::{
+    header::HeaderName,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::runtime::Runtime;
+
+pub fn respond(status: StatusCode, headers: &[(HeaderName, &str)], body: &str) -> Response<Body> {
+    let mut response = Response::builder();
+    response.status(status);
+    for (name, value) in headers {
+        response.header(name, *value);
+    }
+    response.body(Body::from(body.to_owned())).unwrap()
+}
+
+pub fn header(request: &Request<String>, name: HeaderName) -> String {
+    request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+pub fn serve<M, F>(runtime: &mut Runtime, connection: M) -> SocketAddr
+where
+    M: Fn() -> F + Send + Sync + 'static,
+    F: Fn(Request<String>) -> Response<Body> + Send + Sync + 'static,
+{
+    let service = make_service_fn(move |_| {
+        let handle = Arc::new(connection());
+        future::ok::<_, hyper::Error>(service_fn(move |request: Request<Body>| {
+            let handle = handle.clone();
+            let (parts, body) = request.into_parts();
+            body.concat2().map(move |body| {
+                handle(Request::from_parts(
+                    parts,
+                    String::from_utf8_lossy(&body).into_owned(),
+                ))
+            })
+        }))
+    });
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(service);
+    let address = server.local_addr();
+    runtime.spawn(server.map_err(|e| panic!("server error: {}", e)));
+    address
+}
@@ -0,0 +1,110 @@
+use galvanic_test::test_suite;
+
+mod common;
+
+test_suite! {
+    name login;
+
+    use crate::common::{self, header, respond};
+    use hyper::{header::{AUTHORIZATION, LOCATION, WWW_AUTHENTICATE}, Body, Request, Response, StatusCode};
+    use librustbuster::{loginbuster::{builder::LoginScan, result_processor::SingleLoginScanResult, LoginType}, matcher::expr::Expression};
+    use std::{net::SocketAddr, sync::{atomic::{AtomicUsize, Ordering}, mpsc::channel, Arc}, thread, time::{Duration, Instant}};
+    use tokio::runtime::Runtime;
+
+    #[derive(Default)]
+    struct Counters {
+        admin_attempts: AtomicUsize,
+        lockouts: AtomicUsize,
+    }
+
+    fn login(counters: &Counters, request: &Request<String>) -> Response<Body> {
+        let body = request.body();
+        let user = body.split('&').find(|p| p.starts_with("username=")).unwrap_or("username=").trim_start_matches("username=");
+        if user == "admin" {
+            counters.admin_attempts.fetch_add(1, Ordering::SeqCst);
+        }
+        match request.uri().path() {
+            "/basic" if header(request, AUTHORIZATION) == "Basic YWRtaW46c2VjcmV0" => respond(StatusCode::OK, &[], "Welcome"),
+            "/basic" => respond(StatusCode::UNAUTHORIZED, &[(WWW_AUTHENTICATE, "Basic realm=\"intranet\"")], "Unauthorized"),
+            "/locked" if counters.lockouts.fetch_add(1, Ordering::SeqCst) == 0 => respond(StatusCode::TOO_MANY_REQUESTS, &[], "Slow down"),
+            _ if body == "username=admin&password=secret" => respond(StatusCode::FOUND, &[(LOCATION, "/home")], ""),
+            _ => respond(StatusCode::OK, &[], &format!("<form>Invalid credentials for {}</form>", user)),
+        }
+    }
+
+    fn serve(runtime: &mut Runtime, counters: Arc<Counters>) -> SocketAddr {
+        common::serve(runtime, move || {
+            let counters = counters.clone();
+            move |request: Request<String>| login(&counters, &request)
+        })
+    }
+
+    fn run(scan: LoginScan) -> Vec<SingleLoginScanResult> {
+        let (tx, rx) = channel();
+        thread::spawn(move || scan.run(tx).unwrap());
+        rx.iter().collect()
+    }
+
+    fn found(results: &[SingleLoginScanResult]) -> Vec<String> {
+        results.iter().filter(|r| r.success).map(|r| format!("{}:{}", r.username, r.password)).collect()
+    }
+
+    test form_baseline_and_stop_per_user() {
+        let mut runtime = Runtime::new().unwrap();
+        let counters = Arc::new(Counters::default());
+        let address = serve(&mut runtime, counters.clone());
+        let scan = LoginScan::builder()
+            .target(&format!("http://{}/form", address))
+            .threads(1)
+            .username("admin")
+            .username("guest")
+            .password("wrong")
+            .password("secret")
+            .password("other")
+            .build()
+            .unwrap();
+
+        let results = run(scan);
+        assert_eq!(vec!["admin:secret"], found(&results));
+        assert_eq!(5, results.len());
+        assert_eq!(2, counters.admin_attempts.load(Ordering::SeqCst));
+        assert!(results.iter().all(|r| r.error.is_none() && !r.locked));
+    }
+
+    test lockout_pause() {
+        let mut runtime = Runtime::new().unwrap();
+        let counters = Arc::new(Counters::default());
+        let address = serve(&mut runtime, counters.clone());
+        let scan = LoginScan::builder()
+            .target(&format!("http://{}/locked", address))
+            .threads(1)
+            .combo("admin", "secret")
+            .success(Expression::parse("status == 302").unwrap())
+            .lockout_pause(Duration::from_millis(500))
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        let results = run(scan);
+        assert!(start.elapsed() >= Duration::from_millis(500));
+        assert_eq!(vec!["admin:secret"], found(&results));
+        assert_eq!(2, counters.lockouts.load(Ordering::SeqCst));
+    }
+
+    test basic_login_type() {
+        let mut runtime = Runtime::new().unwrap();
+        let address = serve(&mut runtime, Arc::new(Counters::default()));
+        let scan = LoginScan::builder()
+            .target(&format!("http://{}/basic", address))
+            .login_type(LoginType::Basic)
+            .combo("admin", "wrong")
+            .combo("admin", "secret")
+            .combo("root", "secret")
+            .build()
+            .unwrap();
+
+        let results = run(scan);
+        assert_eq!(vec!["admin:secret"], found(&results));
+        assert!(results.iter().all(|r| r.method == "GET"));
+    }
+}
@@ -0,0 +1,79 @@
+use galvanic_test::test_suite;
+
+mod common;
+
+test_suite! {
+    name session;
+
+    use crate::common::{self, header, respond};
+    use futures::future;
+    use hyper::{client::HttpConnector, header::{COOKIE, SET_COOKIE}, Request, StatusCode};
+    use librustbuster::{matcher::expr::Expression, session::{self, HttpClient, JarScope, RequestTemplate, SessionConfig, SessionPool}};
+    use std::{net::SocketAddr, sync::{atomic::{AtomicUsize, Ordering}, Arc}};
+    use tokio::runtime::Runtime;
+
+    fn serve(runtime: &mut Runtime, logins: Arc<AtomicUsize>) -> SocketAddr {
+        common::serve(runtime, move || {
+            let logins = logins.clone();
+            move |request: Request<String>| {
+                let cookie = header(&request, COOKIE);
+                match request.uri().path() {
+                    "/login" => {
+                        let login = logins.fetch_add(1, Ordering::SeqCst) + 1;
+                        respond(StatusCode::OK, &[(SET_COOKIE, &format!("sid={}; Path=/", login))], "OK")
+                    }
+                    _ if cookie.is_empty() || cookie == "sid=1" => respond(StatusCode::UNAUTHORIZED, &[], "Unauthorized"),
+                    _ => respond(StatusCode::OK, &[], "OK"),
+                }
+            }
+        })
+    }
+
+    fn template(address: SocketAddr, path: &str) -> RequestTemplate {
+        RequestTemplate {
+            method: "GET".to_owned(),
+            uri: format!("http://{}{}", address, path).parse().unwrap(),
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    test global_login_once_per_expiry() {
+        let mut runtime = Runtime::new().unwrap();
+        let logins = Arc::new(AtomicUsize::new(0));
+        let address = serve(&mut runtime, logins.clone());
+        let mut config = SessionConfig::new(JarScope::Global);
+        config.login = Some(template(address, "/login"));
+        config.expired = Some(Expression::parse("status == 401").unwrap());
+        let pool = Arc::new(SessionPool::new(config));
+        let client = HttpClient::https(1, false).unwrap();
+
+        let requests: Vec<_> = (0..10).map(|_| {
+            let attempt_client = client.clone();
+            let page = template(address, "/page");
+            session::send(client.clone(), Some(pool.clone()), move |session| attempt_client.send(&page, session.jar()))
+        }).collect();
+        let responses = runtime.block_on(future::join_all(requests)).unwrap();
+
+        assert!(responses.iter().all(|response| response.status == StatusCode::OK));
+        assert_eq!(2, logins.load(Ordering::SeqCst));
+    }
+
+    test worker_login_with_any_connector() {
+        let mut runtime = Runtime::new().unwrap();
+        let logins = Arc::new(AtomicUsize::new(0));
+        let address = serve(&mut runtime, logins.clone());
+        let mut config = SessionConfig::new(JarScope::Worker);
+        config.login = Some(template(address, "/login"));
+        config.expired = Some(Expression::parse("status == 401").unwrap());
+        let pool = Arc::new(SessionPool::new(config));
+        let client = HttpClient::new(HttpConnector::new(1));
+
+        let attempt_client = client.clone();
+        let page = template(address, "/page");
+        let response = runtime.block_on(session::send(client, Some(pool), move |session| session.exchange(&attempt_client, &page))).unwrap();
+
+        assert_eq!(StatusCode::OK, response.status);
+        assert_eq!(2, logins.load(Ordering::SeqCst));
+    }
+}